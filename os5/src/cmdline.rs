@@ -0,0 +1,45 @@
+//! The kernel command line, options separated by spaces that are given at
+//! build time in `CMDLINE`, e.g. `make run CMDLINE="sched=rr log=info"`.
+//! Cargo rebuilds the kernel when it changes.
+//!
+//! - `sched=stride|rr`: the scheduler policy, stride by default
+//! - `log=off|error|warn|info|debug|trace`: the log level, `LOG` by default
+
+use crate::logging;
+use crate::task::{set_sched_policy, SchedPolicy};
+
+/// Apply the options of the command line, warn about those it does not know
+pub fn init() {
+    // the Makefile passes an empty one if none is given
+    let line = match option_env!("CMDLINE") {
+        Some(line) if !line.trim().is_empty() => line,
+        _ => return,
+    };
+    println!("[kernel] command line: {}", line);
+    for option in line.split_whitespace() {
+        if !apply(option) {
+            warn!("[kernel] unknown command line option {}", option);
+        }
+    }
+}
+
+/// Apply `option`, return false if it is not a known `key=value`
+fn apply(option: &str) -> bool {
+    let (key, value) = match option.split_once('=') {
+        Some(pair) => pair,
+        None => return false,
+    };
+    match key {
+        "sched" => {
+            let policy = match value {
+                "stride" => SchedPolicy::Stride,
+                "rr" => SchedPolicy::RoundRobin,
+                _ => return false,
+            };
+            set_sched_policy(policy);
+            true
+        }
+        "log" => logging::set_level(value),
+        _ => false,
+    }
+}
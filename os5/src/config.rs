@@ -6,11 +6,67 @@ pub const KERNEL_HEAP_SIZE: usize = 0x20_0000;
 pub const MEMORY_END: usize = 0x88000000;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
+/// a megapage, mapped by a leaf pte in the middle level of Sv39
+pub const HUGE_PAGE_SIZE: usize = 0x20_0000;
 pub const MAX_SYSCALL_NUM: usize = 500;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+/// end of the lower half of Sv39, no user mapping reaches past it
+pub const USER_SPACE_END: usize = 0x40_0000_0000;
+/// user stacks grow down from the top of the lower half of Sv39,
+/// leaving the space above the elf to the heap
+pub const USER_STACK_TOP: usize = USER_SPACE_END - PAGE_SIZE;
+/// a fault at most this many pages below the user stack grows the stack
+pub const USER_STACK_GROW_PAGES: usize = 64;
+/// the user stack grows to at most this size, the page below is its guard page
+pub const USER_STACK_MAX_SIZE: usize = 4096 * 256;
 pub const CLOCK_FREQ: usize = 12500000;
+/// at most this many tasks hold a pid, counting those not reaped yet
+pub const MAX_TASKS: usize = 128;
 
 pub const BIG_STRIDE: u64 = 0x1111_1111;
+/// priority of every new task, forked or spawned
 pub const PRIORITY: usize = 16;
+/// sys_set_priority rejects anything above
+pub const MAX_PRIORITY: usize = 1024;
+/// timer ticks a task of the default priority runs before it is preempted,
+/// the slice grows with the priority up to MAX_TIME_SLICE
+pub const BASE_TIME_SLICE: usize = 1;
+pub const MAX_TIME_SLICE: usize = 8;
+/// every this many scheduling rounds the ready tasks that have not run for
+/// AGING_THRESHOLD rounds catch up with the stride of the next one to run
+pub const AGING_INTERVAL: usize = 16;
+pub const AGING_THRESHOLD: usize = 128;
+/// whether the scheduler boots without timer preemption, so tasks switch
+/// only when they yield, block or exit; `sys_sched_deterministic` turns it
+/// on and off later
+pub const DETERMINISTIC_SCHED: bool = false;
+/// a munmap or mprotect of more pages than this flushes the whole TLB
+/// instead of the pages one by one
+pub const TLB_FLUSH_PAGES: usize = 64;
+/// a reader of an empty stdin sleeps this long before polling the console again
+pub const STDIN_POLL_MS: usize = 10;
+/// task names are cut to this many bytes
+pub const TASK_NAME_LEN: usize = 16;
+/// longest path or name read from user space, the NUL excluded
+pub const PATH_MAX: usize = 255;
+/// whether mmap, munmap and the like of a new address space reject a len
+/// that is not a whole number of pages rather than rounding it up;
+/// `sys_mmap_strict` changes it for the current one
+pub const MMAP_STRICT_LEN: bool = false;
+/// frames at the end of memory kept from the frame allocator as the swap
+/// area for the pages of lazy areas, 0 turns swapping off. There is no block
+/// device, so they are taken from the frames there would be otherwise.
+pub const SWAP_PAGES: usize = 1024;
+/// the pages of an address space faulted in last are never swapped out, so
+/// a task does not lose the page it faulted in before it runs again
+pub const SWAP_RECENT_PAGES: usize = 16;
+/// a task can have at most this many fds open, stdin, stdout and stderr
+/// included
+pub const MAX_FD: usize = 64;
+/// harts the kernel schedules tasks on, numbered from 0. Only hart 0 runs
+/// for now, the cpu affinity masks of the tasks are ready for more.
+pub const HART_COUNT: usize = 1;
+/// the cpu affinity of a new task, every hart
+pub const ALL_HARTS: usize = (1 << HART_COUNT) - 1;
@@ -1,14 +1,59 @@
 //! SBI console driver, for text output
+//!
+//! Output is line buffered, [`console_flush()`] emits a partial line.
 
 use crate::sbi::console_putchar;
+use crate::sync::UPSafeCell;
 use core::fmt::{self, Write};
+use lazy_static::*;
+
+const CONSOLE_BUFFER_SIZE: usize = 256;
+
+/// Bytes printed since the last newline
+struct ConsoleBuffer {
+    buf: [u8; CONSOLE_BUFFER_SIZE],
+    len: usize,
+}
+
+impl ConsoleBuffer {
+    /// Emit the buffer once it ends a line or is full
+    fn push(&mut self, byte: u8) {
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if byte == b'\n' || self.len == CONSOLE_BUFFER_SIZE {
+            self.flush();
+        }
+    }
+    fn flush(&mut self) {
+        for byte in self.buf[..self.len].iter() {
+            console_putchar(*byte as usize);
+        }
+        self.len = 0;
+    }
+}
+
+lazy_static! {
+    static ref CONSOLE_BUFFER: UPSafeCell<ConsoleBuffer> = unsafe {
+        UPSafeCell::new(ConsoleBuffer {
+            buf: [0; CONSOLE_BUFFER_SIZE],
+            len: 0,
+        })
+    };
+}
+
+/// Emit what is buffered, it is called on every task switch and on panic so
+/// nothing printed is lost
+pub fn console_flush() {
+    CONSOLE_BUFFER.exclusive_access().flush();
+}
 
 struct Stdout;
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            console_putchar(c as usize);
+        let mut buffer = CONSOLE_BUFFER.exclusive_access();
+        for byte in s.bytes() {
+            buffer.push(byte);
         }
         Ok(())
     }
@@ -0,0 +1,24 @@
+//! File trait & the files a task can open: stdin, stdout and pipes
+//!
+//! Every task keeps them in its fd table, see
+//! [`TaskControlBlockInner`](crate::task::TaskControlBlockInner).
+
+mod pipe;
+mod stdio;
+
+use crate::mm::UserBuffer;
+
+/// trait File for all file types
+pub trait File: Send + Sync {
+    /// the file readable?
+    fn readable(&self) -> bool;
+    /// the file writable?
+    fn writable(&self) -> bool;
+    /// read from the file to buf, return the number of bytes read
+    fn read(&self, buf: UserBuffer) -> usize;
+    /// write to the file from buf, return the number of bytes written
+    fn write(&self, buf: UserBuffer) -> usize;
+}
+
+pub use pipe::{make_pipe, Pipe};
+pub use stdio::{inject_input, Stdin, Stdout};
@@ -0,0 +1,102 @@
+//! Stdin & Stdout, both on the console
+use super::File;
+use crate::config::STDIN_POLL_MS;
+use crate::mm::UserBuffer;
+use crate::sbi::console_getchar;
+use crate::sync::UPSafeCell;
+use crate::task::{block_current_and_run_next, check_signals_error_of_current, current_task};
+use crate::timer::{add_interruptible_timer, get_time_us};
+use alloc::collections::VecDeque;
+use lazy_static::*;
+
+/// stdin file for getting chars from console
+pub struct Stdin;
+
+/// stdout file for putting chars to console
+pub struct Stdout;
+
+lazy_static! {
+    /// Input read from stdin before the console, tests feed it to `sys_read`
+    static ref INJECTED_INPUT: UPSafeCell<VecDeque<u8>> =
+        unsafe { UPSafeCell::new(VecDeque::new()) };
+}
+
+/// Queue `bytes` to be read from stdin ahead of the console
+pub fn inject_input(bytes: impl Iterator<Item = u8>) {
+    INJECTED_INPUT.exclusive_access().extend(bytes);
+}
+
+/// The next byte of input, None if there is none right now
+fn getchar() -> Option<u8> {
+    if let Some(ch) = INJECTED_INPUT.exclusive_access().pop_front() {
+        return Some(ch);
+    }
+    // the legacy sbi call returns -1 if there is no input, some versions 0
+    match console_getchar() {
+        0 | usize::MAX => None,
+        c => Some(c as u8),
+    }
+}
+
+impl File for Stdin {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    /// Sleep until there is some input, then read as much of it as there is
+    /// right now, up to the length of the buffer. A signal that kills the
+    /// task wakes it up, and nothing is read then.
+    fn read(&self, user_buf: UserBuffer) -> usize {
+        if user_buf.len() == 0 {
+            return 0;
+        }
+        let mut first = loop {
+            if let Some(ch) = getchar() {
+                break Some(ch);
+            }
+            // the task is killed on the way back to user space
+            if check_signals_error_of_current().is_some() {
+                return 0;
+            }
+            add_interruptible_timer(
+                get_time_us() + STDIN_POLL_MS * 1000,
+                current_task().unwrap(),
+            );
+            block_current_and_run_next();
+        };
+        let mut already_read = 0usize;
+        for byte_ref in user_buf {
+            match first.take().or_else(getchar) {
+                Some(ch) => unsafe {
+                    *byte_ref = ch;
+                },
+                None => break,
+            }
+            already_read += 1;
+        }
+        already_read
+    }
+    fn write(&self, _user_buf: UserBuffer) -> usize {
+        panic!("Cannot write to stdin!");
+    }
+}
+
+impl File for Stdout {
+    fn readable(&self) -> bool {
+        false
+    }
+    fn writable(&self) -> bool {
+        true
+    }
+    fn read(&self, _user_buf: UserBuffer) -> usize {
+        panic!("Cannot read from stdout!");
+    }
+    fn write(&self, user_buf: UserBuffer) -> usize {
+        for buffer in user_buf.buffers.iter() {
+            print!("{}", core::str::from_utf8(buffer).unwrap());
+        }
+        user_buf.len()
+    }
+}
@@ -1,7 +1,8 @@
 //! The panic handler
 
-use crate::console::ANSICON;
+use crate::console::{console_flush, ANSICON};
 use crate::sbi::shutdown;
+use crate::task::current_task_name;
 
 use core::panic::PanicInfo;
 
@@ -25,5 +26,15 @@ fn panic(info: &PanicInfo) -> ! {
             info.message().unwrap()
         );
     }
+    if let Some((pid, name)) = current_task_name() {
+        println_colorized!(
+            "[kernel] Current task: pid {} ({})",
+            ANSICON::FgRed,
+            ANSICON::BgDefault,
+            pid,
+            name
+        );
+    }
+    console_flush();
     shutdown()
 }
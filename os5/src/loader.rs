@@ -53,12 +53,31 @@ lazy_static! {
     };
 }
 
+lazy_static! {
+    /// Names and elf data of the apps sorted by name, built once for the
+    /// lookups of exec and spawn
+    static ref APPS_BY_NAME: Vec<(&'static str, &'static [u8])> = {
+        let mut v: Vec<_> = APP_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (*name, get_app_data(i)))
+            .collect();
+        v.sort_unstable_by_key(|(name, _)| *name);
+        v
+    };
+}
+
 /// Get elf data by app name
 pub fn get_app_data_by_name(name: &str) -> Option<&'static [u8]> {
-    let num_app = get_num_app();
-    (0..num_app)
-        .find(|&i| APP_NAMES[i] == name)
-        .map(get_app_data)
+    APPS_BY_NAME
+        .binary_search_by_key(&name, |(name, _)| *name)
+        .ok()
+        .map(|i| APPS_BY_NAME[i].1)
+}
+
+/// Names of all the apps, in the order they were linked
+pub fn app_names() -> &'static [&'static str] {
+    APP_NAMES.as_slice()
 }
 
 /// Print all of app names during kernel initialization
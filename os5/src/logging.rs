@@ -30,16 +30,40 @@ impl Log for SimpleLogger {
     fn flush(&self) {}
 }
 
+/// The level called `name`, in any case
+fn level_filter(name: &str) -> Option<LevelFilter> {
+    [
+        ("OFF", LevelFilter::Off),
+        ("ERROR", LevelFilter::Error),
+        ("WARN", LevelFilter::Warn),
+        ("INFO", LevelFilter::Info),
+        ("DEBUG", LevelFilter::Debug),
+        ("TRACE", LevelFilter::Trace),
+    ]
+    .iter()
+    .find(|(level_name, _)| level_name.eq_ignore_ascii_case(name))
+    .map(|(_, level)| *level)
+}
+
 /// initiate logger
 pub fn init() {
     static LOGGER: SimpleLogger = SimpleLogger;
     log::set_logger(&LOGGER).unwrap();
-    log::set_max_level(match option_env!("LOG") {
-        Some("ERROR") => LevelFilter::Error,
-        Some("WARN") => LevelFilter::Warn,
-        Some("INFO") => LevelFilter::Info,
-        Some("DEBUG") => LevelFilter::Debug,
-        Some("TRACE") => LevelFilter::Trace,
-        _ => LevelFilter::Off,
-    });
+    log::set_max_level(
+        option_env!("LOG")
+            .and_then(level_filter)
+            .unwrap_or(LevelFilter::Off),
+    );
+}
+
+/// Log at the level called `name` and above from now on, return false if
+/// there is no such level
+pub fn set_level(name: &str) -> bool {
+    match level_filter(name) {
+        Some(level) => {
+            log::set_max_level(level);
+            true
+        }
+        None => false,
+    }
 }
@@ -27,10 +27,15 @@ extern crate log;
 
 extern crate alloc;
 
+// the canary is only written in debug builds
+#[cfg(all(feature = "stack-overflow-test", not(debug_assertions)))]
+compile_error!("the stack-overflow-test feature needs a debug build, make MODE=debug");
 
 #[macro_use]
 mod console;
+mod cmdline;
 mod config;
+mod fs;
 mod lang_items;
 mod loader;
 mod logging;
@@ -62,9 +67,15 @@ fn clear_bss() {
 pub fn rust_main() -> ! {
     clear_bss();
     logging::init();
+    cmdline::init();
     println!("[kernel] Hello, world!");
     mm::init();
     mm::remap_test();
+    mm::elf_load_test();
+    mm::frame_leak_test();
+    task::kernel_stack_canary_test();
+    #[cfg(feature = "stack-overflow-test")]
+    task::kernel_stack_overflow_test();
     task::add_initproc();
     info!("after initproc!");
     trap::init();
@@ -1,19 +1,24 @@
 //! Implementation of [`FrameAllocator`] which
 //! controls all the frames in the operating system.
 
-use super::{PhysAddr, PhysPageNum};
-use crate::config::MEMORY_END;
+use super::{reclaim_frame, PhysAddr, PhysPageNum};
+use crate::config::{MEMORY_END, PAGE_SIZE, SWAP_PAGES};
 use crate::sync::UPSafeCell;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
 use lazy_static::*;
 
 /// manage a frame which has the same lifecycle as the tracker
+///
+/// A clone shares the frame, as copy-on-write pages do: the frame allocator
+/// counts the trackers of each frame and frees it with the last of them.
 pub struct FrameTracker {
     pub ppn: PhysPageNum,
 }
 
 impl FrameTracker {
+    /// The frame is zeroed, so a new page never shows the data of whoever
+    /// freed the frame before.
     pub fn new(ppn: PhysPageNum) -> Self {
         // page cleaning
         let bytes_array = ppn.get_bytes_array();
@@ -22,6 +27,17 @@ impl FrameTracker {
         }
         Self { ppn }
     }
+    /// Trackers of the frame, this one included
+    pub fn ref_count(&self) -> usize {
+        FRAME_ALLOCATOR.exclusive_access().ref_count(self.ppn)
+    }
+}
+
+impl Clone for FrameTracker {
+    fn clone(&self) -> Self {
+        FRAME_ALLOCATOR.exclusive_access().share(self.ppn);
+        Self { ppn: self.ppn }
+    }
 }
 
 impl Debug for FrameTracker {
@@ -32,13 +48,14 @@ impl Debug for FrameTracker {
 
 impl Drop for FrameTracker {
     fn drop(&mut self) {
-        frame_dealloc(self.ppn);
+        frame_release(self.ppn);
     }
 }
 
 trait FrameAllocator {
     fn new() -> Self;
     fn alloc(&mut self) -> Option<PhysPageNum>;
+    fn alloc_contiguous(&mut self, count: usize) -> Option<PhysPageNum>;
     fn dealloc(&mut self, ppn: PhysPageNum);
 }
 
@@ -47,14 +64,81 @@ pub struct StackFrameAllocator {
     current: usize,
     end: usize,
     recycled: Vec<usize>,
+    /// The first frame managed
+    start: usize,
+    /// Trackers of each frame indexed by `ppn - start`, 0 while it is free
+    refcounts: Vec<u16>,
+    /// Bit `ppn - start` is set while the frame is allocated, so that a
+    /// double free is caught in debug builds
+    #[cfg(debug_assertions)]
+    allocated: Vec<u64>,
 }
 
 impl StackFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.start = l.0;
         self.current = l.0;
         self.end = r.0;
+        self.refcounts = alloc::vec![0; r.0 - l.0];
+        #[cfg(debug_assertions)]
+        {
+            self.allocated = alloc::vec![0; (r.0 - l.0 + 63) / 64];
+        }
         info!("last {} Physical Frames.", self.end - self.current);
     }
+    /// Number of frames managed
+    pub fn total(&self) -> usize {
+        self.end - self.start
+    }
+    /// Number of frames that can be allocated, never allocated or recycled
+    pub fn free(&self) -> usize {
+        self.end - self.current + self.recycled.len()
+    }
+    fn ref_count(&self, ppn: PhysPageNum) -> usize {
+        self.refcounts[ppn.0 - self.start] as usize
+    }
+    /// One more tracker of the allocated frame `ppn`
+    fn share(&mut self, ppn: PhysPageNum) {
+        let count = &mut self.refcounts[ppn.0 - self.start];
+        assert!(*count > 0, "Frame ppn={:#x} is shared while free!", ppn.0);
+        *count = count.checked_add(1).expect("too many trackers of a frame");
+    }
+    /// One tracker of `ppn` less, the frame is freed with the last one
+    fn release(&mut self, ppn: PhysPageNum) {
+        let count = &mut self.refcounts[ppn.0 - self.start];
+        // the count catches a double free in release builds as well
+        assert!(*count > 0, "Frame ppn={:#x} is freed twice!", ppn.0);
+        *count -= 1;
+        if *count == 0 {
+            self.dealloc(ppn);
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl StackFrameAllocator {
+    fn allocated_bit(&self, ppn: usize) -> (usize, u64) {
+        let idx = ppn - self.start;
+        (idx / 64, 1 << (idx % 64))
+    }
+    fn mark_allocated(&mut self, ppn: usize) {
+        let (word, bit) = self.allocated_bit(ppn);
+        assert!(
+            self.allocated[word] & bit == 0,
+            "Frame ppn={:#x} is allocated twice!",
+            ppn
+        );
+        self.allocated[word] |= bit;
+    }
+    fn mark_free(&mut self, ppn: usize) {
+        let (word, bit) = self.allocated_bit(ppn);
+        assert!(
+            self.allocated[word] & bit != 0,
+            "Frame ppn={:#x} is freed twice!",
+            ppn
+        );
+        self.allocated[word] &= !bit;
+    }
 }
 impl FrameAllocator for StackFrameAllocator {
     fn new() -> Self {
@@ -62,24 +146,52 @@ impl FrameAllocator for StackFrameAllocator {
             current: 0,
             end: 0,
             recycled: Vec::new(),
+            start: 0,
+            refcounts: Vec::new(),
+            #[cfg(debug_assertions)]
+            allocated: Vec::new(),
         }
     }
     fn alloc(&mut self) -> Option<PhysPageNum> {
-        if let Some(ppn) = self.recycled.pop() {
-            Some(ppn.into())
+        let ppn = if let Some(ppn) = self.recycled.pop() {
+            ppn
         } else if self.current == self.end {
-            None
+            return None;
         } else {
             self.current += 1;
-            Some((self.current - 1).into())
+            self.current - 1
+        };
+        #[cfg(debug_assertions)]
+        self.mark_allocated(ppn);
+        self.refcounts[ppn - self.start] = 1;
+        Some(ppn.into())
+    }
+    /// Only frames never allocated before are contiguous, those recycled
+    /// are not merged back.
+    fn alloc_contiguous(&mut self, count: usize) -> Option<PhysPageNum> {
+        let start = (self.current + count - 1) & !(count - 1);
+        if start + count > self.end {
+            return None;
+        }
+        // the frames skipped for alignment can still be allocated one by one
+        self.recycled.extend(self.current..start);
+        self.current = start + count;
+        #[cfg(debug_assertions)]
+        for ppn in start..start + count {
+            self.mark_allocated(ppn);
         }
+        self.refcounts[start - self.start..start + count - self.start].fill(1);
+        Some(start.into())
     }
     fn dealloc(&mut self, ppn: PhysPageNum) {
         let ppn = ppn.0;
-        // validity check
-        if ppn >= self.current || self.recycled.iter().any(|v| *v == ppn) {
+        // validity check, `release` has caught a double free already, the
+        // bitmap checks it again in debug builds
+        if ppn >= self.current {
             panic!("Frame ppn={:#x} has not been allocated!", ppn);
         }
+        #[cfg(debug_assertions)]
+        self.mark_free(ppn);
         // recycle
         self.recycled.push(ppn);
     }
@@ -99,21 +211,100 @@ pub fn init_frame_allocator() {
     }
     FRAME_ALLOCATOR.exclusive_access().init(
         PhysAddr::from(ekernel as usize).ceil(),
-        PhysAddr::from(MEMORY_END).floor(),
+        // the swap area comes last
+        PhysAddr::from(MEMORY_END - SWAP_PAGES * PAGE_SIZE).floor(),
     );
 }
 
-/// initiate the frame allocator using `ekernel` and `MEMORY_END`
+/// Allocate a frame, swapping out pages of the lazy areas while there is
+/// none free. None if nothing can be swapped out.
 pub fn frame_alloc() -> Option<FrameTracker> {
-    FRAME_ALLOCATOR
-        .exclusive_access()
-        .alloc()
-        .map(FrameTracker::new)
+    loop {
+        // the allocator is not borrowed while reclaiming, which frees a frame
+        let ppn = FRAME_ALLOCATOR.exclusive_access().alloc();
+        match ppn {
+            Some(ppn) => return Some(FrameTracker::new(ppn)),
+            None if reclaim_frame() => {}
+            None => return None,
+        }
+    }
+}
+
+/// Allocate `count` contiguous frames starting at a ppn aligned to `count`,
+/// which must be a power of two, as a megapage needs.
+pub fn frame_alloc_contiguous(count: usize) -> Option<Vec<FrameTracker>> {
+    let start = FRAME_ALLOCATOR.exclusive_access().alloc_contiguous(count)?;
+    Some(
+        (start.0..start.0 + count)
+            .map(|ppn| FrameTracker::new(ppn.into()))
+            .collect(),
+    )
+}
+
+/// Total and free frames of the frame allocator
+pub fn frame_usage() -> (usize, usize) {
+    let allocator = FRAME_ALLOCATOR.exclusive_access();
+    (allocator.total(), allocator.free())
+}
+
+/// Drop a tracker of the frame `ppn`, deallocate it with the last one
+fn frame_release(ppn: PhysPageNum) {
+    FRAME_ALLOCATOR.exclusive_access().release(ppn);
+}
+
+/// Frame leak detector: it remembers the free frames when created, and
+/// panics on drop if fewer are free then. In debug builds the frames
+/// allocated meanwhile and not freed are listed, from the double-free bitmap.
+pub struct FrameLeakGuard {
+    name: &'static str,
+    free: usize,
+    #[cfg(debug_assertions)]
+    allocated: Vec<u64>,
+}
+
+impl FrameLeakGuard {
+    pub fn new(name: &'static str) -> Self {
+        let allocator = FRAME_ALLOCATOR.exclusive_access();
+        Self {
+            name,
+            free: allocator.free(),
+            #[cfg(debug_assertions)]
+            allocated: allocator.allocated.clone(),
+        }
+    }
 }
 
-/// deallocate a frame
-fn frame_dealloc(ppn: PhysPageNum) {
-    FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
+impl Drop for FrameLeakGuard {
+    fn drop(&mut self) {
+        let allocator = FRAME_ALLOCATOR.exclusive_access();
+        let leaked = self.free as isize - allocator.free() as isize;
+        if leaked == 0 {
+            return;
+        }
+        error!("{}: {} frames leaked", self.name, leaked);
+        #[cfg(debug_assertions)]
+        for (word, (now, before)) in allocator
+            .allocated
+            .iter()
+            .zip(self.allocated.iter())
+            .enumerate()
+        {
+            let mut bits = now & !before;
+            while bits != 0 {
+                let ppn = allocator.start + word * 64 + bits.trailing_zeros() as usize;
+                error!("{}: frame ppn={:#x} leaked", self.name, ppn);
+                bits &= bits - 1;
+            }
+        }
+        panic!("{}: the free frames are not restored", self.name);
+    }
+}
+
+/// Run `f` under a [`FrameLeakGuard`], it must give back every frame it
+/// allocates
+pub fn check_frame_leaks<F: FnOnce()>(name: &'static str, f: F) {
+    let _guard = FrameLeakGuard::new(name);
+    f();
 }
 
 #[allow(unused)]
@@ -134,3 +325,16 @@ pub fn frame_allocator_test() {
     drop(v);
     info!("frame_allocator_test passed!");
 }
+
+#[allow(unused)]
+/// Free a frame twice, which panics with its ppn
+pub fn frame_double_free_test() {
+    let frame = frame_alloc().unwrap();
+    let ppn = frame.ppn;
+    drop(frame);
+    frame_release(ppn);
+    panic!(
+        "frame_double_free_test: the double free of {:?} is not detected",
+        ppn
+    );
+}
@@ -1,14 +1,20 @@
 //! Implementation of [`MapArea`] and [`MemorySet`].
 
-use super::{frame_alloc, FrameTracker};
+use super::{check_frame_leaks, frame_alloc, frame_alloc_contiguous, FrameTracker};
 use super::{PTEFlags, PageTable, PageTableEntry};
 use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
-use super::{StepByOne, VPNRange};
-use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT, USER_STACK_SIZE};
-use crate::sync::UPSafeCell;
-use alloc::collections::BTreeMap;
+use super::{StepByOne, SwapSlot, VPNRange};
+use crate::config::{
+    HUGE_PAGE_SIZE, MEMORY_END, MMAP_STRICT_LEN, PAGE_SIZE, SWAP_RECENT_PAGES, TLB_FLUSH_PAGES,
+    TRAMPOLINE, TRAP_CONTEXT, USER_STACK_GROW_PAGES, USER_STACK_MAX_SIZE, USER_STACK_SIZE,
+    USER_STACK_TOP,
+};
+use crate::sync::{futex_in_frame, UPSafeCell};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
 use lazy_static::*;
 use riscv::register::satp;
@@ -36,6 +42,23 @@ lazy_static! {
 pub struct MemorySet {
     page_table: PageTable,
     areas: Vec<MapArea>,
+    /// The end of the elf, where the heap begins
+    heap_bottom: usize,
+    /// Current program break, the heap covers `[heap_bottom, program_brk)`
+    program_brk: usize,
+    /// Whether a len that is not a whole number of pages is an error to
+    /// mmap and the like, instead of being rounded up
+    strict_len: bool,
+    /// The most pages the areas have held frames for at once
+    max_rss: usize,
+    /// Where the reclaimer goes on looking for a page to swap out
+    clock_hand: VirtPageNum,
+    /// The last `SWAP_RECENT_PAGES` pages faulted in, which are not swapped
+    /// out
+    recent: VecDeque<VirtPageNum>,
+    /// Pages the kernel is using for a syscall, which are not swapped out
+    /// either, with the number of tasks pinning each
+    pinned: BTreeMap<VirtPageNum, usize>,
 }
 
 impl MemorySet {
@@ -43,20 +66,43 @@ impl MemorySet {
         Self {
             page_table: PageTable::new(),
             areas: Vec::new(),
+            heap_bottom: 0,
+            program_brk: 0,
+            strict_len: MMAP_STRICT_LEN,
+            max_rss: 0,
+            clock_hand: VirtPageNum(0),
+            recent: VecDeque::new(),
+            pinned: BTreeMap::new(),
         }
     }
+    /// None if there is no frame for the root page table.
+    pub fn try_new_bare() -> Option<Self> {
+        Some(Self {
+            page_table: PageTable::try_new()?,
+            areas: Vec::new(),
+            heap_bottom: 0,
+            program_brk: 0,
+            strict_len: MMAP_STRICT_LEN,
+            max_rss: 0,
+            clock_hand: VirtPageNum(0),
+            recent: VecDeque::new(),
+            pinned: BTreeMap::new(),
+        })
+    }
     pub fn token(&self) -> usize {
         self.page_table.token()
     }
     /// Assume that no conflicts.
+    #[allow(unused)]
     pub fn insert_framed_area(
         &mut self,
         start_va: VirtAddr,
         end_va: VirtAddr,
         permission: MapPermission,
+        name: &'static str,
     ) {
         self.push(
-            MapArea::new(start_va, end_va, MapType::Framed, permission),
+            MapArea::new(start_va, end_va, MapType::Framed, permission, name),
             None,
         );
     }
@@ -74,23 +120,78 @@ impl MemorySet {
     fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
         map_area.map(&mut self.page_table);
         if let Some(data) = data {
-            map_area.copy_data(&mut self.page_table, data);
+            map_area.copy_data(&mut self.page_table, data, 0);
+        }
+        self.areas.push(map_area);
+    }
+    /// Unlike `push`, running out of frames is an error. The frames of
+    /// `map_area` are released when it is dropped on failure.
+    fn try_push(&mut self, map_area: MapArea, data: Option<&[u8]>) -> Result<(), MapError> {
+        self.try_push_at(map_area, data, 0)
+    }
+    /// Like `try_push`, with the data starting `offset` bytes into the first page
+    fn try_push_at(
+        &mut self,
+        mut map_area: MapArea,
+        data: Option<&[u8]>,
+        offset: usize,
+    ) -> Result<(), MapError> {
+        map_area.map_result(&mut self.page_table)?;
+        if let Some(data) = data {
+            map_area.copy_data(&mut self.page_table, data, offset);
         }
         self.areas.push(map_area);
+        Ok(())
+    }
+    /// Give the page `vpn` of the last area `map_perm` as well and return its
+    /// frame, None if the last area does not cover it. The page goes to an
+    /// area of its own, so the rest of the last area keeps its permission.
+    /// Like an RWX segment, it loses X if it ends up writable.
+    fn share_last_page(
+        &mut self,
+        vpn: VirtPageNum,
+        map_perm: MapPermission,
+    ) -> Option<PhysPageNum> {
+        let last = self.areas.last_mut().filter(|area| area.contains(vpn))?;
+        let mut shared = last.split_off(vpn);
+        let tail = shared.split_off(VirtPageNum(vpn.0 + 1));
+        if last.is_empty() {
+            *last = shared;
+        } else {
+            self.areas.push(shared);
+        }
+        if !tail.is_empty() {
+            self.areas.push(tail);
+        }
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| area.contains(vpn))
+            .unwrap();
+        let mut perm = area.map_perm | map_perm;
+        if perm.contains(MapPermission::W | MapPermission::X) {
+            warn!(
+                "elf page {:?} is shared by W and X segments, mapped without X",
+                vpn
+            );
+            perm -= MapPermission::X;
+        }
+        area.set_perm(&mut self.page_table, perm);
+        Some(self.page_table.translate(vpn).unwrap().ppn())
     }
     /// Mention that trampoline is not collected by areas.
-    fn map_trampoline(&mut self) {
-        self.page_table.map(
+    fn map_trampoline(&mut self) -> Result<(), MapError> {
+        self.page_table.map_result(
             VirtAddr::from(TRAMPOLINE).into(),
             PhysAddr::from(strampoline as usize).into(),
             PTEFlags::R | PTEFlags::X,
-        );
+        )
     }
     /// Without kernel stacks.
     pub fn new_kernel() -> Self {
         let mut memory_set = Self::new_bare();
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline().unwrap();
         // map kernel sections
         info!(".text [{:#x}, {:#x})", stext as usize, etext as usize);
         info!(".rodata [{:#x}, {:#x})", srodata as usize, erodata as usize);
@@ -106,6 +207,7 @@ impl MemorySet {
                 (etext as usize).into(),
                 MapType::Identical,
                 MapPermission::R | MapPermission::X,
+                ".text",
             ),
             None,
         );
@@ -116,6 +218,7 @@ impl MemorySet {
                 (erodata as usize).into(),
                 MapType::Identical,
                 MapPermission::R,
+                ".rodata",
             ),
             None,
         );
@@ -126,6 +229,7 @@ impl MemorySet {
                 (edata as usize).into(),
                 MapType::Identical,
                 MapPermission::R | MapPermission::W,
+                ".data",
             ),
             None,
         );
@@ -136,6 +240,7 @@ impl MemorySet {
                 (ebss as usize).into(),
                 MapType::Identical,
                 MapPermission::R | MapPermission::W,
+                ".bss",
             ),
             None,
         );
@@ -146,24 +251,31 @@ impl MemorySet {
                 MEMORY_END.into(),
                 MapType::Identical,
                 MapPermission::R | MapPermission::W,
+                "physical memory",
             ),
             None,
         );
         memory_set
     }
     /// Include sections in elf and trampoline and TrapContext and user stack,
-    /// also returns user_sp and entry point.
+    /// also returns user_sp and entry point. The heap is empty, right after
+    /// the end of the elf.
     pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
-        let mut memory_set = Self::new_bare();
+        Self::try_from_elf(elf_data).unwrap()
+    }
+    /// Like `from_elf`, but fail if frames run out. Whatever has been
+    /// allocated until then is released along with the partial memory set.
+    pub fn try_from_elf(elf_data: &[u8]) -> Result<(Self, usize, usize), MapError> {
+        let mut memory_set = Self::try_new_bare().ok_or(MapError::NoFrame(VirtPageNum(0)))?;
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline()?;
         // map program headers of elf, with U flag
         let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
         let elf_header = elf.header;
         let magic = elf_header.pt1.magic;
         assert_eq!(magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf!");
         let ph_count = elf_header.pt2.ph_count();
-        let mut max_end_vpn = VirtPageNum(0);
+        let mut max_end_va = VirtAddr(0);
         for i in 0..ph_count {
             let ph = elf.program_header(i).unwrap();
             if ph.get_type().unwrap() == xmas_elf::program::Type::Load {
@@ -178,56 +290,129 @@ impl MemorySet {
                     map_perm |= MapPermission::W;
                 }
                 if ph_flags.is_execute() {
-                    map_perm |= MapPermission::X;
+                    // W^X: a writable segment loses X
+                    if ph_flags.is_write() {
+                        warn!(
+                            "elf segment [{:#x}, {:#x}) is RWX, mapped without X",
+                            start_va.0, end_va.0
+                        );
+                    } else {
+                        map_perm |= MapPermission::X;
+                    }
+                }
+                let mut data =
+                    &elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize];
+                // the segment starts anywhere in a page, which may be the
+                // last one of the previous segment
+                let mut offset = start_va.page_offset();
+                let mut start_vpn = start_va.floor();
+                if let Some(ppn) = memory_set.share_last_page(start_vpn, map_perm) {
+                    let len = data.len().min(PAGE_SIZE - offset);
+                    ppn.get_bytes_array()[offset..offset + len].copy_from_slice(&data[..len]);
+                    data = &data[len..];
+                    offset = 0;
+                    start_vpn.step();
+                }
+                if start_vpn < end_va.ceil() {
+                    let map_area =
+                        MapArea::new(start_vpn.into(), end_va, MapType::Framed, map_perm, "elf");
+                    memory_set.try_push_at(map_area, Some(data), offset)?;
                 }
-                let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
-                max_end_vpn = map_area.vpn_range.get_end();
-                memory_set.push(
-                    map_area,
-                    Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
-                );
+                max_end_va = max_end_va.max(end_va.ceil().into());
             }
         }
-        // map user stack with U flags
-        let max_end_va: VirtAddr = max_end_vpn.into();
-        let mut user_stack_bottom: usize = max_end_va.into();
-        // guard page
-        user_stack_bottom += PAGE_SIZE;
-        let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
-        memory_set.push(
+        // the heap starts right after the elf and grows with sys_brk
+        // map user stack with U flags, it may grow down to USER_STACK_MAX_SIZE
+        // and the guard page below that is never mapped
+        let user_stack_top = USER_STACK_TOP;
+        let user_stack_bottom = user_stack_top - USER_STACK_SIZE;
+        memory_set.try_push(
             MapArea::new(
                 user_stack_bottom.into(),
                 user_stack_top.into(),
                 MapType::Framed,
                 MapPermission::R | MapPermission::W | MapPermission::U,
+                "user stack",
             ),
             None,
-        );
+        )?;
         // map TrapContext
-        memory_set.push(
+        memory_set.try_push(
             MapArea::new(
                 TRAP_CONTEXT.into(),
                 TRAMPOLINE.into(),
                 MapType::Framed,
                 MapPermission::R | MapPermission::W,
+                "trap context",
             ),
             None,
-        );
-        (
+        )?;
+        memory_set.heap_bottom = max_end_va.into();
+        memory_set.program_brk = max_end_va.into();
+        memory_set.note_rss();
+        Ok((
             memory_set,
             user_stack_top,
             elf.header.pt2.entry_point() as usize,
-        )
+        ))
     }
     /// Copy an identical user_space
-    pub fn from_existed_user(user_space: &MemorySet) -> MemorySet {
-        let mut memory_set = Self::new_bare();
+    ///
+    /// User pages are shared copy-on-write: both sides map the same frames
+    /// without `W` until one of them stores to the page. Pages of shared
+    /// areas keep `W` and are never copied. Kernel-only areas (the trap
+    /// context), megapages and pages with futex waiters are still copied
+    /// eagerly: a futex is keyed by its frame, which must stay with the
+    /// waiters.
+    ///
+    /// Fail if frames run out. Pages shared until then stay without `W` in
+    /// `user_space`, which is harmless: once the partial copy is dropped they
+    /// are not shared any more and the next store only restores `W`.
+    pub fn try_from_existed_user(user_space: &mut MemorySet) -> Result<MemorySet, MapError> {
+        let mut memory_set = Self::try_new_bare().ok_or(MapError::NoFrame(VirtPageNum(0)))?;
+        memory_set.heap_bottom = user_space.heap_bottom;
+        memory_set.program_brk = user_space.program_brk;
+        memory_set.strict_len = user_space.strict_len;
         // map trampoline
-        memory_set.map_trampoline();
-        // copy data sections/trap_context/user_stack
+        memory_set.map_trampoline()?;
+        // share data sections/user_stack, copy trap_context
         for area in user_space.areas.iter() {
-            let new_area = MapArea::from_another(area);
-            memory_set.push(new_area, None);
+            let mut new_area = MapArea::from_another(area);
+            if area.map_perm.contains(MapPermission::U) && area.map_type != MapType::Huge {
+                let pte_flags = if area.map_type == MapType::Shared {
+                    PTEFlags::from_bits(area.map_perm.bits).unwrap()
+                } else {
+                    area.cow_pte_flags()
+                };
+                for (vpn, frame) in area.data_frames.iter() {
+                    if area.map_type != MapType::Shared && futex_in_frame(frame.ppn) {
+                        let copy = frame_alloc().ok_or(MapError::NoFrame(*vpn))?;
+                        copy.ppn
+                            .get_bytes_array()
+                            .copy_from_slice(frame.ppn.get_bytes_array());
+                        memory_set.page_table.map_result(
+                            *vpn,
+                            copy.ppn,
+                            PTEFlags::from_bits(area.map_perm.bits).unwrap(),
+                        )?;
+                        new_area.data_frames.insert(*vpn, copy);
+                        continue;
+                    }
+                    memory_set
+                        .page_table
+                        .map_result(*vpn, frame.ppn, pte_flags)?;
+                    user_space.page_table.set_flags(*vpn, pte_flags);
+                    new_area.data_frames.insert(*vpn, frame.clone());
+                }
+                // a page swapped out gets a slot of its own
+                for (vpn, slot) in area.swapped.iter() {
+                    let slot = slot.duplicate().ok_or(MapError::NoFrame(*vpn))?;
+                    new_area.swapped.insert(*vpn, slot);
+                }
+                memory_set.areas.push(new_area);
+                continue;
+            }
+            memory_set.try_push(new_area, None)?;
             // copy data from another space
             for vpn in area.vpn_range {
                 let src_ppn = user_space.translate(vpn).unwrap().ppn();
@@ -237,7 +422,241 @@ impl MemorySet {
                     .copy_from_slice(src_ppn.get_bytes_array());
             }
         }
-        memory_set
+        memory_set.note_rss();
+        Ok(memory_set)
+    }
+    /// Give the page at `vpn` a private writable frame if it is shared
+    /// copy-on-write, return false if it is not a copy-on-write page.
+    pub fn copy_on_write(&mut self, vpn: VirtPageNum) -> bool {
+        match self.page_table.translate(vpn) {
+            Some(pte) if pte.is_valid() && !pte.writable() => {}
+            _ => return false,
+        }
+        if let Some(area) = self.areas.iter_mut().find(|area| area.contains(vpn)) {
+            area.copy_on_write(&mut self.page_table, vpn)
+        } else {
+            false
+        }
+    }
+    /// Allocate the frame of a page in a lazy area on its first access, or
+    /// read it back if it is swapped out. Pages of the lazy areas are swapped
+    /// out while there is no frame for it. Return false if `vpn` is mapped
+    /// already or not in a lazy area, or frames run out all the same.
+    pub fn lazy_alloc(&mut self, vpn: VirtPageNum) -> bool {
+        if self
+            .page_table
+            .translate(vpn)
+            .map_or(false, |pte| pte.is_valid())
+        {
+            return false;
+        }
+        let idx = match self
+            .areas
+            .iter()
+            .position(|area| area.map_type == MapType::Lazy && area.contains(vpn))
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+        loop {
+            match self.areas[idx].fault_in_lazy(&mut self.page_table, vpn) {
+                Ok(()) => break,
+                Err(MapError::NoFrame(_)) if self.swap_out_one() => {}
+                Err(_) => return false,
+            }
+        }
+        if self.recent.len() == SWAP_RECENT_PAGES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(vpn);
+        self.note_rss();
+        true
+    }
+    /// Keep `vpn` from being swapped out until it is unpinned as many times
+    pub fn pin(&mut self, vpn: VirtPageNum) {
+        *self.pinned.entry(vpn).or_insert(0) += 1;
+    }
+    pub fn unpin(&mut self, vpn: VirtPageNum) {
+        let count = self.pinned.get_mut(&vpn).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.pinned.remove(&vpn);
+        }
+    }
+    /// The first page at or after `from` that may be swapped out: present in
+    /// a lazy area and not shared copy-on-write
+    fn next_clock_page(&self, from: VirtPageNum) -> Option<VirtPageNum> {
+        self.areas
+            .iter()
+            .filter(|area| area.map_type == MapType::Lazy)
+            .filter_map(|area| {
+                area.data_frames
+                    .range(from..)
+                    .find(|(_, frame)| frame.ref_count() == 1)
+                    .map(|(vpn, _)| *vpn)
+            })
+            .min()
+    }
+    /// Swap a page of the lazy areas out to free its frame, picked by the
+    /// clock algorithm: the hand sweeps the pages in address order, and one
+    /// accessed since the last sweep gets a second chance with its accessed
+    /// bit cleared. Return false if there is no page to swap out or the swap
+    /// area is full.
+    pub fn swap_out_one(&mut self) -> bool {
+        let mut vpn = self.clock_hand;
+        // the first sweep may only clear accessed bits, it starts at the hand
+        let mut sweeps = 0;
+        let victim = loop {
+            let next = match self.next_clock_page(vpn) {
+                Some(next) => next,
+                None if sweeps == 2 => return false,
+                None => {
+                    sweeps += 1;
+                    vpn = VirtPageNum(0);
+                    continue;
+                }
+            };
+            vpn = VirtPageNum(next.0 + 1);
+            if self.recent.contains(&next) || self.pinned.contains_key(&next) {
+                continue;
+            }
+            let pte = self.page_table.translate(next).unwrap();
+            // a futex is keyed by its frame, its waiters would never be woken
+            if futex_in_frame(pte.ppn()) {
+                continue;
+            }
+            let flags = pte.flags();
+            if flags.contains(PTEFlags::A) {
+                self.page_table.set_flags(next, flags - PTEFlags::A);
+                flush_tlb(VPNRange::new(next, vpn));
+                continue;
+            }
+            break next;
+        };
+        let slot = match SwapSlot::alloc() {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| area.map_type == MapType::Lazy && area.contains(victim))
+            .unwrap();
+        let frame = area.data_frames.remove(&victim).unwrap();
+        slot.write(frame.ppn);
+        area.swapped.insert(victim, slot);
+        self.page_table.unmap(victim);
+        flush_tlb(VPNRange::new(victim, vpn));
+        self.clock_hand = vpn;
+        true
+    }
+    /// The lowest address of the user stack. The stack grows down in new
+    /// areas, so follow the user areas downwards from `USER_STACK_TOP`.
+    pub fn user_stack_bottom(&self) -> usize {
+        let mut bottom = VirtAddr::from(USER_STACK_TOP).floor();
+        while let Some(area) = self.areas.iter().find(|area| {
+            area.map_perm.contains(MapPermission::U) && area.vpn_range.get_end() == bottom
+        }) {
+            bottom = area.vpn_range.get_start();
+        }
+        VirtAddr::from(bottom).into()
+    }
+    /// Grow the user stack down to `vpn` if it is no more than
+    /// `USER_STACK_GROW_PAGES` below the stack and above the guard page,
+    /// keeping an unmapped page beneath it. Return false if the stack can
+    /// not grow there.
+    pub fn grow_user_stack(&mut self, vpn: VirtPageNum) -> bool {
+        let bottom = VirtAddr::from(self.user_stack_bottom()).floor();
+        let guard_vpn = user_stack_guard_page();
+        if vpn >= bottom || vpn <= guard_vpn || bottom.0 - vpn.0 > USER_STACK_GROW_PAGES {
+            return false;
+        }
+        // the guard page itself is reserved, anything else right below the
+        // stack means there is no room for it
+        let below_vpn = VirtPageNum::from(vpn.0 - 1);
+        if below_vpn != guard_vpn && self.find_mapped(VPNRange::new(below_vpn, vpn)).is_some() {
+            return false;
+        }
+        self.try_map_range(
+            vpn.into(),
+            bottom.into(),
+            MapType::Framed,
+            MapPermission::R | MapPermission::W | MapPermission::U,
+            "user stack",
+        )
+        .is_ok()
+    }
+    pub fn strict_len(&self) -> bool {
+        self.strict_len
+    }
+    /// Return the previous setting
+    pub fn set_strict_len(&mut self, strict: bool) -> bool {
+        core::mem::replace(&mut self.strict_len, strict)
+    }
+    pub fn program_brk(&self) -> usize {
+        self.program_brk
+    }
+    /// Move the program break to `new_brk`, mapping or unmapping the heap
+    /// pages in between. Return None if `new_brk` is below the heap bottom
+    /// or the heap would run into the user stack or another mapping.
+    pub fn change_program_brk(&mut self, new_brk: usize) -> Option<usize> {
+        let old_brk = self.program_brk;
+        // the heap ends below the guard page of the stack
+        let heap_top: VirtAddr = user_stack_guard_page().into();
+        if new_brk < self.heap_bottom || new_brk > heap_top.into() {
+            return None;
+        }
+        let old_end: VirtAddr = VirtAddr::from(old_brk).ceil().into();
+        let new_end: VirtAddr = VirtAddr::from(new_brk).ceil().into();
+        let result = match new_end.cmp(&old_end) {
+            core::cmp::Ordering::Greater => self.insert_framed_area_result(
+                old_end,
+                new_end,
+                MapPermission::R | MapPermission::W | MapPermission::U,
+                "heap",
+            ),
+            core::cmp::Ordering::Less => self.remove_area_result(new_end, old_end),
+            core::cmp::Ordering::Equal => Ok(()),
+        };
+        if let Err(err) = result {
+            error!("change_program_brk err: {}", err);
+            return None;
+        }
+        self.program_brk = new_brk;
+        Some(new_brk)
+    }
+    /// Map a user stack and a trap context for a new thread in the lowest
+    /// free slot and return the slot. Nothing is mapped on failure.
+    pub fn map_thread_slot(&mut self) -> Result<usize, MapError> {
+        let slot = (1..)
+            .find(|slot| !self.in_area(VirtAddr::from(thread_trap_cx_va(*slot)).floor()))
+            .unwrap();
+        let stack_top = thread_stack_top(slot);
+        self.try_map_range(
+            (stack_top - USER_STACK_SIZE).into(),
+            stack_top.into(),
+            MapType::Framed,
+            MapPermission::R | MapPermission::W | MapPermission::U,
+            "thread stack",
+        )?;
+        let trap_cx_va = thread_trap_cx_va(slot);
+        if let Err(err) = self.try_map_range(
+            trap_cx_va.into(),
+            (trap_cx_va + PAGE_SIZE).into(),
+            MapType::Framed,
+            MapPermission::R | MapPermission::W,
+            "trap context",
+        ) {
+            self.remove_area_with_start_vpn(VirtAddr::from(stack_top - USER_STACK_SIZE).floor());
+            return Err(err);
+        }
+        Ok(slot)
+    }
+    /// Unmap the user stack and the trap context of an exited thread
+    pub fn unmap_thread_slot(&mut self, slot: usize) {
+        let stack_top = thread_stack_top(slot);
+        self.remove_area_with_start_vpn(VirtAddr::from(stack_top - USER_STACK_SIZE).floor());
+        self.remove_area_with_start_vpn(VirtAddr::from(thread_trap_cx_va(slot)).floor());
     }
     pub fn activate(&self) {
         let satp = self.page_table.token();
@@ -249,18 +668,73 @@ impl MemorySet {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.page_table.translate(vpn)
     }
+    /// Free the frames of every area and those of the page table except its
+    /// root when the process exits, instead of when its zombie is reaped.
+    /// Runs on the kernel address space, nothing uses the user one after it.
     pub fn recycle_data_pages(&mut self) {
-        //*self = Self::new_bare();
         self.areas.clear();
+        self.page_table.release_tables();
+    }
+}
+
+/// Number of 4KiB pages in a megapage
+const HUGE_PAGE_PAGES: usize = HUGE_PAGE_SIZE / PAGE_SIZE;
+
+/// The megapage containing `vpn`
+fn huge_page_of(vpn: VirtPageNum) -> VirtPageNum {
+    VirtPageNum(vpn.0 & !(HUGE_PAGE_PAGES - 1))
+}
+
+/// Drop the TLB entries of the pages in `vpn_range` after their ptes changed,
+/// one by one unless there are more than `TLB_FLUSH_PAGES` of them. The
+/// trampoline also flushes the whole TLB when it switches to user space, but
+/// a freed frame must not stay reachable in between.
+fn flush_tlb(vpn_range: VPNRange) {
+    let pages = vpn_range.get_end().0 - vpn_range.get_start().0;
+    if pages > TLB_FLUSH_PAGES {
+        unsafe {
+            core::arch::asm!("sfence.vma");
+        }
+        return;
+    }
+    for vpn in vpn_range {
+        let va: VirtAddr = vpn.into();
+        unsafe {
+            core::arch::asm!("sfence.vma {}, zero", in(reg) va.0);
+        }
     }
 }
 
+/// The page below the lowest the user stack may grow to. It is never mapped,
+/// so a stack overflow faults there instead of running into other memory.
+pub fn user_stack_guard_page() -> VirtPageNum {
+    VirtAddr::from(USER_STACK_TOP - USER_STACK_MAX_SIZE - PAGE_SIZE).floor()
+}
+
+/// The trap context of the thread in `slot`, slot 0 is that of the main
+/// thread at `TRAP_CONTEXT`, the others follow downwards.
+pub fn thread_trap_cx_va(slot: usize) -> usize {
+    TRAP_CONTEXT - slot * PAGE_SIZE
+}
+
+/// The top of the user stack of the thread in `slot`, which is not 0. These
+/// stacks lie below the guard page of the main stack, each of them above an
+/// unmapped page, and do not grow.
+pub fn thread_stack_top(slot: usize) -> usize {
+    let guard: VirtAddr = user_stack_guard_page().into();
+    guard.0 - (slot - 1) * (USER_STACK_SIZE + PAGE_SIZE)
+}
+
 /// map area structure, controls a contiguous piece of virtual memory
 pub struct MapArea {
     vpn_range: VPNRange,
     data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    /// Pages of a lazy area that are swapped out
+    swapped: BTreeMap<VirtPageNum, SwapSlot>,
     map_type: MapType,
     map_perm: MapPermission,
+    /// what the area is for, only used for debugging
+    name: &'static str,
 }
 
 impl MapArea {
@@ -269,22 +743,27 @@ impl MapArea {
         end_va: VirtAddr,
         map_type: MapType,
         map_perm: MapPermission,
+        name: &'static str,
     ) -> Self {
         let start_vpn: VirtPageNum = start_va.floor();
         let end_vpn: VirtPageNum = end_va.ceil();
         Self {
             vpn_range: VPNRange::new(start_vpn, end_vpn),
             data_frames: BTreeMap::new(),
+            swapped: BTreeMap::new(),
             map_type,
             map_perm,
+            name,
         }
     }
     pub fn from_another(another: &MapArea) -> Self {
         Self {
             vpn_range: VPNRange::new(another.vpn_range.get_start(), another.vpn_range.get_end()),
             data_frames: BTreeMap::new(),
+            swapped: BTreeMap::new(),
             map_type: another.map_type,
             map_perm: another.map_perm,
+            name: another.name,
         }
     }
     pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
@@ -293,11 +772,12 @@ impl MapArea {
             MapType::Identical => {
                 ppn = PhysPageNum(vpn.0);
             }
-            MapType::Framed => {
+            MapType::Framed | MapType::Lazy | MapType::Shared => {
                 let frame = frame_alloc().unwrap();
                 ppn = frame.ppn;
                 self.data_frames.insert(vpn, frame);
             }
+            MapType::Huge => panic!("vpn {:?} is in a megapage", vpn),
         }
         let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
         page_table.map(vpn, ppn, pte_flags);
@@ -306,70 +786,213 @@ impl MapArea {
     pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
         #[allow(clippy::single_match)]
         match self.map_type {
-            MapType::Framed => {
+            MapType::Framed | MapType::Lazy | MapType::Shared => {
                 self.data_frames.remove(&vpn);
             }
             _ => {}
         }
         page_table.unmap(vpn);
     }
+    pub fn is_empty(&self) -> bool {
+        self.vpn_range.get_start() == self.vpn_range.get_end()
+    }
+    /// Split the area at `at`, keeping `[start, at)` and returning `[at, end)`
+    pub fn split_off(&mut self, at: VirtPageNum) -> MapArea {
+        let start = self.vpn_range.get_start();
+        let end = self.vpn_range.get_end();
+        let at = at.max(start).min(end);
+        self.vpn_range = VPNRange::new(start, at);
+        Self {
+            vpn_range: VPNRange::new(at, end),
+            data_frames: self.data_frames.split_off(&at),
+            swapped: self.swapped.split_off(&at),
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+            name: self.name,
+        }
+    }
+    /// Start of each megapage of a huge area
+    fn huge_pages(&self) -> impl Iterator<Item = VirtPageNum> {
+        (self.vpn_range.get_start().0..self.vpn_range.get_end().0)
+            .step_by(HUGE_PAGE_PAGES)
+            .map(VirtPageNum)
+    }
+    pub fn contains(&self, vpn: VirtPageNum) -> bool {
+        self.vpn_range.get_start() <= vpn && vpn < self.vpn_range.get_end()
+    }
+    /// Whether the whole area lies in `[start_vpn, end_vpn)`
+    pub fn within(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        start_vpn <= self.vpn_range.get_start() && self.vpn_range.get_end() <= end_vpn
+    }
+    /// Change the permission of the area. Pages still shared copy-on-write
+    /// keep `W` cleared until they are written, those of a shared area do not.
+    pub fn set_perm(&mut self, page_table: &mut PageTable, permission: MapPermission) {
+        self.map_perm = permission;
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        if self.map_type == MapType::Huge {
+            for vpn in self.huge_pages() {
+                page_table.set_huge_flags(vpn, pte_flags);
+            }
+            return;
+        }
+        let cow_pte_flags = self.cow_pte_flags();
+        for (vpn, frame) in self.data_frames.iter() {
+            if frame.ref_count() > 1 && self.map_type != MapType::Shared {
+                page_table.set_flags(*vpn, cow_pte_flags);
+            } else {
+                page_table.set_flags(*vpn, pte_flags);
+            }
+        }
+    }
+    /// Unmap the pages that have a frame and make the area lazy, so they get
+    /// a fresh zeroed frame on their next access
+    pub fn drop_frames(&mut self, page_table: &mut PageTable) {
+        assert!(matches!(self.map_type, MapType::Framed | MapType::Lazy));
+        for vpn in core::mem::take(&mut self.data_frames).into_keys() {
+            page_table.unmap(vpn);
+        }
+        self.swapped.clear();
+        self.map_type = MapType::Lazy;
+    }
+    /// Pte flags of a page shared copy-on-write: the area permission without `W`
+    fn cow_pte_flags(&self) -> PTEFlags {
+        PTEFlags::from_bits((self.map_perm - MapPermission::W).bits).unwrap()
+    }
+    /// Resolve a store to a copy-on-write page. The frame is duplicated only
+    /// when someone else still holds it, otherwise `W` is simply restored.
+    pub fn copy_on_write(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool {
+        if !self.map_perm.contains(MapPermission::W)
+            || matches!(self.map_type, MapType::Shared | MapType::Huge)
+        {
+            return false;
+        }
+        let frame = match self.data_frames.get(&vpn) {
+            Some(frame) => frame,
+            None => return false,
+        };
+        if frame.ref_count() > 1 {
+            let new_frame = match frame_alloc() {
+                Some(new_frame) => new_frame,
+                None => return false,
+            };
+            new_frame
+                .ppn
+                .get_bytes_array()
+                .copy_from_slice(frame.ppn.get_bytes_array());
+            self.data_frames.insert(vpn, new_frame);
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        page_table.set_ppn_flags(vpn, self.data_frames[&vpn].ppn, pte_flags);
+        true
+    }
     pub fn map(&mut self, page_table: &mut PageTable) {
+        if self.map_type == MapType::Lazy {
+            return;
+        }
+        if self.map_type == MapType::Huge {
+            self.map_result(page_table).unwrap();
+            return;
+        }
         for vpn in self.vpn_range {
             self.map_one(page_table, vpn);
         }
     }
     pub fn unmap(&mut self, page_table: &mut PageTable) {
+        if self.map_type == MapType::Huge {
+            for vpn in self.huge_pages() {
+                // a failed `map_result` may not have reached it
+                if self.data_frames.contains_key(&vpn) {
+                    page_table.unmap_huge(vpn);
+                }
+            }
+            self.data_frames.clear();
+            return;
+        }
         for vpn in self.vpn_range {
+            // pages of a lazy area that were never touched, or that a failed
+            // `map_result` did not reach, have no frame
+            if self.map_type != MapType::Identical && !self.data_frames.contains_key(&vpn) {
+                continue;
+            }
             self.unmap_one(page_table, vpn);
         }
     }
-    /// data: start-aligned but maybe with shorter length
-    /// assume that all frames were cleared before
-    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8]) {
+    /// data: starting `offset` bytes into the first page, maybe shorter
+    /// than the area
+    ///
+    /// The rest of the area is zeroed rather than trusting the frames to be
+    /// clear, it is the bss of an elf segment and may share its last page
+    /// with the data.
+    pub fn copy_data(&mut self, page_table: &mut PageTable, mut data: &[u8], mut offset: usize) {
         assert_eq!(self.map_type, MapType::Framed);
-        let mut start: usize = 0;
-        let mut current_vpn = self.vpn_range.get_start();
-        let len = data.len();
-        loop {
-            let src = &data[start..len.min(start + PAGE_SIZE)];
-            let dst = &mut page_table
-                .translate(current_vpn)
-                .unwrap()
-                .ppn()
-                .get_bytes_array()[..src.len()];
-            dst.copy_from_slice(src);
-            start += PAGE_SIZE;
-            if start >= len {
-                break;
-            }
-            current_vpn.step();
+        for vpn in self.vpn_range {
+            let len = data.len().min(PAGE_SIZE - offset);
+            let dst = page_table.translate(vpn).unwrap().ppn().get_bytes_array();
+            dst.fill(0);
+            dst[offset..offset + len].copy_from_slice(&data[..len]);
+            data = &data[len..];
+            offset = 0;
         }
     }
 }
 
 /// 实现 map/unmap
 impl MemorySet {
-    fn push_result(&mut self, mut map_area: MapArea, data: Option<&[u8]>) -> Result<(), String> {
-        map_area.map_result(&mut self.page_table)?;
-        if let Some(data) = data {
-            map_area.copy_data(&mut self.page_table, data);
-        }
-        self.areas.push(map_area);
-        Ok(())
-    }
-
-    /// Assume that no conflicts.
+    /// Fail without touching the address space if any page in the range
+    /// is already mapped.
     pub fn insert_framed_area_result(
         &mut self,
         start_va: VirtAddr,
         end_va: VirtAddr,
         permission: MapPermission,
+        name: &'static str,
     ) -> Result<(), String> {
-        let area = MapArea::new(start_va, end_va, MapType::Framed, permission);
-        self.push_result(area, None)
+        self.try_map_range(start_va, end_va, MapType::Framed, permission, name)
+            .map(|_| ())
+            .map_err(|err| format!("{:?}", err))
     }
 
-    /// Assume that no conflicts.
+    /// Map the range as a new area and return the number of its pages.
+    /// The call is transactional: on failure every page it has mapped is
+    /// unmapped again, leaving the address space as it was.
+    pub fn try_map_range(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        map_type: MapType,
+        permission: MapPermission,
+        name: &'static str,
+    ) -> Result<usize, MapError> {
+        let mut area = MapArea::new(start_va, end_va, map_type, permission, name);
+        if let Some(vpn) = self.find_mapped(area.vpn_range) {
+            return Err(MapError::Overlap(vpn));
+        }
+        if let Err(err) = area.map_result(&mut self.page_table) {
+            area.unmap(&mut self.page_table);
+            return Err(err);
+        }
+        let pages = area.vpn_range.get_end().0 - area.vpn_range.get_start().0;
+        self.areas.push(area);
+        self.note_rss();
+        Ok(pages)
+    }
+
+    /// Find the first page in `vpn_range` that is covered by an area or
+    /// has a valid pte (e.g. the trampoline), or is the user stack guard page.
+    fn find_mapped(&self, vpn_range: VPNRange) -> Option<VirtPageNum> {
+        vpn_range.into_iter().find(|&vpn| {
+            vpn == user_stack_guard_page()
+                || self.areas.iter().any(|area| area.contains(vpn))
+                || self
+                    .page_table
+                    .translate(vpn)
+                    .map_or(false, |pte| pte.is_valid())
+        })
+    }
+
+    /// Unmap every page in the range, splitting areas that are only partly
+    /// covered. Fail without unmapping anything if some page in the range is
+    /// not mapped.
     pub fn remove_area_result(
         &mut self,
         start_va: VirtAddr,
@@ -377,39 +1000,373 @@ impl MemorySet {
     ) -> Result<(), String> {
         let start_vpn: VirtPageNum = start_va.floor();
         let end_vpn: VirtPageNum = end_va.ceil();
+        let vpn_range = VPNRange::new(start_vpn, end_vpn);
+
+        if let Some(vpn) = vpn_range.into_iter().find(|&vpn| !self.is_mapped(vpn)) {
+            return Err(format!("vpn {:?} is invalid before unmapping", vpn));
+        }
+        self.split_areas(start_vpn, end_vpn)
+            .map_err(|err| format!("{:?}", err))?;
+        let areas = core::mem::take(&mut self.areas);
+        for mut area in areas {
+            if area.within(start_vpn, end_vpn) {
+                area.unmap(&mut self.page_table);
+            } else {
+                self.areas.push(area);
+            }
+        }
+        flush_tlb(vpn_range);
+        Ok(())
+    }
+
+    /// Resize the mapping `[start_va, old_end_va)` in place to end at
+    /// `new_end_va`. Growing extends the area holding its last page, which
+    /// must end there, by fresh zeroed pages of the same kind; it fails if
+    /// any page it needs is taken. Shrinking unmaps the tail. Fail without
+    /// changing anything if some page of the old range is not mapped.
+    pub fn mremap(
+        &mut self,
+        start_va: VirtAddr,
+        old_end_va: VirtAddr,
+        new_end_va: VirtAddr,
+    ) -> Result<(), String> {
+        let start_vpn: VirtPageNum = start_va.floor();
+        let old_end_vpn: VirtPageNum = old_end_va.ceil();
+        let new_end_vpn: VirtPageNum = new_end_va.ceil();
+        let vpn_range = VPNRange::new(start_vpn, old_end_vpn);
+
+        if let Some(vpn) = vpn_range.into_iter().find(|&vpn| !self.is_mapped(vpn)) {
+            return Err(format!("vpn {:?} is invalid before mremap", vpn));
+        }
+        match new_end_vpn.cmp(&old_end_vpn) {
+            core::cmp::Ordering::Less => {
+                return self.remove_area_result(new_end_vpn.into(), old_end_va)
+            }
+            core::cmp::Ordering::Equal => return Ok(()),
+            core::cmp::Ordering::Greater => {}
+        }
+        if let Some(vpn) = self.find_mapped(VPNRange::new(old_end_vpn, new_end_vpn)) {
+            return Err(format!("vpn {:?} is taken", vpn));
+        }
+        let last_vpn = VirtPageNum(old_end_vpn.0 - 1);
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| area.contains(last_vpn))
+            .unwrap();
+        area.grow_result(&mut self.page_table, new_end_vpn)
+            .map_err(|err| format!("{:?}", err))?;
+        self.note_rss();
+        Ok(())
+    }
+
+    /// Free the frames of every page in the range but keep it mapped, the
+    /// pages read as zero again when next touched. Areas only partly covered
+    /// are split, those covered turn lazy. Fail without changing anything if
+    /// some page in the range is not mapped, or is in a shared area or a
+    /// megapage.
+    pub fn madvise_dontneed(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> Result<(), String> {
+        let start_vpn: VirtPageNum = start_va.floor();
+        let end_vpn: VirtPageNum = end_va.ceil();
+        let vpn_range = VPNRange::new(start_vpn, end_vpn);
 
-        if let Some((idx, area)) = self.areas.iter_mut().enumerate().find(|(_, area)| {
-            area.vpn_range.get_start() == start_vpn && area.vpn_range.get_end() == end_vpn
+        if let Some(vpn) = vpn_range.into_iter().find(|&vpn| !self.is_mapped(vpn)) {
+            return Err(format!("vpn {:?} is invalid before madvise", vpn));
+        }
+        // the frames of a shared area are what it shares
+        if let Some(area) = self.areas.iter().find(|area| {
+            matches!(area.map_type, MapType::Shared | MapType::Huge)
+                && vpn_range.into_iter().any(|vpn| area.contains(vpn))
         }) {
-            area.unmap_result(&mut self.page_table)?;
-            self.areas.remove(idx);
-            Ok(())
-        } else {
-            Err("wrong len".to_string())
+            return Err(format!(
+                "{} area {:?} can not drop pages",
+                area.name, area.map_type
+            ));
+        }
+        self.split_areas(start_vpn, end_vpn)
+            .map_err(|err| format!("{:?}", err))?;
+        for area in self.areas.iter_mut() {
+            if area.within(start_vpn, end_vpn) {
+                area.drop_frames(&mut self.page_table);
+            }
+        }
+        flush_tlb(vpn_range);
+        Ok(())
+    }
+
+    /// Change the permission of every page in the range, splitting areas
+    /// that are only partly covered. Fail without changing anything if some
+    /// page in the range is not mapped.
+    pub fn mprotect(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) -> Result<(), String> {
+        let start_vpn: VirtPageNum = start_va.floor();
+        let end_vpn: VirtPageNum = end_va.ceil();
+        let vpn_range = VPNRange::new(start_vpn, end_vpn);
+
+        if let Some(vpn) = vpn_range.into_iter().find(|&vpn| !self.is_mapped(vpn)) {
+            return Err(format!("vpn {:?} is invalid before mprotect", vpn));
+        }
+        self.split_areas(start_vpn, end_vpn)
+            .map_err(|err| format!("{:?}", err))?;
+        for area in self.areas.iter_mut() {
+            if area.within(start_vpn, end_vpn) {
+                area.set_perm(&mut self.page_table, permission);
+            }
+        }
+        flush_tlb(vpn_range);
+        Ok(())
+    }
+
+    /// Split the areas crossing `start_vpn` or `end_vpn`, so that each area
+    /// lies either inside or outside `[start_vpn, end_vpn)`. A megapage
+    /// crossing them is mapped with 4KiB pages first, which only fails if
+    /// there is no frame for the new page table.
+    fn split_areas(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+    ) -> Result<(), MapError> {
+        self.split_huge_page(start_vpn)?;
+        self.split_huge_page(end_vpn)?;
+        let areas = core::mem::take(&mut self.areas);
+        for mut area in areas {
+            let tail = area.split_off(end_vpn);
+            let middle = area.split_off(start_vpn);
+            for area in [area, middle, tail] {
+                if !area.is_empty() {
+                    self.areas.push(area);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn the megapage containing `vpn` into a framed area of 4KiB pages
+    /// on the same frames, unless `vpn` is its start. Then areas can be
+    /// split at `vpn`.
+    fn split_huge_page(&mut self, vpn: VirtPageNum) -> Result<(), MapError> {
+        let huge_vpn = huge_page_of(vpn);
+        if huge_vpn == vpn {
+            return Ok(());
+        }
+        let idx = match self
+            .areas
+            .iter()
+            .position(|area| area.map_type == MapType::Huge && area.contains(vpn))
+        {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+        self.page_table.split_huge(huge_vpn)?;
+        let mut area = self.areas.remove(idx);
+        let tail = area.split_off(VirtPageNum(huge_vpn.0 + HUGE_PAGE_PAGES));
+        let mut middle = area.split_off(huge_vpn);
+        middle.map_type = MapType::Framed;
+        for area in [area, middle, tail] {
+            if !area.is_empty() {
+                self.areas.push(area);
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of pages the areas hold a frame for, shared or not
+    fn resident_pages(&self) -> usize {
+        self.areas.iter().map(|area| area.data_frames.len()).sum()
+    }
+
+    /// Raise `max_rss` to the pages resident now, after frames are added
+    fn note_rss(&mut self) {
+        self.max_rss = self.max_rss.max(self.resident_pages());
+    }
+
+    /// The most pages resident at once, what `resident_pages` was at its
+    /// highest
+    pub fn max_rss(&self) -> usize {
+        self.max_rss
+    }
+
+    /// Number of pages present in the page table whose frame no other
+    /// address space maps
+    pub fn rss_pages(&self) -> usize {
+        self.present_pages().filter(|&shared| !shared).count()
+    }
+
+    /// Number of pages present in the page table whose frame another address
+    /// space maps as well, copy-on-write after fork or through a shared area
+    pub fn shared_pages(&self) -> usize {
+        self.present_pages().filter(|&shared| shared).count()
+    }
+
+    /// Whether the frame of each page present in the page table is shared
+    fn present_pages(&self) -> impl Iterator<Item = bool> + '_ {
+        self.areas.iter().flat_map(move |area| {
+            area.vpn_range
+                .into_iter()
+                .filter(move |&vpn| {
+                    self.page_table
+                        .translate(vpn)
+                        .map_or(false, |pte| pte.is_valid())
+                })
+                .map(move |vpn| {
+                    area.data_frames
+                        .get(&vpn)
+                        .map_or(false, |frame| frame.ref_count() > 1)
+                })
+        })
+    }
+
+    /// Number of frames holding the page table
+    pub fn page_table_frames(&self) -> usize {
+        self.page_table.frame_count()
+    }
+
+    /// Whether some area covers `vpn`, whether or not its frame is there yet
+    pub fn in_area(&self, vpn: VirtPageNum) -> bool {
+        self.areas.iter().any(|area| area.contains(vpn))
+    }
+
+    /// Number of pages the areas cover, whether their frames are there or not
+    pub fn mapped_pages(&self) -> usize {
+        self.areas
+            .iter()
+            .map(|area| area.vpn_range.get_end().0 - area.vpn_range.get_start().0)
+            .sum()
+    }
+
+    /// Number of distinct mapped areas
+    pub fn area_count(&self) -> usize {
+        self.areas.len()
+    }
+
+    /// One line `start-end perm name` per area, sorted by address
+    pub fn dump(&self) -> String {
+        let mut areas: Vec<&MapArea> = self.areas.iter().collect();
+        areas.sort_by_key(|area| area.vpn_range.get_start());
+        let mut dump = String::new();
+        for area in areas {
+            let start: VirtAddr = area.vpn_range.get_start().into();
+            let end: VirtAddr = area.vpn_range.get_end().into();
+            let perm = area.map_perm;
+            dump += &format!(
+                "{:#x}-{:#x} {}{}{}{} {}\n",
+                start.0,
+                end.0,
+                if perm.contains(MapPermission::R) {
+                    'r'
+                } else {
+                    '-'
+                },
+                if perm.contains(MapPermission::W) {
+                    'w'
+                } else {
+                    '-'
+                },
+                if perm.contains(MapPermission::X) {
+                    'x'
+                } else {
+                    '-'
+                },
+                if perm.contains(MapPermission::U) {
+                    'u'
+                } else {
+                    '-'
+                },
+                area.name,
+            );
+        }
+        dump
+    }
+
+    /// Whether `vpn` is backed by a frame of some framed user area, or lies
+    /// in a lazy area where the frame will be allocated on demand. Kernel
+    /// pages like the trap context and the trampoline never count, so user
+    /// space can not unmap or mprotect them.
+    fn is_mapped(&self, vpn: VirtPageNum) -> bool {
+        match self
+            .areas
+            .iter()
+            .find(|area| area.map_perm.contains(MapPermission::U) && area.contains(vpn))
+        {
+            Some(area) if area.map_type == MapType::Lazy => true,
+            Some(area) if area.map_type != MapType::Identical => self
+                .page_table
+                .translate(vpn)
+                .map_or(false, |pte| pte.is_valid() && pte.is_user()),
+            _ => false,
         }
     }
 }
 
 /// 实现 map/unmap
 impl MapArea {
+    /// Unlike `map_one`, running out of frames is an error. The frame is
+    /// only kept when the page has been mapped, and it is zeroed already.
     pub fn map_one_result(
         &mut self,
         page_table: &mut PageTable,
         vpn: VirtPageNum,
-    ) -> Result<(), String> {
-        let ppn: PhysPageNum;
+    ) -> Result<(), MapError> {
         match self.map_type {
-            MapType::Identical => {
-                return Err("invalid map type".to_string());
-            }
-            MapType::Framed => {
-                let frame = frame_alloc().unwrap();
-                ppn = frame.ppn;
+            MapType::Identical | MapType::Huge => Err(MapError::InvalidType),
+            MapType::Framed | MapType::Lazy | MapType::Shared => {
+                let frame = frame_alloc().ok_or(MapError::NoFrame(vpn))?;
+                let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+                page_table.map_result(vpn, frame.ppn, pte_flags)?;
                 self.data_frames.insert(vpn, frame);
+                Ok(())
             }
         }
-        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
-        page_table.map_result(vpn, ppn, pte_flags)
+    }
+
+    /// Give the page `vpn` of a lazy area a frame, with its contents read
+    /// back from the swap area if it is swapped out. The pte is marked
+    /// accessed, so the page is not the next to be swapped out.
+    fn fault_in_lazy(
+        &mut self,
+        page_table: &mut PageTable,
+        vpn: VirtPageNum,
+    ) -> Result<(), MapError> {
+        let frame = frame_alloc().ok_or(MapError::NoFrame(vpn))?;
+        if let Some(slot) = self.swapped.get(&vpn) {
+            slot.read(frame.ppn);
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap() | PTEFlags::A;
+        page_table.map_result(vpn, frame.ppn, pte_flags)?;
+        self.swapped.remove(&vpn);
+        self.data_frames.insert(vpn, frame);
+        Ok(())
+    }
+
+    /// Extend the area up to `end_vpn`, mapping the new pages like
+    /// `map_result`. On failure the pages mapped so far are unmapped again
+    /// and the area keeps its end.
+    pub fn grow_result(
+        &mut self,
+        page_table: &mut PageTable,
+        end_vpn: VirtPageNum,
+    ) -> Result<(), MapError> {
+        let old_end_vpn = self.vpn_range.get_end();
+        match self.map_type {
+            MapType::Identical | MapType::Huge => return Err(MapError::InvalidType),
+            MapType::Lazy => {}
+            MapType::Framed | MapType::Shared => {
+                for vpn in VPNRange::new(old_end_vpn, end_vpn) {
+                    if let Err(err) = self.map_one_result(page_table, vpn) {
+                        for vpn in VPNRange::new(old_end_vpn, vpn) {
+                            self.unmap_one(page_table, vpn);
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), end_vpn);
+        Ok(())
     }
 
     #[allow(unused)]
@@ -420,7 +1377,7 @@ impl MapArea {
     ) -> Result<(), String> {
         #[allow(clippy::single_match)]
         match self.map_type {
-            MapType::Framed => {
+            MapType::Framed | MapType::Lazy | MapType::Shared => {
                 self.data_frames.remove(&vpn);
             }
             _ => return Err("invalid map type".to_string()),
@@ -428,7 +1385,33 @@ impl MapArea {
         page_table.unmap_result(vpn)
     }
 
-    pub fn map_result(&mut self, page_table: &mut PageTable) -> Result<(), String> {
+    /// Map the megapage at `vpn` to contiguous frames. The frames are kept
+    /// page by page, so once `PageTable::split_huge` has remapped them with
+    /// 4KiB ptes the range is an ordinary framed one.
+    fn map_huge_result(
+        &mut self,
+        page_table: &mut PageTable,
+        vpn: VirtPageNum,
+    ) -> Result<(), MapError> {
+        let frames = frame_alloc_contiguous(HUGE_PAGE_PAGES).ok_or(MapError::NoFrame(vpn))?;
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        page_table.map_huge_result(vpn, frames[0].ppn, pte_flags)?;
+        for (i, frame) in frames.into_iter().enumerate() {
+            self.data_frames.insert(VirtPageNum(vpn.0 + i), frame);
+        }
+        Ok(())
+    }
+
+    pub fn map_result(&mut self, page_table: &mut PageTable) -> Result<(), MapError> {
+        if self.map_type == MapType::Lazy {
+            return Ok(());
+        }
+        if self.map_type == MapType::Huge {
+            for vpn in self.huge_pages() {
+                self.map_huge_result(page_table, vpn)?;
+            }
+            return Ok(());
+        }
         for vpn in self.vpn_range {
             self.map_one_result(page_table, vpn)?;
         }
@@ -444,11 +1427,28 @@ impl MapArea {
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// why mapping a range failed
+pub enum MapError {
+    /// the page is mapped already
+    Overlap(VirtPageNum),
+    /// no frame left for the page or its page table
+    NoFrame(VirtPageNum),
+    /// only framed areas can be mapped this way
+    InvalidType,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
-/// map type for memory set: identical or framed
+/// map type for memory set: identical, framed, lazy, shared or huge
 pub enum MapType {
     Identical,
     Framed,
+    /// framed, but each frame is allocated on the first access to its page
+    Lazy,
+    /// framed, and the frames stay shared with the child after fork
+    Shared,
+    /// framed with megapages, the range is aligned to `HUGE_PAGE_SIZE`
+    Huge,
 }
 
 bitflags! {
@@ -484,3 +1484,123 @@ pub fn remap_test() {
         .executable());
     info!("remap_test passed!");
 }
+
+/// A crafted elf whose segments start in the middle of pages, the second
+/// one in the last page of the first one
+fn crafted_elf() -> Vec<u8> {
+    // (vaddr, file offset, file size, mem size, flags: X = 1, W = 2, R = 4)
+    let segments: [(usize, usize, usize, usize, u32); 2] = [
+        (0x1080, 0x100, 0x100, 0x100, 5),
+        (0x1f00, 0x200, 0x80, 0x1200, 6),
+    ];
+    let mut elf = vec![0u8; 0x280];
+    elf[..4].copy_from_slice(&[0x7f, b'E', b'L', b'F']);
+    // 64 bit, little endian, version 1
+    elf[4..7].copy_from_slice(&[2, 1, 1]);
+    // executable for riscv
+    elf[16..18].copy_from_slice(&2u16.to_le_bytes());
+    elf[18..20].copy_from_slice(&0xf3u16.to_le_bytes());
+    elf[20..24].copy_from_slice(&1u32.to_le_bytes());
+    elf[24..32].copy_from_slice(&0x1080u64.to_le_bytes());
+    // program headers right after the header
+    elf[32..40].copy_from_slice(&64u64.to_le_bytes());
+    elf[52..54].copy_from_slice(&64u16.to_le_bytes());
+    elf[54..56].copy_from_slice(&56u16.to_le_bytes());
+    elf[56..58].copy_from_slice(&(segments.len() as u16).to_le_bytes());
+    elf[58..60].copy_from_slice(&64u16.to_le_bytes());
+    for (i, (vaddr, offset, file_size, mem_size, flags)) in segments.iter().enumerate() {
+        let ph = &mut elf[64 + i * 56..64 + (i + 1) * 56];
+        // PT_LOAD
+        ph[0..4].copy_from_slice(&1u32.to_le_bytes());
+        ph[4..8].copy_from_slice(&flags.to_le_bytes());
+        ph[8..16].copy_from_slice(&(*offset as u64).to_le_bytes());
+        ph[16..24].copy_from_slice(&(*vaddr as u64).to_le_bytes());
+        ph[24..32].copy_from_slice(&(*vaddr as u64).to_le_bytes());
+        ph[32..40].copy_from_slice(&(*file_size as u64).to_le_bytes());
+        ph[40..48].copy_from_slice(&(*mem_size as u64).to_le_bytes());
+        ph[48..56].copy_from_slice(&(PAGE_SIZE as u64).to_le_bytes());
+    }
+    elf[0x100..0x200].fill(0x11);
+    elf[0x200..0x280].fill(0x22);
+    elf
+}
+
+/// Load the crafted elf
+#[allow(unused)]
+pub fn elf_load_test() {
+    let (memory_set, _, entry_point) = MemorySet::from_elf(&crafted_elf());
+    assert_eq!(entry_point, 0x1080);
+    assert!(memory_set
+        .translate(VirtPageNum(0))
+        .map_or(true, |pte| !pte.is_valid()));
+    // the shared page has the permissions of both segments, but W^X
+    let pte = memory_set.translate(VirtPageNum(1)).unwrap();
+    assert!(pte.readable() && pte.writable() && !pte.executable() && pte.is_user());
+    let page = pte.ppn().get_bytes_array();
+    assert!(page[..0x80].iter().all(|b| *b == 0));
+    assert!(page[0x80..0x180].iter().all(|b| *b == 0x11));
+    assert!(page[0x180..0xf00].iter().all(|b| *b == 0));
+    assert!(page[0xf00..0xf80].iter().all(|b| *b == 0x22));
+    assert!(page[0xf80..].iter().all(|b| *b == 0));
+    // the rest of the second segment is bss
+    for vpn in 2..4 {
+        let pte = memory_set.translate(VirtPageNum(vpn)).unwrap();
+        assert!(pte.writable() && !pte.executable());
+        assert!(pte.ppn().get_bytes_array().iter().all(|b| *b == 0));
+    }
+    assert!(memory_set
+        .translate(VirtPageNum(4))
+        .map_or(true, |pte| !pte.is_valid()));
+    assert_eq!(memory_set.program_brk(), 0x4000);
+    info!("elf_load_test passed!");
+}
+
+/// Run the address space operations on the crafted elf that allocate
+/// frames, each must give all of them back
+#[allow(unused)]
+pub fn frame_leak_test() {
+    let elf = crafted_elf();
+    let rw = MapPermission::R | MapPermission::W | MapPermission::U;
+    let start_va = VirtAddr::from(0x10000000);
+    let end_va = VirtAddr::from(0x10000000 + PAGE_SIZE * 4);
+    check_frame_leaks("elf load", || {
+        let (memory_set, _, _) = MemorySet::from_elf(&elf);
+        drop(memory_set);
+    });
+    check_frame_leaks("mmap and munmap", || {
+        let (mut memory_set, _, _) = MemorySet::from_elf(&elf);
+        memory_set
+            .try_map_range(start_va, end_va, MapType::Framed, rw, "mmap")
+            .unwrap();
+        memory_set
+            .mremap(start_va, end_va, (end_va.0 + PAGE_SIZE * 4).into())
+            .unwrap();
+        memory_set.madvise_dontneed(start_va, end_va).unwrap();
+        assert!(memory_set.lazy_alloc(start_va.floor()));
+        memory_set
+            .remove_area_result(start_va, (end_va.0 + PAGE_SIZE * 4).into())
+            .unwrap();
+        memory_set
+            .try_map_range(start_va, end_va, MapType::Lazy, rw, "mmap")
+            .unwrap();
+        assert!(memory_set.lazy_alloc(start_va.floor()));
+        memory_set.remove_area_result(start_va, end_va).unwrap();
+        drop(memory_set);
+    });
+    check_frame_leaks("fork", || {
+        let (mut parent, _, _) = MemorySet::from_elf(&elf);
+        parent
+            .try_map_range(start_va, end_va, MapType::Framed, rw, "mmap")
+            .unwrap();
+        let mut child = MemorySet::try_from_existed_user(&mut parent).unwrap();
+        // a private copy for each, then the same in the other order
+        assert!(child.copy_on_write(start_va.floor()));
+        assert!(parent.copy_on_write(start_va.floor()));
+        let vpn = VirtPageNum(start_va.floor().0 + 1);
+        assert!(parent.copy_on_write(vpn));
+        drop(parent);
+        assert!(child.copy_on_write(vpn));
+        drop(child);
+    });
+    info!("frame_leak_test passed!");
+}
@@ -11,17 +11,31 @@ mod frame_allocator;
 mod heap_allocator;
 mod memory_set;
 mod page_table;
+mod swap;
 
 pub use address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use address::{StepByOne, VPNRange};
 use alloc::string::String;
-pub use frame_allocator::{frame_alloc, FrameTracker};
-pub use memory_set::remap_test;
-pub use memory_set::{MapPermission, MemorySet, KERNEL_SPACE};
-pub use page_table::{translated_byte_buffer, translated_refmut, translated_str, PageTableEntry};
+use frame_allocator::frame_alloc_contiguous;
+pub use frame_allocator::{
+    check_frame_leaks, frame_alloc, frame_usage, FrameLeakGuard, FrameTracker,
+};
+pub use memory_set::{elf_load_test, frame_leak_test, remap_test};
+pub use memory_set::{
+    thread_stack_top, thread_trap_cx_va, user_stack_guard_page, MapError, MapPermission, MapType,
+    MemorySet, KERNEL_SPACE,
+};
+pub use page_table::{
+    copy_bytes_to_user, copy_from_user, copy_to_user, translated_foreign_buffer, translated_str,
+    translated_user_buffer, try_translated_refmut, PageTableEntry, StrError, UserBuffer,
+};
 use page_table::{PTEFlags, PageTable};
+pub use swap::swap_usage;
+use swap::SwapSlot;
 
-use crate::task::current_task;
+use crate::sync::UPSafeCell;
+use crate::task::{current_task, list_tasks};
+use alloc::sync::Arc;
 
 /// initiate heap allocator, frame allocator and kernel space
 pub fn init() {
@@ -30,20 +44,120 @@ pub fn init() {
     KERNEL_SPACE.exclusive_access().activate();
 }
 
+/// The address space of the current task, shared with its threads
+fn current_memory_set() -> Arc<UPSafeCell<MemorySet>> {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .clone()
+}
+
+/// Keep the page at `va` of the current task in memory until the syscall in
+/// progress returns, the kernel is about to use its frame
+fn pin_user_page(va: VirtAddr) {
+    if let Some(task) = current_task() {
+        task.inner_exclusive_access().pin_user_page(va.floor());
+    }
+}
+
+/// Swap out a page of some task to free a frame, return false if there is
+/// none. Address spaces borrowed right now are skipped, the one that ran out
+/// of frames falls back on its own pages.
+fn reclaim_frame() -> bool {
+    list_tasks().iter().any(|task| {
+        let memory_set = match task.try_inner_exclusive_access() {
+            Some(inner) => inner.memory_set.clone(),
+            None => return false,
+        };
+        let swapped = match memory_set.try_exclusive_access() {
+            Some(mut memory_set) => memory_set.swap_out_one(),
+            None => false,
+        };
+        swapped
+    })
+}
+
+/// Map a new range for the current task. Its pages read as zero: each frame
+/// is cleared by `frame_alloc` before its pte is installed, for lazy pages on
+/// the first access.
 pub fn memeory_map(
     start_va: VirtAddr,
     end_va: VirtAddr,
+    map_type: MapType,
     map_perm: MapPermission,
-) -> Result<(), String> {
-    let current_task = current_task().unwrap();
-    let mut inner = current_task.inner_exclusive_access();
-    inner
-        .memory_set
-        .insert_framed_area_result(start_va, end_va, map_perm)
+    name: &'static str,
+) -> Result<usize, MapError> {
+    current_memory_set()
+        .exclusive_access()
+        .try_map_range(start_va, end_va, map_type, map_perm, name)
 }
 
 pub fn memeory_unmap(start_va: VirtAddr, end_va: VirtAddr) -> Result<(), String> {
-    let current_task = current_task().unwrap();
-    let mut inner = current_task.inner_exclusive_access();
-    inner.memory_set.remove_area_result(start_va, end_va)
+    current_memory_set()
+        .exclusive_access()
+        .remove_area_result(start_va, end_va)
+}
+
+/// Resize a mapping of the current task in place, see `MemorySet::mremap`
+pub fn memeory_remap(
+    start_va: VirtAddr,
+    old_end_va: VirtAddr,
+    new_end_va: VirtAddr,
+) -> Result<(), String> {
+    current_memory_set()
+        .exclusive_access()
+        .mremap(start_va, old_end_va, new_end_va)
+}
+
+pub fn memeory_madvise_dontneed(start_va: VirtAddr, end_va: VirtAddr) -> Result<(), String> {
+    current_memory_set()
+        .exclusive_access()
+        .madvise_dontneed(start_va, end_va)
+}
+
+/// Whether the current address space rejects a len that is not a whole
+/// number of pages, and set it to `strict` returning the previous setting
+pub fn mmap_strict_len() -> bool {
+    current_memory_set().exclusive_access().strict_len()
+}
+
+pub fn set_mmap_strict_len(strict: bool) -> bool {
+    current_memory_set()
+        .exclusive_access()
+        .set_strict_len(strict)
+}
+
+pub fn memeory_protect(
+    start_va: VirtAddr,
+    end_va: VirtAddr,
+    map_perm: MapPermission,
+) -> Result<(), String> {
+    current_memory_set()
+        .exclusive_access()
+        .mprotect(start_va, end_va, map_perm)
+}
+
+/// Resolve a store to a copy-on-write page of the current task,
+/// return false if `va` is not such a page.
+pub fn copy_on_write(va: VirtAddr) -> bool {
+    current_memory_set()
+        .exclusive_access()
+        .copy_on_write(va.floor())
+}
+
+/// Grow the user stack of the current task down to `va`,
+/// return false if `va` is too far below the stack.
+pub fn grow_user_stack(va: VirtAddr) -> bool {
+    current_memory_set()
+        .exclusive_access()
+        .grow_user_stack(va.floor())
+}
+
+/// Allocate the frame of a lazy page of the current task on its first access,
+/// return false if `va` is not such a page.
+pub fn lazy_alloc(va: VirtAddr) -> bool {
+    current_memory_set()
+        .exclusive_access()
+        .lazy_alloc(va.floor())
 }
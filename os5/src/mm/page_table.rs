@@ -1,6 +1,9 @@
 //! Implementation of [`PageTableEntry`] and [`PageTable`].
 
-use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use super::{
+    copy_on_write, frame_alloc, grow_user_stack, lazy_alloc, pin_user_page, FrameTracker, MapError,
+    PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum,
+};
 use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::{format, vec};
@@ -54,6 +57,15 @@ impl PageTableEntry {
     pub fn executable(&self) -> bool {
         (self.flags() & PTEFlags::X) != PTEFlags::empty()
     }
+    pub fn is_user(&self) -> bool {
+        (self.flags() & PTEFlags::U) != PTEFlags::empty()
+    }
+    /// A valid pte with any of `R W X` maps a page, otherwise it points to
+    /// the next level of the page table
+    pub fn is_leaf(&self) -> bool {
+        self.is_valid()
+            && (self.flags() & (PTEFlags::R | PTEFlags::W | PTEFlags::X)) != PTEFlags::empty()
+    }
 }
 
 /// page table structure
@@ -62,14 +74,18 @@ pub struct PageTable {
     frames: Vec<FrameTracker>,
 }
 
-/// Assume that it won't oom when creating/mapping.
+/// Assume that it won't oom when creating/mapping, except for `map_result`.
 impl PageTable {
     pub fn new() -> Self {
-        let frame = frame_alloc().unwrap();
-        PageTable {
+        Self::try_new().unwrap()
+    }
+    /// None if there is no frame for the root page table.
+    pub fn try_new() -> Option<Self> {
+        let frame = frame_alloc()?;
+        Some(PageTable {
             root_ppn: frame.ppn,
             frames: vec![frame],
-        }
+        })
     }
     /// Temporarily used to get arguments from user space.
     pub fn from_token(satp: usize) -> Self {
@@ -79,40 +95,49 @@ impl PageTable {
         }
     }
     fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
-        let mut idxs = vpn.indexes();
+        self.find_pte_create_at(vpn, 2)
+    }
+    /// The pte at `level` on the way to `vpn`, level 1 maps megapages.
+    /// None if there is no frame for a page table or a megapage is in the way.
+    fn find_pte_create_at(
+        &mut self,
+        vpn: VirtPageNum,
+        level: usize,
+    ) -> Option<&mut PageTableEntry> {
+        let idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
-        let mut result: Option<&mut PageTableEntry> = None;
-        for (i, idx) in idxs.iter_mut().enumerate() {
+        for (i, idx) in idxs.iter().enumerate() {
             let pte = &mut ppn.get_pte_array()[*idx];
-            if i == 2 {
-                result = Some(pte);
-                break;
+            if i == level {
+                return Some(pte);
+            }
+            if pte.is_leaf() {
+                return None;
             }
             if !pte.is_valid() {
-                let frame = frame_alloc().unwrap();
+                let frame = frame_alloc()?;
                 *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
                 self.frames.push(frame);
             }
             ppn = pte.ppn();
         }
-        result
+        None
     }
-    fn find_pte(&self, vpn: VirtPageNum) -> Option<&PageTableEntry> {
+    /// The leaf pte mapping `vpn` and its level, or the empty one at level 2
+    fn find_pte(&self, vpn: VirtPageNum) -> Option<(&PageTableEntry, usize)> {
         let idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
-        let mut result: Option<&PageTableEntry> = None;
         for (i, idx) in idxs.iter().enumerate() {
             let pte = &ppn.get_pte_array()[*idx];
-            if i == 2 {
-                result = Some(pte);
-                break;
+            if i == 2 || pte.is_leaf() {
+                return Some((pte, i));
             }
             if !pte.is_valid() {
                 return None;
             }
             ppn = pte.ppn();
         }
-        result
+        None
     }
     #[allow(unused)]
     pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
@@ -126,11 +151,32 @@ impl PageTable {
         assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
         *pte = PageTableEntry::empty();
     }
+    /// Change the flags of a mapped page, keeping its frame
+    pub fn set_flags(&mut self, vpn: VirtPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(
+            pte.is_valid(),
+            "vpn {:?} is invalid before setting flags",
+            vpn
+        );
+        *pte = PageTableEntry::new(pte.ppn(), flags | PTEFlags::V);
+    }
+    /// Point a mapped page at another frame with new flags
+    pub fn set_ppn_flags(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before remapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
+    /// The pte of the 4KiB page `vpn`. A page inside a megapage gets the
+    /// flags of the megapage and its own frame within it.
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.find_pte(vpn).copied()
+        self.find_pte(vpn).map(|(pte, level)| {
+            let offset = vpn.0 & ((1usize << (9 * (2 - level))) - 1);
+            PageTableEntry::new((pte.ppn().0 + offset).into(), pte.flags())
+        })
     }
     pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
-        self.find_pte(va.clone().floor()).map(|pte| {
+        self.translate(va.clone().floor()).map(|pte| {
             //println!("translate_va:va = {:?}", va);
             let aligned_pa: PhysAddr = pte.ppn().into();
             //println!("translate_va:pa_align = {:?}", aligned_pa);
@@ -142,6 +188,16 @@ impl PageTable {
     pub fn token(&self) -> usize {
         8usize << 60 | self.root_ppn.0
     }
+    /// Number of frames holding the page table itself
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    /// Free every frame of the page table but the root, which is cleared, so
+    /// nothing is mapped any more. The token stays the same.
+    pub fn release_tables(&mut self) {
+        self.root_ppn.get_pte_array().fill(PageTableEntry::empty());
+        self.frames.truncate(1);
+    }
 }
 
 /// map/unmap
@@ -151,15 +207,72 @@ impl PageTable {
         vpn: VirtPageNum,
         ppn: PhysPageNum,
         flags: PTEFlags,
-    ) -> Result<(), String> {
-        let pte = self.find_pte_create(vpn).unwrap();
+    ) -> Result<(), MapError> {
+        let pte = self.find_pte_create(vpn).ok_or(MapError::NoFrame(vpn))?;
         if pte.is_valid() {
-            return Err(format!("vpn {:?} is mapped before mapping", vpn));
+            return Err(MapError::Overlap(vpn));
         }
         *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
         Ok(())
     }
 
+    /// Map the megapage at `vpn`, aligned to `HUGE_PAGE_SIZE`, with a leaf
+    /// pte at level 1. `ppn` must be aligned to the megapage as well.
+    pub fn map_huge_result(
+        &mut self,
+        vpn: VirtPageNum,
+        ppn: PhysPageNum,
+        flags: PTEFlags,
+    ) -> Result<(), MapError> {
+        let pte = self
+            .find_pte_create_at(vpn, 1)
+            .ok_or(MapError::NoFrame(vpn))?;
+        if pte.is_valid() {
+            return Err(MapError::Overlap(vpn));
+        }
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        Ok(())
+    }
+
+    pub fn unmap_huge(&mut self, vpn: VirtPageNum) {
+        let pte = self.find_pte_create_at(vpn, 1).unwrap();
+        assert!(
+            pte.is_leaf(),
+            "vpn {:?} is not a megapage before unmapping",
+            vpn
+        );
+        *pte = PageTableEntry::empty();
+    }
+
+    /// Change the flags of the megapage at `vpn`, keeping its frames
+    pub fn set_huge_flags(&mut self, vpn: VirtPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_create_at(vpn, 1).unwrap();
+        assert!(
+            pte.is_leaf(),
+            "vpn {:?} is not a megapage before setting flags",
+            vpn
+        );
+        *pte = PageTableEntry::new(pte.ppn(), flags | PTEFlags::V);
+    }
+
+    /// Map the megapage at `vpn` with 4KiB ptes to the same frames instead,
+    /// so that its pages can be changed one by one.
+    pub fn split_huge(&mut self, vpn: VirtPageNum) -> Result<(), MapError> {
+        let frame = frame_alloc().ok_or(MapError::NoFrame(vpn))?;
+        let pte = self.find_pte_create_at(vpn, 1).unwrap();
+        assert!(
+            pte.is_leaf(),
+            "vpn {:?} is not a megapage before splitting",
+            vpn
+        );
+        for (i, page_pte) in frame.ppn.get_pte_array().iter_mut().enumerate() {
+            *page_pte = PageTableEntry::new((pte.ppn().0 + i).into(), pte.flags());
+        }
+        *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
+        self.frames.push(frame);
+        Ok(())
+    }
+
     pub fn unmap_result(&mut self, vpn: VirtPageNum) -> Result<(), String> {
         let pte = self.find_pte_create(vpn).unwrap();
         if !pte.is_valid() {
@@ -170,14 +283,164 @@ impl PageTable {
     }
 }
 
-/// translate a pointer to a mutable u8 Vec through page table
-pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+/// The kernel accesses user memory through the physical frames, so a page
+/// has to be faulted in here just like the page fault handler would do:
+/// allocate it if it is lazy or just below the stack and split it if it is
+/// copy-on-write and `write`.
+/// Then tell whether user space may read the page, or write it if `write`.
+/// Such a page is pinned until the syscall returns, so that its frame is not
+/// swapped out while the kernel uses it.
+fn fault_in_user_page(page_table: &PageTable, va: VirtAddr, write: bool) -> bool {
+    match page_table.translate(va.floor()) {
+        Some(pte) if pte.is_valid() => {
+            if write && !pte.writable() {
+                copy_on_write(va);
+            }
+        }
+        _ => {
+            if !lazy_alloc(va) {
+                grow_user_stack(va);
+            }
+        }
+    }
+    let accessible = page_table.translate(va.floor()).map_or(false, |pte| {
+        pte.is_valid()
+            && pte.is_user()
+            && if write {
+                pte.writable()
+            } else {
+                pte.readable()
+            }
+    });
+    if accessible {
+        pin_user_page(va);
+    }
+    accessible
+}
+
+/// Translate a user buffer to the slices of the frames holding it, None if
+/// some page is not accessible to user space. The pages are faulted in for
+/// writing if `write`.
+pub fn translated_user_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    write: bool,
+) -> Option<UserBuffer> {
+    translated_buffer(token, ptr, len, write).map(UserBuffer::new)
+}
+
+/// Array of u8 slice that user communicate with os
+pub struct UserBuffer {
+    /// U8 vec
+    pub buffers: Vec<&'static mut [u8]>,
+}
+
+impl UserBuffer {
+    /// Create a `UserBuffer` by parameter
+    pub fn new(buffers: Vec<&'static mut [u8]>) -> Self {
+        Self { buffers }
+    }
+    /// Length of `UserBuffer`
+    pub fn len(&self) -> usize {
+        self.buffers.iter().map(|buffer| buffer.len()).sum()
+    }
+}
+
+impl IntoIterator for UserBuffer {
+    type Item = *mut u8;
+    type IntoIter = UserBufferIterator;
+    fn into_iter(self) -> Self::IntoIter {
+        UserBufferIterator {
+            buffers: self.buffers,
+            current_buffer: 0,
+            current_idx: 0,
+        }
+    }
+}
+
+/// Iterator of `UserBuffer`, yielding a pointer to each byte in turn
+pub struct UserBufferIterator {
+    buffers: Vec<&'static mut [u8]>,
+    current_buffer: usize,
+    current_idx: usize,
+}
+
+impl Iterator for UserBufferIterator {
+    type Item = *mut u8;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_buffer >= self.buffers.len() {
+            None
+        } else {
+            let r = &mut self.buffers[self.current_buffer][self.current_idx] as *mut _;
+            if self.current_idx + 1 == self.buffers[self.current_buffer].len() {
+                self.current_idx = 0;
+                self.current_buffer += 1;
+            } else {
+                self.current_idx += 1;
+            }
+            Some(r)
+        }
+    }
+}
+
+/// Copy `value` to `ptr` in user space byte by byte, so the destination may
+/// straddle a page boundary. Return false without writing anything if some
+/// byte of the destination is not writable by user space.
+pub fn copy_to_user<T>(token: usize, ptr: *mut T, value: &T) -> bool {
+    let src = unsafe {
+        core::slice::from_raw_parts(value as *const T as *const u8, core::mem::size_of::<T>())
+    };
+    copy_bytes_to_user(token, ptr as *mut u8, src)
+}
+
+/// Like `copy_to_user`, for a byte slice.
+pub fn copy_bytes_to_user(token: usize, ptr: *mut u8, src: &[u8]) -> bool {
+    let buffers = match translated_buffer(token, ptr as *const u8, src.len(), true) {
+        Some(buffers) => buffers,
+        None => return false,
+    };
+    let mut start = 0;
+    for dst in buffers {
+        dst.copy_from_slice(&src[start..start + dst.len()]);
+        start += dst.len();
+    }
+    true
+}
+
+/// Read a `T` from `ptr` in user space byte by byte, None if some byte of
+/// the source is not readable by user space.
+pub fn copy_from_user<T: Copy>(token: usize, ptr: *const T) -> Option<T> {
+    let mut value = core::mem::MaybeUninit::<T>::uninit();
+    let dst = unsafe {
+        core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, core::mem::size_of::<T>())
+    };
+    let mut start = 0;
+    for src in translated_buffer(token, ptr as *const u8, dst.len(), false)? {
+        dst[start..start + src.len()].copy_from_slice(src);
+        start += src.len();
+    }
+    Some(unsafe { value.assume_init() })
+}
+
+/// Split the user buffer into slices of the frames holding it, the pages
+/// are faulted in for writing if `write`. None if some page is not
+/// accessible to user space.
+fn translated_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    write: bool,
+) -> Option<Vec<&'static mut [u8]>> {
     let page_table = PageTable::from_token(token);
     let mut start = ptr as usize;
-    let end = start + len;
+    let end = start.checked_add(len)?;
     let mut v = Vec::new();
     while start < end {
         let start_va = VirtAddr::from(start);
+        if !fault_in_user_page(&page_table, start_va, write) {
+            return None;
+        }
         let mut vpn = start_va.floor();
         let ppn = page_table.translate(vpn).unwrap().ppn();
         vpn.step();
@@ -190,35 +453,89 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
         }
         start = end_va.into();
     }
-    v
+    Some(v)
+}
+
+/// Split `len` bytes at `ptr` in the address space of `token`, which need not
+/// be the current one, into slices of the frames holding them. Nothing is
+/// faulted in, so None if some page is not mapped readable by user space,
+/// even one that is lazy or swapped out.
+pub fn translated_foreign_buffer(
+    token: usize,
+    ptr: usize,
+    len: usize,
+) -> Option<Vec<&'static [u8]>> {
+    let page_table = PageTable::from_token(token);
+    let mut start = ptr;
+    let end = start.checked_add(len)?;
+    let mut v = Vec::new();
+    while start < end {
+        let start_va = VirtAddr::from(start);
+        let mut vpn = start_va.floor();
+        let ppn = match page_table.translate(vpn) {
+            Some(pte) if pte.is_valid() && pte.is_user() && pte.readable() => pte.ppn(),
+            _ => return None,
+        };
+        vpn.step();
+        let end_va = VirtAddr::from(vpn).min(VirtAddr::from(end));
+        let bytes: &'static [u8] = ppn.get_bytes_array();
+        if end_va.page_offset() == 0 {
+            v.push(&bytes[start_va.page_offset()..]);
+        } else {
+            v.push(&bytes[start_va.page_offset()..end_va.page_offset()]);
+        }
+        start = end_va.into();
+    }
+    Some(v)
 }
 
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// why a string could not be read from user space
+pub enum StrError {
+    /// the byte at this address is not readable by user space
+    Fault(usize),
+    /// there is no NUL right after the longest string allowed
+    TooLong,
+}
+
+/// Read the NUL-terminated string at `ptr` in user space, at most `max_len`
+/// bytes without the NUL. The walk stops at the first byte user space may
+/// not read, so an unterminated string fails instead of faulting the kernel.
+pub fn translated_str(token: usize, ptr: *const u8, max_len: usize) -> Result<String, StrError> {
     let page_table = PageTable::from_token(token);
     let mut string = String::new();
-    let mut va = ptr as usize;
+    let start = ptr as usize;
+    let mut va = start;
     loop {
+        if !fault_in_user_page(&page_table, VirtAddr::from(va), false) {
+            return Err(StrError::Fault(va));
+        }
         let ch: u8 = *(page_table
             .translate_va(VirtAddr::from(va))
             .unwrap()
             .get_mut());
         if ch == 0 {
             break;
-        } else {
-            string.push(ch as char);
-            va += 1;
         }
+        if va - start == max_len {
+            return Err(StrError::TooLong);
+        }
+        string.push(ch as char);
+        va += 1;
     }
-    string
+    Ok(string)
 }
 
-pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
-    //println!("into translated_refmut!");
+/// Translate a user pointer to a mutable reference, None if `*ptr` is not
+/// inside a single page writable by user space.
+pub fn try_translated_refmut<T>(token: usize, ptr: *mut T) -> Option<&'static mut T> {
     let page_table = PageTable::from_token(token);
-    let va = ptr as usize;
-    //println!("translated_refmut: before translate_va");
-    page_table
-        .translate_va(VirtAddr::from(va))
-        .unwrap()
-        .get_mut()
+    let start_va = VirtAddr::from(ptr as usize);
+    let end_va = VirtAddr::from((ptr as usize).checked_add(core::mem::size_of::<T>())?);
+    if start_va.floor() != VirtAddr::from(end_va.0 - 1).floor()
+        || !fault_in_user_page(&page_table, start_va, true)
+    {
+        return None;
+    }
+    Some(page_table.translate_va(start_va).unwrap().get_mut())
 }
@@ -0,0 +1,101 @@
+//! Implementation of the swap area, where the reclaimer puts the pages of
+//! lazy areas when frames run out.
+//!
+//! There is no block device, the swap area is the last `SWAP_PAGES` frames
+//! of memory, which the frame allocator leaves out. It adds no memory then:
+//! every page swapped out takes a frame the frame allocator could have had.
+//! It stands in for a block region so that the reclaimer can be exercised,
+//! and only a real one would let the lazy areas outgrow memory.
+
+use super::PhysPageNum;
+use crate::config::{MEMORY_END, PAGE_SIZE, SWAP_PAGES};
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// Slots of the swap area, allocated like frames
+struct SwapSpace {
+    /// The frame of the first slot
+    start: usize,
+    current: usize,
+    end: usize,
+    recycled: Vec<usize>,
+}
+
+impl SwapSpace {
+    fn new() -> Self {
+        let end = MEMORY_END / PAGE_SIZE;
+        Self {
+            start: end - SWAP_PAGES,
+            current: 0,
+            end: SWAP_PAGES,
+            recycled: Vec::new(),
+        }
+    }
+    fn alloc(&mut self) -> Option<usize> {
+        if let Some(slot) = self.recycled.pop() {
+            Some(slot)
+        } else if self.current == self.end {
+            None
+        } else {
+            self.current += 1;
+            Some(self.current - 1)
+        }
+    }
+    fn dealloc(&mut self, slot: usize) {
+        assert!(
+            slot < self.current,
+            "Swap slot {} has not been allocated!",
+            slot
+        );
+        self.recycled.push(slot);
+    }
+    fn used(&self) -> usize {
+        self.current - self.recycled.len()
+    }
+}
+
+lazy_static! {
+    static ref SWAP_SPACE: UPSafeCell<SwapSpace> = unsafe { UPSafeCell::new(SwapSpace::new()) };
+}
+
+/// A page in the swap area, which is freed on drop
+pub struct SwapSlot(usize);
+
+impl SwapSlot {
+    /// None if the swap area is full
+    pub fn alloc() -> Option<Self> {
+        SWAP_SPACE.exclusive_access().alloc().map(SwapSlot)
+    }
+    fn ppn(&self) -> PhysPageNum {
+        PhysPageNum(SWAP_SPACE.exclusive_access().start + self.0)
+    }
+    /// Save the frame `ppn` in the slot
+    pub fn write(&self, ppn: PhysPageNum) {
+        self.ppn()
+            .get_bytes_array()
+            .copy_from_slice(ppn.get_bytes_array());
+    }
+    /// Load the page saved in the slot into the frame `ppn`
+    pub fn read(&self, ppn: PhysPageNum) {
+        ppn.get_bytes_array()
+            .copy_from_slice(self.ppn().get_bytes_array());
+    }
+    /// Another slot with the same page, None if the swap area is full
+    pub fn duplicate(&self) -> Option<Self> {
+        let slot = Self::alloc()?;
+        slot.write(self.ppn());
+        Some(slot)
+    }
+}
+
+impl Drop for SwapSlot {
+    fn drop(&mut self) {
+        SWAP_SPACE.exclusive_access().dealloc(self.0);
+    }
+}
+
+/// Total and used pages of the swap area
+pub fn swap_usage() -> (usize, usize) {
+    (SWAP_PAGES, SWAP_SPACE.exclusive_access().used())
+}
@@ -0,0 +1,85 @@
+//! Condition variables working with the mutexes of a process
+
+use super::{Mutex, UPSafeCell};
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+pub struct Condvar {
+    inner: UPSafeCell<CondvarInner>,
+}
+
+struct CondvarInner {
+    wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(CondvarInner {
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// Wake up the first waiter, if any
+    pub fn signal(&self) {
+        let task = self.inner.exclusive_access().wait_queue.pop_front();
+        if let Some(task) = task {
+            wakeup_task(task);
+        }
+    }
+
+    /// Release `mutex` held by the thread `tid` and wait for a signal, then
+    /// take the mutex again. Nothing can run in between, the kernel is not
+    /// preempted. Return false if `tid` does not hold the mutex.
+    pub fn wait(&self, mutex: Arc<dyn Mutex>, tid: usize) -> bool {
+        if !mutex.unlock(tid) {
+            return false;
+        }
+        let task = current_task().unwrap();
+        self.inner
+            .exclusive_access()
+            .wait_queue
+            .push_back(task.clone());
+        block_current_and_run_next();
+        // a killed waiter must not wait for the mutex again, it could be
+        // held by a thread that is gone as well
+        if task
+            .inner_exclusive_access()
+            .signals
+            .check_error()
+            .is_none()
+        {
+            mutex.lock(tid);
+        }
+        true
+    }
+
+    /// Take `task` out of the wait queue, return false if it is not waiting here
+    pub fn remove_waiter(&self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        match inner
+            .wait_queue
+            .iter()
+            .position(|waiter| Arc::ptr_eq(waiter, task))
+        {
+            Some(index) => {
+                inner.wait_queue.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wake up every waiting task when the process exits and they have been
+    /// killed
+    pub fn wake_all_waiters(&self) {
+        let waiters = core::mem::take(&mut self.inner.exclusive_access().wait_queue);
+        for waiter in waiters {
+            wakeup_task(waiter);
+        }
+    }
+}
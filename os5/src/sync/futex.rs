@@ -0,0 +1,80 @@
+//! Futexes, wait queues in the kernel for words in user space
+//!
+//! A queue is keyed by the physical address of its word, so the threads of
+//! a process and the processes sharing a page all meet on the same queue.
+
+use super::UPSafeCell;
+use crate::config::PAGE_SIZE;
+use crate::mm::PhysPageNum;
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use lazy_static::*;
+
+lazy_static! {
+    /// Waiters of each futex in the order they came, a futex without any
+    /// has no queue
+    static ref FUTEX_QUEUES: UPSafeCell<BTreeMap<usize, VecDeque<Arc<TaskControlBlock>>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// Block the current task on the futex at the physical address `key` until
+/// `futex_wake` wakes it
+pub fn futex_wait(key: usize) {
+    FUTEX_QUEUES
+        .exclusive_access()
+        .entry(key)
+        .or_default()
+        .push_back(current_task().unwrap());
+    block_current_and_run_next();
+}
+
+/// Wake up to `count` waiters of the futex at `key`, the earliest first,
+/// and return how many were woken
+pub fn futex_wake(key: usize, count: usize) -> usize {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let queue = match queues.get_mut(&key) {
+        Some(queue) => queue,
+        None => return 0,
+    };
+    let woken = count.min(queue.len());
+    let tasks: VecDeque<_> = queue.drain(..woken).collect();
+    if queue.is_empty() {
+        queues.remove(&key);
+    }
+    drop(queues);
+    for task in tasks {
+        wakeup_task(task);
+    }
+    woken
+}
+
+/// Take `task` out of the futex it waits on, return false if it waits on
+/// none
+pub fn futex_remove_waiter(task: &Arc<TaskControlBlock>) -> bool {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let found = queues.iter_mut().find_map(|(key, queue)| {
+        let idx = queue.iter().position(|waiter| Arc::ptr_eq(waiter, task))?;
+        queue.remove(idx);
+        Some((*key, queue.is_empty()))
+    });
+    match found {
+        Some((key, true)) => {
+            queues.remove(&key);
+            true
+        }
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// Whether a futex in the frame `ppn` has waiters, the frame must not
+/// change under them
+pub fn futex_in_frame(ppn: PhysPageNum) -> bool {
+    let start = ppn.0 * PAGE_SIZE;
+    FUTEX_QUEUES
+        .exclusive_access()
+        .range(start..start + PAGE_SIZE)
+        .next()
+        .is_some()
+}
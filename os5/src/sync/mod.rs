@@ -1,5 +1,13 @@
 //! Synchronization and interior mutability primitives
 
+mod condvar;
+mod futex;
+mod mutex;
+mod semaphore;
 mod up;
 
+pub use condvar::Condvar;
+pub use futex::{futex_in_frame, futex_remove_waiter, futex_wait, futex_wake};
+pub use mutex::{Mutex, MutexBlocking, MutexSpin};
+pub use semaphore::Semaphore;
 pub use up::UPSafeCell;
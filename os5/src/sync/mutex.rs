@@ -0,0 +1,169 @@
+//! Mutexes shared by the threads of a process
+//!
+//! A mutex is owned by the tid of the thread holding it, so only the owner
+//! may unlock it and a thread locking it again gets an error instead of a
+//! deadlock.
+
+use super::UPSafeCell;
+use crate::task::{
+    block_current_and_run_next, current_task, suspend_current_and_run_next, wakeup_task,
+    TaskControlBlock,
+};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+pub trait Mutex: Sync + Send {
+    /// Take the mutex for the thread `tid`, waiting until it is free.
+    /// Return false if `tid` holds it already.
+    fn lock(&self, tid: usize) -> bool;
+    /// Release the mutex held by the thread `tid`, return false if it does
+    /// not hold it
+    fn unlock(&self, tid: usize) -> bool;
+    fn is_locked(&self) -> bool;
+    /// Take `task` out of the waiters, return false if it is not one of them
+    fn remove_waiter(&self, task: &Arc<TaskControlBlock>) -> bool;
+    /// Wake up every waiting thread as if it got the mutex, when the process
+    /// exits and they have been killed
+    fn wake_all_waiters(&self);
+}
+
+/// A mutex whose waiters keep yielding until it is free
+pub struct MutexSpin {
+    owner: UPSafeCell<Option<usize>>,
+}
+
+impl MutexSpin {
+    pub fn new() -> Self {
+        Self {
+            owner: unsafe { UPSafeCell::new(None) },
+        }
+    }
+}
+
+impl Mutex for MutexSpin {
+    fn lock(&self, tid: usize) -> bool {
+        loop {
+            let mut owner = self.owner.exclusive_access();
+            match *owner {
+                Some(holder) if holder == tid => return false,
+                Some(_) => {
+                    drop(owner);
+                    suspend_current_and_run_next();
+                }
+                None => {
+                    *owner = Some(tid);
+                    return true;
+                }
+            }
+        }
+    }
+
+    fn unlock(&self, tid: usize) -> bool {
+        let mut owner = self.owner.exclusive_access();
+        if *owner != Some(tid) {
+            return false;
+        }
+        *owner = None;
+        true
+    }
+
+    fn is_locked(&self) -> bool {
+        self.owner.exclusive_access().is_some()
+    }
+
+    fn remove_waiter(&self, _task: &Arc<TaskControlBlock>) -> bool {
+        // its waiters are never blocked
+        false
+    }
+
+    fn wake_all_waiters(&self) {
+        // its waiters are ready already, they only have to find it free
+        *self.owner.exclusive_access() = None;
+    }
+}
+
+/// A mutex whose waiters are blocked in its wait queue, unlock hands it
+/// over to the first of them
+pub struct MutexBlocking {
+    inner: UPSafeCell<MutexBlockingInner>,
+}
+
+struct MutexBlockingInner {
+    owner: Option<usize>,
+    wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl MutexBlocking {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(MutexBlockingInner {
+                    owner: None,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+}
+
+impl Mutex for MutexBlocking {
+    fn lock(&self, tid: usize) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        match inner.owner {
+            Some(holder) if holder == tid => false,
+            Some(_) => {
+                inner.wait_queue.push_back(current_task().unwrap());
+                drop(inner);
+                // the mutex is ours once we are woken up
+                block_current_and_run_next();
+                true
+            }
+            None => {
+                inner.owner = Some(tid);
+                true
+            }
+        }
+    }
+
+    fn unlock(&self, tid: usize) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.owner != Some(tid) {
+            return false;
+        }
+        match inner.wait_queue.pop_front() {
+            Some(waiter) => {
+                inner.owner = Some(waiter.inner_exclusive_access().tid);
+                drop(inner);
+                wakeup_task(waiter);
+            }
+            None => inner.owner = None,
+        }
+        true
+    }
+
+    fn is_locked(&self) -> bool {
+        self.inner.exclusive_access().owner.is_some()
+    }
+
+    fn remove_waiter(&self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        match inner
+            .wait_queue
+            .iter()
+            .position(|waiter| Arc::ptr_eq(waiter, task))
+        {
+            Some(index) => {
+                inner.wait_queue.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn wake_all_waiters(&self) {
+        let waiters = core::mem::take(&mut self.inner.exclusive_access().wait_queue);
+        for waiter in waiters {
+            wakeup_task(waiter);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Counting semaphores shared by the threads of a process
+
+use super::UPSafeCell;
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+/// A negative count is the number of tasks in the wait queue
+pub struct Semaphore {
+    inner: UPSafeCell<SemaphoreInner>,
+}
+
+struct SemaphoreInner {
+    count: isize,
+    wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Semaphore {
+    pub fn new(res_count: usize) -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(SemaphoreInner {
+                    count: res_count as isize,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// Add a resource, waking up the first waiter to take it
+    pub fn up(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.count += 1;
+        if inner.count <= 0 {
+            if let Some(task) = inner.wait_queue.pop_front() {
+                drop(inner);
+                wakeup_task(task);
+            }
+        }
+    }
+
+    /// Take a resource, blocking until there is one
+    pub fn down(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.count -= 1;
+        if inner.count < 0 {
+            inner.wait_queue.push_back(current_task().unwrap());
+            drop(inner);
+            block_current_and_run_next();
+        }
+    }
+
+    /// Take `task` out of the wait queue, giving back the resource it was
+    /// waiting for. Return false if it is not waiting here.
+    pub fn remove_waiter(&self, task: &Arc<TaskControlBlock>) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        match inner
+            .wait_queue
+            .iter()
+            .position(|waiter| Arc::ptr_eq(waiter, task))
+        {
+            Some(index) => {
+                inner.wait_queue.remove(index);
+                inner.count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wake up every waiting task when the process exits and they have been
+    /// killed
+    pub fn wake_all_waiters(&self) {
+        let waiters = core::mem::take(&mut self.inner.exclusive_access().wait_queue);
+        for waiter in waiters {
+            wakeup_task(waiter);
+        }
+    }
+}
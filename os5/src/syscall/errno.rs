@@ -0,0 +1,15 @@
+//! Error numbers of the syscalls that tell why they failed, returned negated.
+//! They are those of Linux, most syscalls still just return -1. A user
+//! pointer that is unmapped or lacks the permission needed is `-EFAULT`
+//! everywhere, as the copy helpers of `mm` find it.
+
+/// A signal came before the syscall was done
+pub const EINTR: isize = 4;
+/// Try again later, e.g. no pid is left for a new task
+pub const EAGAIN: isize = 11;
+/// Out of memory
+pub const ENOMEM: isize = 12;
+/// A pointer argument is not accessible
+pub const EFAULT: isize = 14;
+/// The task has `MAX_FD` fds open already
+pub const EMFILE: isize = 24;
@@ -1,50 +1,145 @@
 //! File and filesystem-related syscalls
 
-use crate::mm::translated_byte_buffer;
-use crate::sbi::console_getchar;
-use crate::task::{current_user_token, suspend_current_and_run_next};
-
-const FD_STDIN: usize = 0;
-const FD_STDOUT: usize = 1;
+use super::errno::{EFAULT, EMFILE};
+use crate::config::MAX_FD;
+use crate::fs::{inject_input, make_pipe};
+use crate::mm::{copy_to_user, translated_user_buffer};
+use crate::task::{current_task, current_user_token};
+use alloc::sync::Arc;
 
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
-    match fd {
-        FD_STDOUT => {
-            let buffers = translated_byte_buffer(current_user_token(), buf, len);
-            for buffer in buffers {
-                print!("{}", core::str::from_utf8(buffer).unwrap());
-            }
-            len as isize
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    if fd >= fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &fd_table[fd] {
+        if !file.writable() {
+            return -1;
         }
-        _ => {
-            panic!("Unsupported fd in sys_write!");
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(fd_table);
+        drop(inner);
+        match translated_user_buffer(token, buf, len, false) {
+            Some(user_buf) => file.write(user_buf) as isize,
+            None => -EFAULT,
         }
+    } else {
+        -1
     }
 }
 
 pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
-    match fd {
-        FD_STDIN => {
-            assert_eq!(len, 1, "Only support len = 1 in sys_read!");
-            let mut c: usize;
-            loop {
-                c = console_getchar();
-                if c == 0 {
-                    suspend_current_and_run_next();
-                    continue;
-                } else {
-                    break;
-                }
-            }
-            let ch = c as u8;
-            let mut buffers = translated_byte_buffer(current_user_token(), buf, len);
-            unsafe {
-                buffers[0].as_mut_ptr().write_volatile(ch);
-            }
-            1
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let fd_table = inner.fd_table.exclusive_access();
+    if fd >= fd_table.len() {
+        return -1;
+    }
+    if let Some(file) = &fd_table[fd] {
+        if !file.readable() {
+            return -1;
+        }
+        let file = file.clone();
+        // release current task TCB manually to avoid multi-borrow
+        drop(fd_table);
+        drop(inner);
+        match translated_user_buffer(token, buf, len, true) {
+            Some(user_buf) => file.read(user_buf) as isize,
+            None => -EFAULT,
         }
-        _ => {
-            panic!("Unsupported fd in sys_read!");
+    } else {
+        -1
+    }
+}
+
+/// Duplicate `fd` to the lowest closed fd and return it, -1 if `fd` is not open
+pub fn sys_dup(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let file = match inner.fd_table.exclusive_access().get(fd) {
+        Some(Some(file)) => Arc::clone(file),
+        _ => return -1,
+    };
+    let new_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
+    inner.fd_table.exclusive_access()[new_fd] = Some(file);
+    new_fd as isize
+}
+
+/// Close `fd`, return -1 if it is not open.
+///
+/// The file goes away with the last fd of any task that refers to it, so a
+/// reader of a pipe gets EOF once every write end is closed. The threads of
+/// a process share its fds, a close in one closes it for all of them.
+pub fn sys_close(fd: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut fd_table = inner.fd_table.exclusive_access();
+    if fd >= fd_table.len() {
+        return -1;
+    }
+    match fd_table[fd].take() {
+        Some(_) => 0,
+        None => -1,
+    }
+}
+
+/// Create a pipe and write its read end and write end fds to `pipe`,
+/// return `-EMFILE` if the task cannot open two more fds or `-EFAULT` if
+/// `pipe` is not writable
+pub fn sys_pipe(pipe: *mut usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let (pipe_read, pipe_write) = make_pipe();
+    let read_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
+    inner.fd_table.exclusive_access()[read_fd] = Some(pipe_read);
+    let write_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => {
+            inner.fd_table.exclusive_access()[read_fd] = None;
+            return -EMFILE;
+        }
+    };
+    inner.fd_table.exclusive_access()[write_fd] = Some(pipe_write);
+    drop(inner);
+    if !copy_to_user(
+        current_user_token(),
+        pipe as *mut [usize; 2],
+        &[read_fd, write_fd],
+    ) {
+        let inner = task.inner_exclusive_access();
+        let mut fd_table = inner.fd_table.exclusive_access();
+        fd_table[read_fd] = None;
+        fd_table[write_fd] = None;
+        return -EFAULT;
+    }
+    0
+}
+
+/// The most fds a task can have open
+pub fn sys_getdtablesize() -> isize {
+    MAX_FD as isize
+}
+
+/// Debug hook: queue `len` bytes at `buf` as input to stdin, so tests can
+/// feed `sys_read` without a console. Return `len`, `-EFAULT` for a bad
+/// buffer.
+pub fn sys_inject_input(buf: *const u8, len: usize) -> isize {
+    match translated_user_buffer(current_user_token(), buf, len, false) {
+        Some(user_buf) => {
+            inject_input(user_buf.into_iter().map(|byte_ref| unsafe { *byte_ref }));
+            len as isize
         }
+        None => -EFAULT,
     }
 }
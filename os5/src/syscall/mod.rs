@@ -10,44 +10,179 @@
 //! `sys_` then the name of the syscall. You can find functions like this in
 //! submodules, and you should also implement syscalls this way.
 
+const SYSCALL_DUP: usize = 24;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_SCHED_SETAFFINITY: usize = 122;
+const SYSCALL_SCHED_GETAFFINITY: usize = 123;
 const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_GETRUSAGE: usize = 165;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_GETTID: usize = 178;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_BRK: usize = 214;
 const SYSCALL_SPAWN: usize = 400;
 const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_MREMAP: usize = 216;
 const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
+const SYSCALL_MADVISE: usize = 233;
 const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_GETPRIORITY: usize = 141;
 const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_RESET_TASK_INFO: usize = 411;
+const SYSCALL_LIST_PROCS: usize = 412;
+const SYSCALL_ALARM: usize = 413;
+const SYSCALL_TRANSLATE: usize = 414;
+const SYSCALL_MEMINFO: usize = 415;
+const SYSCALL_VM_DUMP: usize = 416;
+const SYSCALL_QUERY_PTE: usize = 417;
+const SYSCALL_INJECT_INPUT: usize = 418;
+const SYSCALL_SET_TASK_NAME: usize = 419;
+const SYSCALL_LIST_APPS: usize = 420;
+const SYSCALL_CPU_STATS: usize = 421;
+const SYSCALL_YIELD_TO: usize = 422;
+const SYSCALL_TASK_SCHED_INFO: usize = 423;
+const SYSCALL_FORK2: usize = 424;
+const SYSCALL_SCHED_DETERMINISTIC: usize = 425;
+const SYSCALL_LAST_FAULT: usize = 426;
+const SYSCALL_MMAP_STRICT: usize = 427;
+const SYSCALL_UPTIME: usize = 428;
+const SYSCALL_GETDTABLESIZE: usize = 429;
+const SYSCALL_SCHED_POLICY: usize = 430;
+const SYSCALL_NANOSLEEP: usize = 431;
+const SYSCALL_PRLIMIT: usize = 432;
+const SYSCALL_PROCESS_VM_READ: usize = 433;
+const SYSCALL_TRACE_STEP: usize = 434;
+const SYSCALL_TASK_STATS: usize = 435;
+const SYSCALL_THREAD_CREATE: usize = 460;
+const SYSCALL_MUTEX_CREATE: usize = 463;
+const SYSCALL_MUTEX_LOCK: usize = 464;
+const SYSCALL_MUTEX_DESTROY: usize = 465;
+const SYSCALL_MUTEX_UNLOCK: usize = 466;
+const SYSCALL_SEMAPHORE_CREATE: usize = 467;
+const SYSCALL_SEMAPHORE_UP: usize = 468;
+const SYSCALL_SEMAPHORE_DOWN: usize = 470;
+const SYSCALL_CONDVAR_CREATE: usize = 471;
+const SYSCALL_CONDVAR_SIGNAL: usize = 472;
+const SYSCALL_CONDVAR_WAIT: usize = 473;
 
+mod errno;
 mod fs;
 mod process;
+mod sync;
+mod thread;
 
+use crate::task::{current_task, Rlimit};
 use fs::*;
 use process::*;
+use sync::*;
+use thread::*;
 
-/// handle syscall exception with `syscall_id` and other arguments
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+/// handle syscall exception with `syscall_id` and other arguments, `a0` to
+/// `a3`; the syscalls that take less ignore the rest
+pub fn syscall(syscall_id: usize, args: [usize; 4]) -> isize {
     match syscall_id {
+        SYSCALL_DUP => sys_dup(args[0]),
+        SYSCALL_CLOSE => sys_close(args[0]),
+        SYSCALL_PIPE => sys_pipe(args[0] as *mut usize),
         SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_FUTEX => sys_futex(args[0] as *mut u32, args[1], args[2] as u32),
+        SYSCALL_SLEEP => sys_sleep(args[0]),
+        SYSCALL_NANOSLEEP => sys_nanosleep(args[0] as *const TimeSpec, args[1] as *mut TimeSpec),
+        SYSCALL_SCHED_SETAFFINITY => sys_sched_setaffinity(args[0], args[1]),
+        SYSCALL_SCHED_GETAFFINITY => sys_sched_getaffinity(args[0]),
+        SYSCALL_PRLIMIT => sys_prlimit(args[0], args[1] as *const Rlimit, args[2] as *mut Rlimit),
+        SYSCALL_PROCESS_VM_READ => {
+            sys_process_vm_read(args[0], args[1], args[2] as *mut u8, args[3])
+        }
+        SYSCALL_TRACE_STEP => sys_trace_step(args[0]),
         SYSCALL_YIELD => sys_yield(),
+        SYSCALL_YIELD_TO => sys_yield_to(args[0]),
+        SYSCALL_SCHED_DETERMINISTIC => sys_sched_deterministic(args[0]),
+        SYSCALL_SCHED_POLICY => sys_sched_policy(args[0]),
+        SYSCALL_KILL => sys_kill(args[0], args[1] as i32),
+        SYSCALL_SIGACTION => sys_sigaction(args[0] as i32, args[1], args[2] as *mut usize),
+        SYSCALL_SIGPROCMASK => sys_sigprocmask(args[0], args[1] as *const u64, args[2] as *mut u64),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        SYSCALL_LAST_FAULT => sys_last_fault(args[0] as *mut FaultInfo),
+        SYSCALL_MMAP_STRICT => sys_mmap_strict(args[0]),
+        SYSCALL_UPTIME => sys_uptime(args[0] as *mut u64),
+        SYSCALL_GETDTABLESIZE => sys_getdtablesize(),
+        SYSCALL_ALARM => sys_alarm(args[0]),
         SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_GETPPID => sys_getppid(),
+        SYSCALL_GETTID => sys_gettid(),
         SYSCALL_FORK => sys_fork(),
-        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
-        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_FORK2 => sys_fork2(args[0] as *mut isize),
+        SYSCALL_EXEC => sys_exec(
+            args[0] as *const u8,
+            args[1] as *const usize,
+            args[2] as *const usize,
+        ),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32, args[2]),
         SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
+        SYSCALL_CLOCK_GETTIME => sys_clock_gettime(args[0], args[1] as *mut TimeSpec),
+        SYSCALL_TIMES => sys_times(args[0] as *mut Tms),
+        SYSCALL_GETRUSAGE => sys_getrusage(args[0] as isize, args[1] as *mut Rusage),
         SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
         SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_MREMAP => sys_mremap(args[0], args[1], args[2]),
+        SYSCALL_MPROTECT => sys_mprotect(args[0], args[1], args[2]),
+        SYSCALL_MADVISE => sys_madvise(args[0], args[1], args[2]),
+        SYSCALL_BRK => sys_brk(args[0]),
+        SYSCALL_TRANSLATE => sys_translate(args[0]),
+        SYSCALL_MEMINFO => sys_meminfo(args[0] as *mut MemInfo),
+        SYSCALL_VM_DUMP => sys_vm_dump(args[0] as *mut u8, args[1]),
+        SYSCALL_QUERY_PTE => sys_query_pte(args[0], args[1] as *mut PteInfo),
+        SYSCALL_INJECT_INPUT => sys_inject_input(args[0] as *const u8, args[1]),
+        SYSCALL_SET_TASK_NAME => sys_set_task_name(args[0] as *const u8),
+        SYSCALL_LIST_APPS => sys_list_apps(args[0] as *mut u8, args[1]),
+        SYSCALL_CPU_STATS => sys_cpu_stats(args[0] as *mut CpuStats),
         SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_GETPRIORITY => sys_getpriority(),
+        SYSCALL_TASK_SCHED_INFO => sys_task_sched_info(args[0] as isize, args[1] as *mut SchedInfo),
         SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
-        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+        SYSCALL_TASK_STATS => sys_task_stats(args[0] as *mut TaskStats),
+        SYSCALL_RESET_TASK_INFO => sys_reset_task_info(),
+        SYSCALL_LIST_PROCS => sys_list_procs(args[0] as *mut ProcEntry, args[1]),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8, args[1]),
+        SYSCALL_THREAD_CREATE => sys_thread_create(args[0], args[1]),
+        SYSCALL_MUTEX_CREATE => sys_mutex_create(args[0] != 0),
+        SYSCALL_MUTEX_LOCK => sys_mutex_lock(args[0]),
+        SYSCALL_MUTEX_UNLOCK => sys_mutex_unlock(args[0]),
+        SYSCALL_MUTEX_DESTROY => sys_mutex_destroy(args[0]),
+        SYSCALL_SEMAPHORE_CREATE => sys_semaphore_create(args[0]),
+        SYSCALL_SEMAPHORE_UP => sys_semaphore_up(args[0]),
+        SYSCALL_SEMAPHORE_DOWN => sys_semaphore_down(args[0]),
+        SYSCALL_CONDVAR_CREATE => sys_condvar_create(),
+        SYSCALL_CONDVAR_SIGNAL => sys_condvar_signal(args[0]),
+        SYSCALL_CONDVAR_WAIT => sys_condvar_wait(args[0], args[1]),
+        _ => {
+            // ENOSYS, a bad syscall only fails for the caller
+            error!(
+                "Unsupported syscall_id: {}, pid = {}",
+                syscall_id,
+                current_task().unwrap().getpid()
+            );
+            -1
+        }
     }
 }
@@ -1,16 +1,34 @@
 //! Process management syscalls
 
-use crate::config::MAX_SYSCALL_NUM;
-use crate::loader::get_app_data_by_name;
+use super::errno::{EAGAIN, EFAULT, EINTR, ENOMEM};
+use crate::config::{
+    HUGE_PAGE_SIZE, MAX_PRIORITY, MAX_SYSCALL_NUM, MAX_TASKS, PAGE_SIZE, PATH_MAX, TASK_NAME_LEN,
+    USER_SPACE_END, USER_STACK_SIZE,
+};
+use crate::loader::{app_names, get_app_data_by_name};
 use crate::mm::{
-    memeory_map, memeory_unmap, translated_refmut, translated_str, MapPermission, VirtAddr,
+    copy_bytes_to_user, copy_from_user, copy_to_user, frame_usage, memeory_madvise_dontneed,
+    memeory_map, memeory_protect, memeory_remap, memeory_unmap, mmap_strict_len,
+    set_mmap_strict_len, swap_usage, thread_trap_cx_va, translated_foreign_buffer, translated_str,
+    translated_user_buffer, try_translated_refmut, MapPermission, MapType, PhysAddr, VirtAddr,
 };
 use crate::task::{
-    add_task, current_task, current_user_token, exit_current_and_run_next,
-    suspend_current_and_run_next, TaskStatus,
+    add_task, block_current_and_run_next, check_signals_error_of_current, cpu_stats, current_task,
+    current_user_token, discard_child, exit_current_and_run_next, hart_id, list_tasks,
+    move_to_front, pid2task, sched_policy, set_deterministic_sched, set_sched_policy,
+    suspend_current_and_run_next, task_count, wakeup_killed_task, wakeup_task, Rlimit, SchedPolicy,
+    SignalFlags, TaskControlBlock, TaskStatus, Trace,
+};
+use crate::timer::{
+    add_interruptible_timer, add_timer, get_realtime_ns, get_ticks, get_time_ns, get_time_us,
+    interrupt_sleep,
 };
-use crate::timer::get_time_us;
+use crate::trap::TrapContext;
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use riscv::register::scause::Exception;
 
 #[repr(C)]
 #[derive(Debug)]
@@ -19,6 +37,66 @@ pub struct TimeVal {
     pub usec: usize,
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+/// CPU times of a process in microseconds, the c* fields are those of the
+/// reaped children
+#[repr(C)]
+#[derive(Debug)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
+/// Resource usage reported by `sys_getrusage`, times in microseconds
+#[repr(C)]
+#[derive(Debug)]
+pub struct Rusage {
+    pub utime: usize,
+    pub stime: usize,
+    /// The most pages resident at once
+    pub maxrss: usize,
+    /// Page faults resolved without I/O
+    pub minflt: usize,
+    /// Page faults that needed I/O, always 0: the swap area is memory, a
+    /// page read back from it is a minor fault
+    pub majflt: usize,
+}
+
+/// One process reported by `sys_list_procs`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ProcEntry {
+    pub pid: usize,
+    /// 0 if the parent has exited
+    pub ppid: usize,
+    pub status: TaskStatus,
+    pub priority: usize,
+    /// Name of the task, padded with NULs
+    pub name: [u8; TASK_NAME_LEN],
+}
+
+/// Stride scheduling state of a task reported by `sys_task_sched_info`
+#[repr(C)]
+#[derive(Debug)]
+pub struct SchedInfo {
+    pub priority: usize,
+    pub stride: u64,
+    /// What `stride` grows by each time the task runs, `BIG_STRIDE / priority`
+    pub pass: u64,
+    pub switch_count: usize,
+    /// Scheduling round the task last ran in
+    pub last_run_tick: usize,
+}
+
+#[repr(C)]
 #[derive(Clone, Copy)]
 pub struct TaskInfo {
     pub status: TaskStatus,
@@ -26,6 +104,24 @@ pub struct TaskInfo {
     pub time: usize,
 }
 
+/// What `sys_task_stats` reports about a task and its address space. The
+/// layout of `TaskInfo` is fixed by the test programs of the labs, so these
+/// are kept out of it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TaskStats {
+    /// Pages of the address space backed by a frame of its own
+    pub rss_pages: usize,
+    /// Mapped areas of the address space
+    pub area_count: usize,
+    /// Pages backed by a frame other address spaces map as well
+    pub shared_pages: usize,
+    /// Frames holding the page table of the address space
+    pub page_table_frames: usize,
+    /// Times the task has been switched in
+    pub switch_count: usize,
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     debug!("[kernel] Application exited with code {}", exit_code);
     exit_current_and_run_next(exit_code);
@@ -38,83 +134,657 @@ pub fn sys_yield() -> isize {
     0
 }
 
+/// Turn timer preemption off if `enable` is not 0, so tasks only switch when
+/// they yield, block or exit, or on again. The stride order is kept either
+/// way. Return 1 if preemption was off already, 0 otherwise.
+pub fn sys_sched_deterministic(enable: usize) -> isize {
+    set_deterministic_sched(enable != 0) as isize
+}
+
+/// Schedule with the stride policy if `policy` is 0, round-robin if it is 1,
+/// return the policy used before or -1 for another `policy`. It starts as
+/// the kernel command line says.
+pub fn sys_sched_policy(policy: usize) -> isize {
+    let policy = match policy {
+        0 => SchedPolicy::Stride,
+        1 => SchedPolicy::RoundRobin,
+        _ => return -1,
+    };
+    match set_sched_policy(policy) {
+        SchedPolicy::Stride => 0,
+        SchedPolicy::RoundRobin => 1,
+    }
+}
+
+/// Give the processor to the ready task `pid`, it runs next whatever its
+/// stride. Return -1 if it is not waiting in the ready queue, the current
+/// task never is.
+pub fn sys_yield_to(pid: usize) -> isize {
+    if !move_to_front(pid) {
+        return -1;
+    }
+    suspend_current_and_run_next();
+    0
+}
+
+/// Block the current task for at least `ms` milliseconds, `sys_sleep(0)`
+/// is the same as `sys_yield`. A time too long to be kept in microseconds
+/// sleeps for good.
+pub fn sys_sleep(ms: usize) -> isize {
+    if ms == 0 {
+        return sys_yield();
+    }
+    let expire_us = get_time_us().saturating_add(ms.saturating_mul(1000));
+    let task = current_task().unwrap();
+    add_timer(expire_us, task);
+    block_current_and_run_next();
+    0
+}
+
+/// Sleep for the time `req` points to, to the microsecond, unless a signal
+/// that is not masked comes first. Then write the time left to `rem`, if it
+/// is not null, and return `-EINTR`; otherwise write 0 to it and return 0.
+/// Return -1 if `req` is not a valid time or one too long to be kept in
+/// microseconds, `-EFAULT` if it is not readable or `rem` is not writable.
+pub fn sys_nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> isize {
+    let token = current_user_token();
+    let req = match copy_from_user(token, req) {
+        Some(req) if req.nsec < 1_000_000_000 => req,
+        Some(_) => return -1,
+        None => return -EFAULT,
+    };
+    let expire_us = match req
+        .sec
+        .checked_mul(1_000_000)
+        .and_then(|us| us.checked_add((req.nsec + 999) / 1000))
+        .and_then(|us| us.checked_add(get_time_us()))
+    {
+        Some(expire_us) => expire_us,
+        None => return -1,
+    };
+    let task = current_task().unwrap();
+    add_interruptible_timer(expire_us, task);
+    block_current_and_run_next();
+    let left_us = expire_us.saturating_sub(get_time_us());
+    let left = TimeSpec {
+        sec: left_us / 1_000_000,
+        nsec: left_us % 1_000_000 * 1000,
+    };
+    if !rem.is_null() && !copy_to_user(token, rem, &left) {
+        return -EFAULT;
+    }
+    if left_us > 0 {
+        -EINTR
+    } else {
+        0
+    }
+}
+
+/// Raise SIGALRM for the current task after `seconds` seconds, cancelling the
+/// pending alarm. `sys_alarm(0)` only cancels it. Return the seconds left of
+/// the cancelled alarm, rounded up, or 0 if there was none. An alarm too far
+/// off to be kept in microseconds never goes off.
+pub fn sys_alarm(seconds: usize) -> isize {
+    let now = get_time_us();
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let left = match inner.alarm_expire_us {
+        Some(expire_us) => expire_us.saturating_sub(now).saturating_add(999_999) / 1_000_000,
+        None => 0,
+    };
+    inner.alarm_expire_us = if seconds == 0 {
+        None
+    } else {
+        Some(now.saturating_add(seconds.saturating_mul(1_000_000)))
+    };
+    left as isize
+}
+
+/// The pid of the process, the same in all of its threads
 pub fn sys_getpid() -> isize {
-    current_task().unwrap().pid.0 as isize
+    current_task().unwrap().process_pid as isize
+}
+
+/// Return the pid of the parent, which is initproc once the parent has exited.
+/// Initproc itself has no parent and gets 0.
+pub fn sys_getppid() -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.process_pid as isize)
+}
+
+/// Rename the current task after the string at `name`, cut to
+/// `TASK_NAME_LEN` bytes. Return -1 if it is not readable or longer than
+/// `PATH_MAX`.
+pub fn sys_set_task_name(name: *const u8) -> isize {
+    let name = match translated_str(current_user_token(), name, PATH_MAX) {
+        Ok(name) => name,
+        Err(_) => return -1,
+    };
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .set_name(name.as_str());
+    0
+}
+
+/// Fill `buf` with up to `cap` processes that have not exited and return how
+/// many there are, which is more than `cap` if some did not fit.
+pub fn sys_list_procs(buf: *mut ProcEntry, cap: usize) -> isize {
+    // the pid table is not borrowed while copying, so faulting in the
+    // user buffer can not run into it
+    let entries: Vec<ProcEntry> = list_tasks()
+        .iter()
+        .map(|task| {
+            let inner = task.inner_exclusive_access();
+            let mut name = [0; TASK_NAME_LEN];
+            name[..inner.name.len()].copy_from_slice(inner.name.as_bytes());
+            ProcEntry {
+                pid: task.getpid(),
+                ppid: inner
+                    .parent
+                    .as_ref()
+                    .and_then(|parent| parent.upgrade())
+                    .map_or(0, |parent| parent.getpid()),
+                status: inner.task_status,
+                priority: inner.priority.prio(),
+                name,
+            }
+        })
+        .collect();
+    let token = current_user_token();
+    for (i, entry) in entries.iter().take(cap).enumerate() {
+        if !copy_to_user(token, buf.wrapping_add(i), entry) {
+            return -EFAULT;
+        }
+    }
+    entries.len() as isize
+}
+
+/// Send signal `signum` to process `pid`, return -1 if there is no such
+/// process or `signum` is out of range.
+pub fn sys_kill(pid: usize, signum: i32) -> isize {
+    let task = match pid2task(pid) {
+        Some(task) => task,
+        None => return -1,
+    };
+    match SignalFlags::from_signum(signum) {
+        Some(flag) => {
+            let mut inner = task.inner_exclusive_access();
+            inner.signals |= flag;
+            let pending = flag - inner.signal_mask;
+            drop(inner);
+            // a task in `sys_nanosleep` or reading stdin wakes up for any
+            // signal it takes
+            if !pending.is_empty() && interrupt_sleep(&task) {
+                return 0;
+            }
+            if pending.check_error().is_some() {
+                wakeup_killed_task(task);
+            }
+            0
+        }
+        None => -1,
+    }
+}
+
+/// Register `handler` as the entry point of the user handler of `signum`, 0
+/// restores the default action, and write the previous one to `old` unless
+/// it is null. The handler gets the signal number in `a0` and has to end
+/// with `sys_sigreturn`. Return -1 if `signum` is out of range or SIGKILL,
+/// `-EFAULT` if `old` is a bad pointer.
+pub fn sys_sigaction(signum: i32, handler: usize, old: *mut usize) -> isize {
+    if SignalFlags::from_signum(signum).map_or(true, |flag| flag == SignalFlags::SIGKILL) {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let old_handler = inner.signal_actions[signum as usize];
+    let token = inner.get_user_token();
+    // faulting in `old` borrows the task
+    drop(inner);
+    if !old.is_null() && !copy_to_user(token, old, &old_handler) {
+        return -EFAULT;
+    }
+    task.inner_exclusive_access().signal_actions[signum as usize] = handler;
+    0
+}
+
+/// `how` of sys_sigprocmask: add `set` to the mask
+const SIG_BLOCK: usize = 0;
+/// `how` of sys_sigprocmask: take `set` out of the mask
+const SIG_UNBLOCK: usize = 1;
+/// `how` of sys_sigprocmask: replace the mask with `set`
+const SIG_SETMASK: usize = 2;
+
+/// Change the signal mask of the current task as `how` says unless `set` is
+/// null, and write the previous mask to `old` unless it is null. SIGKILL
+/// can not be masked. Return -1 for a bad `how`, `-EFAULT` for a bad pointer.
+pub fn sys_sigprocmask(how: usize, set: *const u64, old: *mut u64) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let old_mask = task.inner_exclusive_access().signal_mask;
+    let new_mask = if set.is_null() {
+        old_mask
+    } else {
+        let set = match copy_from_user(token, set) {
+            Some(set) => SignalFlags::from_bits_truncate(set as u32),
+            None => return -EFAULT,
+        };
+        match how {
+            SIG_BLOCK => old_mask | set,
+            SIG_UNBLOCK => old_mask - set,
+            SIG_SETMASK => set,
+            _ => return -1,
+        }
+    };
+    if !old.is_null() && !copy_to_user(token, old, &(old_mask.bits() as u64)) {
+        return -EFAULT;
+    }
+    // unblocked pending signals are handled on the way back from here
+    task.inner_exclusive_access().signal_mask = new_mask - SignalFlags::SIGKILL;
+    0
+}
+
+/// Return from a signal handler to the code it interrupted, with all of its
+/// registers and its pc as they were. Without a handler running there is
+/// nothing to return to and the task is killed.
+pub fn sys_sigreturn() -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    match inner.trap_ctx_backup.take() {
+        Some(backup) => {
+            let trap_cx = inner.get_trap_cx();
+            *trap_cx = backup;
+            // the return value goes to `a0`, which keeps its value this way
+            trap_cx.x[10] as isize
+        }
+        None => {
+            error!("sigreturn without a signal handler running");
+            inner.signals |= SignalFlags::SIGKILL;
+            -1
+        }
+    }
+}
+
+/// The last fault the kernel could not resolve, reported by `sys_last_fault`
+#[repr(C)]
+#[derive(Debug)]
+pub struct FaultInfo {
+    /// The address accessed, `stval`
+    pub addr: usize,
+    /// `scause` of the fault
+    pub cause: usize,
+    pub is_write: bool,
+}
+
+/// Fill `out` with the last fault of the current task that was not resolved
+/// by copy-on-write, lazy allocation or stack growth, the one a SIGSEGV
+/// handler runs for. Return -1 if there has been none, `-EFAULT` if `out` is
+/// bad.
+pub fn sys_last_fault(out: *mut FaultInfo) -> isize {
+    let (addr, cause) = match current_task().unwrap().inner_exclusive_access().last_fault {
+        Some(fault) => fault,
+        None => return -1,
+    };
+    let fault_info = FaultInfo {
+        addr,
+        cause,
+        is_write: matches!(
+            Exception::from(cause),
+            Exception::StoreFault | Exception::StorePageFault
+        ),
+    };
+    if !copy_to_user(current_user_token(), out, &fault_info) {
+        return -EFAULT;
+    }
+    0
 }
 
 /// Syscall Fork which returns 0 for child process and child_pid for parent process
+/// Return -1 if there are `MAX_TASKS` tasks already, the caller has as many
+/// children as its RLIMIT_NPROC allows or frames run out.
 pub fn sys_fork() -> isize {
-    let current_task = current_task().unwrap();
-    let new_task = current_task.fork();
+    let new_task = match fork_current() {
+        Ok(new_task) => new_task,
+        Err(_) => return -1,
+    };
     let new_pid = new_task.pid.0;
+    // add new task to scheduler
+    add_task(new_task);
+    new_pid as isize
+}
+
+/// Fork like `sys_fork`, but write the pid of the child to `child_pid_out`,
+/// 0 in the child, and return 0. On failure nothing is forked and the return
+/// value is `-EFAULT` for a bad pointer, `-EAGAIN` if there are `MAX_TASKS`
+/// tasks already or as many children as RLIMIT_NPROC allows, or `-ENOMEM`
+/// if frames run out.
+pub fn sys_fork2(child_pid_out: *mut isize) -> isize {
+    let token = current_user_token();
+    // the child inherits the 0, and the pointer is known to be good before
+    // there is a child to undo
+    if !copy_to_user(token, child_pid_out, &0isize) {
+        return -EFAULT;
+    }
+    let new_task = match fork_current() {
+        Ok(new_task) => new_task,
+        Err(errno) => return -errno,
+    };
+    let new_pid = new_task.pid.0;
+    // the page is shared copy-on-write with the child now, there may be no
+    // frame left to copy it
+    if !copy_to_user(token, child_pid_out, &(new_pid as isize)) {
+        discard_child(new_task);
+        return -ENOMEM;
+    }
+    add_task(new_task);
+    0
+}
+
+/// Whether `task` may have another child under its RLIMIT_NPROC, the
+/// children of its process are those of the main thread
+fn below_nproc_limit(task: &Arc<TaskControlBlock>) -> bool {
+    let limit = task.inner_exclusive_access().rlimits.nproc.cur;
+    task.main_thread().inner_exclusive_access().children.len() < limit
+}
+
+/// Fork the current task, the child is not in the ready queue yet. Fail with
+/// the errno of `sys_fork2`.
+fn fork_current() -> Result<Arc<TaskControlBlock>, isize> {
+    if task_count() >= MAX_TASKS {
+        return Err(EAGAIN);
+    }
+    let current_task = current_task().unwrap();
+    if !below_nproc_limit(&current_task) {
+        return Err(EAGAIN);
+    }
+    let new_task = current_task.fork().map_err(|err| {
+        error!("fork err: {:?}", err);
+        ENOMEM
+    })?;
     // modify trap context of new_task, because it returns immediately after switching
     let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
     // we do not have to move to next instruction since we have done it before
     // for child process, fork returns 0
     trap_cx.x[10] = 0;
-    // add new task to scheduler
-    add_task(new_task);
-    new_pid as isize
+    Ok(new_task)
+}
+
+/// Bytes of the strings and pointers of `sys_exec` arguments and environment
+const MAX_ARGS_SIZE: usize = USER_STACK_SIZE / 2;
+
+/// Read the strings of the null-terminated array `ptrs` in user space, a
+/// null `ptrs` is an empty array. `size` counts the bytes they take on the
+/// user stack, None if it goes beyond `MAX_ARGS_SIZE` or a pointer is bad.
+fn translated_str_array(
+    token: usize,
+    mut ptrs: *const usize,
+    size: &mut usize,
+) -> Option<Vec<String>> {
+    let mut strs: Vec<String> = Vec::new();
+    while !ptrs.is_null() {
+        let str_ptr = copy_from_user(token, ptrs)?;
+        if str_ptr == 0 {
+            break;
+        }
+        let s = translated_str(token, str_ptr as *const u8, MAX_ARGS_SIZE).ok()?;
+        // the strings have to fit into the initial user stack
+        *size += s.len() + 1 + core::mem::size_of::<usize>();
+        if *size > MAX_ARGS_SIZE {
+            return None;
+        }
+        strs.push(s);
+        ptrs = ptrs.wrapping_add(1);
+    }
+    Some(strs)
 }
 
 /// Syscall Exec which accepts the elf path
-pub fn sys_exec(path: *const u8) -> isize {
+///
+/// `args` and `envp` are null-terminated arrays of string pointers, or null
+/// for none, `envp` holds `KEY=VALUE` strings. Return `argc`, which becomes
+/// `a0` of the new program, or -1 if frames run out for the new address
+/// space or other threads of the process are still running in the old one,
+/// and also if a string is not readable up to its NUL or `path` is longer
+/// than `PATH_MAX`.
+pub fn sys_exec(path: *const u8, args: *const usize, envp: *const usize) -> isize {
     let token = current_user_token();
-    let path = translated_str(token, path);
+    let path = match translated_str(token, path, PATH_MAX) {
+        Ok(path) => path,
+        Err(err) => {
+            error!("exec err: {:?}", err);
+            return -1;
+        }
+    };
+    let mut size = 0;
+    let args_vec = match translated_str_array(token, args, &mut size) {
+        Some(args_vec) => args_vec,
+        None => return -1,
+    };
+    let envs_vec = match translated_str_array(token, envp, &mut size) {
+        Some(envs_vec) => envs_vec,
+        None => return -1,
+    };
     if let Some(data) = get_app_data_by_name(path.as_str()) {
         let task = current_task().unwrap();
-        task.exec(data);
-        0
+        if task.inner_exclusive_access().thread_count() > 1 {
+            return -1;
+        }
+        let argc = args_vec.len();
+        if let Err(err) = task.exec(path.as_str(), data, args_vec, envs_vec) {
+            error!("exec err: {:?}", err);
+            return -1;
+        }
+        argc as isize
     } else {
         -1
     }
 }
 
+/// `options` bit of sys_waitpid: do not ask the caller to wait for a running child
+const WNOHANG: usize = 1;
+/// `options` bit of sys_waitpid: block until a matching child exits instead
+/// of asking the caller to wait
+const WBLOCK: usize = 1 << 1;
+/// `options` bit of sys_waitpid: write the encoded wait status instead of
+/// the exit code
+const WSTATUS: usize = 1 << 2;
+
 /// If there is not a child process whose pid is same as given, return -1.
-/// Else if there is a child process but it is still running, return -2.
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+/// Else if there is a child process but it is still running, return -2,
+/// or 0 if `options` has WNOHANG. With WBLOCK the caller blocks until the
+/// child exits instead, every exiting child wakes it to look again; it
+/// returns -1 if a signal terminates it meanwhile. It only blocks right
+/// after finding a live child that matches, so waiting for itself or for a
+/// task that is not its child never blocks but returns -1 at once.
+/// Return `-EFAULT` without reaping the child if `exit_code_ptr` is not
+/// writable.
+///
+/// `exit_code_ptr` gets the exit code of the child, or the negated number
+/// of the signal that killed it. With WSTATUS it gets the low 8 bits of the
+/// exit code in bits 8..16 for a normal exit, or the number of the signal in
+/// the low 7 bits, as WIFEXITED and friends expect.
+pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32, options: usize) -> isize {
     let task = current_task().unwrap();
-    // find a child process
+    loop {
+        // find a child process
 
-    // ---- access current TCB exclusively
-    let mut inner = task.inner_exclusive_access();
-    if !inner
-        .children
-        .iter()
-        .any(|p| pid == -1 || pid as usize == p.getpid())
-    {
-        return -1;
-        // ---- release current PCB
-    }
-    let pair = inner.children.iter().enumerate().find(|(_, p)| {
-        // ++++ temporarily access child PCB lock exclusively
-        p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
-        // ++++ release child PCB
-    });
-    if let Some((idx, _)) = pair {
-        let child = inner.children.remove(idx);
-        // confirm that child will be deallocated after removing from children list
-        assert_eq!(Arc::strong_count(&child), 1);
-        let found_pid = child.getpid();
-        // ++++ temporarily access child TCB exclusively
-        let exit_code = child.inner_exclusive_access().exit_code;
-        // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
-        found_pid as isize
-    } else {
-        -2
+        // ---- access current TCB exclusively
+        let inner = task.inner_exclusive_access();
+        // checked again on each wakeup, right before blocking again: a task
+        // is never its own child, and a pid that is no child of it would
+        // leave it blocked with no one to wake it
+        if !inner
+            .children
+            .iter()
+            .any(|p| pid == -1 || pid as usize == p.getpid())
+        {
+            return -1;
+            // ---- release current PCB
+        }
+        let pair = inner.children.iter().enumerate().find(|(_, p)| {
+            // ++++ temporarily access child PCB lock exclusively
+            p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
+            // ++++ release child PCB
+        });
+        if let Some((idx, _)) = pair {
+            let token = inner.memory_set.exclusive_access().token();
+            // the write may need to split a copy-on-write page of the current TCB,
+            // and the child is only reaped once the pointer is known to be good
+            drop(inner);
+            let exit_code_ref = match try_translated_refmut(token, exit_code_ptr) {
+                Some(exit_code_ref) => exit_code_ref,
+                None => return -EFAULT,
+            };
+            let child = task.inner_exclusive_access().children.remove(idx);
+            // confirm that child will be deallocated after removing from children list
+            assert_eq!(Arc::strong_count(&child), 1);
+            let found_pid = child.getpid();
+            // ++++ temporarily access child TCB exclusively
+            let child_inner = child.inner_exclusive_access();
+            *exit_code_ref = if options & WSTATUS != 0 {
+                child_inner.exit_status
+            } else {
+                child_inner.exit_code
+            };
+            let child_info = &child_inner.addtion_info;
+            let child_maxrss = child_inner.memory_set.exclusive_access().max_rss();
+            let mut inner = task.inner_exclusive_access();
+            inner.addtion_info.cutime += child_info.utime + child_info.cutime;
+            inner.addtion_info.cstime += child_info.stime + child_info.cstime;
+            inner.addtion_info.cminflt += child_info.minflt + child_info.cminflt;
+            inner.addtion_info.cmaxrss = inner
+                .addtion_info
+                .cmaxrss
+                .max(child_maxrss)
+                .max(child_info.cmaxrss);
+            drop(inner);
+            drop(child_inner);
+            // ++++ release child PCB
+            return found_pid as isize;
+        } else if options & WNOHANG != 0 {
+            return 0;
+        } else if options & WBLOCK == 0 {
+            return -2;
+        }
+        // ---- release current PCB lock
+        drop(inner);
+        // nothing runs before the block, so no child can exit unnoticed
+        task.inner_exclusive_access().waiting_for_child = true;
+        block_current_and_run_next();
+        let inner = task.inner_exclusive_access();
+        if (inner.signals - inner.signal_mask).check_error().is_some() {
+            return -1;
+        }
     }
-    // ---- release current PCB lock automatically
 }
 
 // YOUR JOB: 引入虚地址后重写 sys_get_time
 pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
     let us = get_time_us();
     let token = current_user_token();
-    *translated_refmut(token, ts) = TimeVal {
+    // the TimeVal may straddle a page boundary
+    let time_val = TimeVal {
         sec: us / 1_000_000,
         usec: us % 1_000_000,
     };
+    if !copy_to_user(token, ts, &time_val) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// Write the number of timer ticks since boot to `out`, 100 a second.
+/// Return `-EFAULT` if `out` is not writable.
+pub fn sys_uptime(out: *mut u64) -> isize {
+    if !copy_to_user(current_user_token(), out, &get_ticks()) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// `clk_id` of sys_clock_gettime
+const CLOCK_REALTIME: usize = 0;
+const CLOCK_MONOTONIC: usize = 1;
+
+/// Return -1 if `clk_id` is not supported, `-EFAULT` if `ts` is not writable.
+pub fn sys_clock_gettime(clk_id: usize, ts: *mut TimeSpec) -> isize {
+    let ns = match clk_id {
+        CLOCK_REALTIME => get_realtime_ns(),
+        CLOCK_MONOTONIC => get_time_ns(),
+        _ => return -1,
+    };
+    let token = current_user_token();
+    let time_spec = TimeSpec {
+        sec: ns / 1_000_000_000,
+        nsec: ns % 1_000_000_000,
+    };
+    if !copy_to_user(token, ts, &time_spec) {
+        return -EFAULT;
+    }
+    0
+}
+
+pub fn sys_times(tms: *mut Tms) -> isize {
+    let times = {
+        let task = current_task().unwrap();
+        let inner = task.inner_exclusive_access();
+        Tms {
+            utime: inner.addtion_info.utime,
+            stime: inner.addtion_info.stime,
+            cutime: inner.addtion_info.cutime,
+            cstime: inner.addtion_info.cstime,
+        }
+    };
+
+    let token = current_user_token();
+    if !copy_to_user(token, tms, &times) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// `who` of sys_getrusage: the caller, or its reaped children and their own
+/// children
+const RUSAGE_SELF: isize = 0;
+const RUSAGE_CHILDREN: isize = -1;
+
+/// Write the resource usage of `who` to `out`. For the children `maxrss` is
+/// the highest of theirs, the rest is summed. Return -1 for another `who`,
+/// `-EFAULT` if `out` is not writable.
+pub fn sys_getrusage(who: isize, out: *mut Rusage) -> isize {
+    let usage = {
+        let task = current_task().unwrap();
+        let inner = task.inner_exclusive_access();
+        let info = &inner.addtion_info;
+        match who {
+            RUSAGE_SELF => Rusage {
+                utime: info.utime,
+                stime: info.stime,
+                maxrss: inner.memory_set.exclusive_access().max_rss(),
+                minflt: info.minflt,
+                majflt: 0,
+            },
+            RUSAGE_CHILDREN => Rusage {
+                utime: info.cutime,
+                stime: info.cstime,
+                maxrss: info.cmaxrss,
+                minflt: info.cminflt,
+                majflt: 0,
+            },
+            _ => return -1,
+        }
+    };
+
+    let token = current_user_token();
+    if !copy_to_user(token, out, &usage) {
+        return -EFAULT;
+    }
     0
 }
 
@@ -135,36 +805,170 @@ pub fn sys_task_info(ti: *mut TaskInfo) -> isize {
     };
 
     let token = current_user_token();
-    *translated_refmut(token, ti) = task_info;
+    if !copy_to_user(token, ti, &task_info) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// Fill `out` with the statistics of the address space of the current task.
+/// Return `-EFAULT` if `out` is not writable.
+pub fn sys_task_stats(out: *mut TaskStats) -> isize {
+    let stats = {
+        let task = current_task().unwrap();
+        let inner = task.inner_exclusive_access();
+        let memory_set = inner.memory_set.exclusive_access();
+        TaskStats {
+            rss_pages: memory_set.rss_pages(),
+            area_count: memory_set.area_count(),
+            shared_pages: memory_set.shared_pages(),
+            page_table_frames: memory_set.page_table_frames(),
+            switch_count: inner.addtion_info.switch_count,
+        }
+    };
+    if !copy_to_user(current_user_token(), out, &stats) {
+        return -EFAULT;
+    }
     0
 }
 
 // YOUR JOB: 实现sys_set_priority，为任务添加优先级
 pub fn sys_set_priority(prio: isize) -> isize {
-    if prio < 2 {
+    if prio < 2 || prio as usize > MAX_PRIORITY {
         -1
     } else {
         let task = current_task().unwrap();
         let mut inner = task.inner_exclusive_access();
+        // both its next pass and the rest of its time slice follow the new
+        // priority, not only those after it is switched in again
         inner.priority.set_prio(prio as usize);
+        inner.priority.resize_slice(sched_policy());
         prio
     }
 }
 
-// YOUR JOB: 扩展内核以实现 sys_mmap 和 sys_munmap
-pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
-    if (port & !0x7) != 0 || (port & 0x7) == 0 {
+/// The task `pid` refers to, the current one for 0
+fn task_or_current(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    if pid == 0 {
+        current_task()
+    } else {
+        pid2task(pid)
+    }
+}
+
+/// Let the task `pid`, the current one for 0, run only on the harts whose
+/// bits are set in `mask`. Harts that do not exist may be named, a task
+/// with none of those that do is not scheduled until it is allowed again.
+/// Return -1 if there is no such task or `mask` is 0.
+pub fn sys_sched_setaffinity(pid: usize, mask: usize) -> isize {
+    if mask == 0 {
         return -1;
     }
+    let task = match task_or_current(pid) {
+        Some(task) => task,
+        None => return -1,
+    };
+    task.inner_exclusive_access().cpu_affinity = mask;
+    let current = current_task().unwrap();
+    // the current task leaves a hart it may not run on right away
+    if Arc::ptr_eq(&task, &current) && mask & (1 << hart_id()) == 0 {
+        drop(task);
+        drop(current);
+        suspend_current_and_run_next();
+    }
+    0
+}
+
+/// The cpu affinity mask of the task `pid`, the current one for 0, or -1 if
+/// there is no such task
+pub fn sys_sched_getaffinity(pid: usize) -> isize {
+    match task_or_current(pid) {
+        Some(task) => task.inner_exclusive_access().cpu_affinity as isize,
+        None => -1,
+    }
+}
 
-    let len = ((len - 1) / 4096 + 1) * 4096;
+/// `resource` of sys_prlimit: seconds of user and kernel time, SIGXCPU is
+/// raised beyond the soft limit once a second and SIGKILL beyond the hard one
+const RLIMIT_CPU: usize = 0;
+/// `resource` of sys_prlimit: the children that have not been reaped, fork
+/// and spawn fail beyond it
+const RLIMIT_NPROC: usize = 6;
+/// `resource` of sys_prlimit: the bytes the address space covers, mmap,
+/// mremap and brk fail beyond it
+const RLIMIT_AS: usize = 9;
 
-    let start_va: VirtAddr = start.into();
-    if start_va.page_offset() != 0 {
-        return -1;
+/// Write the limits of `resource` of the current task to `old` and then
+/// set them to `new`, either may be null. Only RLIMIT_CPU, RLIMIT_NPROC and
+/// RLIMIT_AS are known. Return -1 for another `resource`, or if the soft limit is
+/// above the hard one or the hard one is raised, and `-EFAULT` for bad
+/// pointers; the limits are left alone then.
+pub fn sys_prlimit(resource: usize, new: *const Rlimit, old: *mut Rlimit) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let limit = match resource {
+        RLIMIT_CPU => inner.rlimits.cpu,
+        RLIMIT_NPROC => inner.rlimits.nproc,
+        RLIMIT_AS => inner.rlimits.address_space,
+        _ => return -1,
+    };
+    let token = inner.get_user_token();
+    // the copies may fault pages in, which borrows the task
+    drop(inner);
+    let new_limit = if new.is_null() {
+        None
+    } else {
+        match copy_from_user(token, new) {
+            Some(new_limit) if new_limit.cur <= new_limit.max && new_limit.max <= limit.max => {
+                Some(new_limit)
+            }
+            Some(_) => return -1,
+            None => return -EFAULT,
+        }
+    };
+    if !old.is_null() && !copy_to_user(token, old, &limit) {
+        return -EFAULT;
+    }
+    if let Some(new_limit) = new_limit {
+        let mut inner = task.inner_exclusive_access();
+        match resource {
+            RLIMIT_CPU => {
+                inner.rlimits.cpu = new_limit;
+                // counted from the new soft limit
+                inner.xcpu_raised_us = None;
+            }
+            RLIMIT_NPROC => inner.rlimits.nproc = new_limit,
+            _ => inner.rlimits.address_space = new_limit,
+        }
     }
+    0
+}
+
+// the flags of sys_mmap are kept clear of the bits right above R/W/X, a
+// `port` with any of those set is invalid
+/// `port` bit of sys_mmap: only reserve the range, frames are allocated on
+/// the first access to each page
+const MAP_LAZY: usize = 1 << 8;
+/// `port` bit of sys_mmap: the frames are shared with children instead of
+/// being copied on fork
+const MAP_SHARED: usize = 1 << 9;
+/// `port` bit of sys_mmap: map 2MiB megapages if the range is aligned to
+/// `HUGE_PAGE_SIZE` at both ends, ordinary pages otherwise
+const MAP_HUGE: usize = 1 << 10;
+/// `port` bit of sys_mmap: allocate and map every frame right away. That is
+/// what happens anyway unless `MAP_LAZY` asks otherwise, the two conflict.
+const MAP_POPULATE: usize = 1 << 11;
 
-    let end_va: VirtAddr = (start + len - 1).into();
+/// Translate the `port` of mmap/mprotect into a user permission,
+/// None if it has bits other than R/W/X set, none of them, or both W and X.
+fn port_to_perm(port: usize) -> Option<MapPermission> {
+    if (port & !0x7) != 0 || (port & 0x7) == 0 {
+        return None;
+    }
+    // W^X: a page user code can write must not be executable
+    if port & 0x6 == 0x6 {
+        return None;
+    }
 
     let mut map_perm = MapPermission::U;
 
@@ -180,8 +984,84 @@ pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
         map_perm |= MapPermission::X;
     };
 
-    if let Err(err) = memeory_map(start_va, end_va, map_perm) {
-        error!(" sys_mmap err: {}", err);
+    Some(map_perm)
+}
+
+/// The last byte of `[start, start + len)` rounded up to whole pages, None
+/// if `len` is 0, the range wraps around the address space or it reaches
+/// past `USER_SPACE_END`. In strict mode `len` is not rounded up, None if it
+/// is not a whole number of pages.
+fn range_end(start: usize, len: usize) -> Option<usize> {
+    if len % PAGE_SIZE != 0 && mmap_strict_len() {
+        return None;
+    }
+    let len = len.checked_add(PAGE_SIZE - 1)? / PAGE_SIZE * PAGE_SIZE;
+    if len == 0 {
+        return None;
+    }
+    start
+        .checked_add(len)
+        .filter(|&end| end <= USER_SPACE_END)
+        .map(|end| end - 1)
+}
+
+/// Whether mapping `bytes` more would make the address space of the current
+/// task cover more than its RLIMIT_AS
+fn over_as_limit(bytes: usize) -> bool {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mapped = inner.memory_set.exclusive_access().mapped_pages() * PAGE_SIZE;
+    mapped.saturating_add(bytes) > inner.rlimits.address_space.cur
+}
+
+// YOUR JOB: 扩展内核以实现 sys_mmap 和 sys_munmap
+pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
+    let mut map_type = match (port & MAP_LAZY != 0, port & MAP_SHARED != 0) {
+        (false, false) => MapType::Framed,
+        (true, false) => MapType::Lazy,
+        (false, true) => MapType::Shared,
+        // a page faulted in after fork would not be shared
+        (true, true) => return -1,
+    };
+    // megapages are neither lazy nor shared
+    if port & MAP_HUGE != 0 && map_type != MapType::Framed {
+        return -1;
+    }
+    if port & MAP_POPULATE != 0 && map_type == MapType::Lazy {
+        return -1;
+    }
+    let map_perm = match port_to_perm(port & !(MAP_LAZY | MAP_SHARED | MAP_HUGE | MAP_POPULATE)) {
+        Some(map_perm) => map_perm,
+        None => return -1,
+    };
+
+    let end = match range_end(start, len) {
+        Some(end) => end,
+        None => return -1,
+    };
+
+    let start_va: VirtAddr = start.into();
+    if start_va.page_offset() != 0 {
+        return -1;
+    }
+
+    if over_as_limit(end + 1 - start) {
+        return -1;
+    }
+
+    if port & MAP_HUGE != 0 && start % HUGE_PAGE_SIZE == 0 && (end + 1) % HUGE_PAGE_SIZE == 0 {
+        map_type = MapType::Huge;
+    }
+
+    let end_va: VirtAddr = end.into();
+
+    let name = if map_type == MapType::Shared {
+        "shared"
+    } else {
+        "mmap"
+    };
+    if let Err(err) = memeory_map(start_va, end_va, map_type, map_perm, name) {
+        error!(" sys_mmap err: {:?}", err);
         return -1;
     }
 
@@ -189,14 +1069,17 @@ pub fn sys_mmap(start: usize, len: usize, port: usize) -> isize {
 }
 
 pub fn sys_munmap(start: usize, len: usize) -> isize {
-    let len = ((len - 1) / 4096 + 1) * 4096;
+    let end = match range_end(start, len) {
+        Some(end) => end,
+        None => return -1,
+    };
 
     let start_va: VirtAddr = start.into();
     if start_va.page_offset() != 0 {
         return -1;
     }
 
-    let end_va: VirtAddr = (start + len - 1).into();
+    let end_va: VirtAddr = end.into();
 
     if let Err(err) = memeory_unmap(start_va, end_va) {
         error!("sys_mmap err: {}", err);
@@ -206,15 +1089,319 @@ pub fn sys_munmap(start: usize, len: usize) -> isize {
     0
 }
 
+/// Grow or shrink the mapping `[old_start, old_start + old_len)` to
+/// `new_len` bytes without moving it. The new pages read as zero. Return
+/// `old_start`, or -1 if the old range is not all mapped or the pages right
+/// after it are taken.
+pub fn sys_mremap(old_start: usize, old_len: usize, new_len: usize) -> isize {
+    let (old_end, new_end) = match (range_end(old_start, old_len), range_end(old_start, new_len)) {
+        (Some(old_end), Some(new_end)) => (old_end, new_end),
+        _ => return -1,
+    };
+
+    let start_va: VirtAddr = old_start.into();
+    if start_va.page_offset() != 0 {
+        return -1;
+    }
+
+    if new_end > old_end && over_as_limit(new_end - old_end) {
+        return -1;
+    }
+
+    if let Err(err) = memeory_remap(start_va, old_end.into(), new_end.into()) {
+        error!("sys_mremap err: {}", err);
+        return -1;
+    }
+
+    old_start as isize
+}
+
+/// `advice` of madvise: free the frames of the range, it reads as zero after
+const MADV_DONTNEED: usize = 4;
+
+/// Give advice about the use of `[start, start + len)`, only
+/// `MADV_DONTNEED` is known. Return -1 if the range is not all mapped, or on
+/// any other advice.
+pub fn sys_madvise(start: usize, len: usize, advice: usize) -> isize {
+    if advice != MADV_DONTNEED {
+        return -1;
+    }
+
+    let end = match range_end(start, len) {
+        Some(end) => end,
+        None => return -1,
+    };
+
+    let start_va: VirtAddr = start.into();
+    if start_va.page_offset() != 0 {
+        return -1;
+    }
+
+    if let Err(err) = memeory_madvise_dontneed(start_va, end.into()) {
+        error!("sys_madvise err: {}", err);
+        return -1;
+    }
+
+    0
+}
+
+/// Make mmap, munmap and the other calls taking a range of the current
+/// address space reject a len that is not a whole number of pages if
+/// `enable` is not 0, or round it up again. Fork keeps the setting, exec
+/// resets it. Return 1 if it was strict already, 0 otherwise.
+pub fn sys_mmap_strict(enable: usize) -> isize {
+    set_mmap_strict_len(enable != 0) as isize
+}
+
+pub fn sys_mprotect(start: usize, len: usize, port: usize) -> isize {
+    let map_perm = match port_to_perm(port) {
+        Some(map_perm) => map_perm,
+        None => return -1,
+    };
+
+    let end = match range_end(start, len) {
+        Some(end) => end,
+        None => return -1,
+    };
+
+    let start_va: VirtAddr = start.into();
+    if start_va.page_offset() != 0 {
+        return -1;
+    }
+
+    let end_va: VirtAddr = end.into();
+
+    if let Err(err) = memeory_protect(start_va, end_va, map_perm) {
+        error!("sys_mprotect err: {}", err);
+        return -1;
+    }
+
+    0
+}
+
+/// Frames of the whole system
+#[repr(C)]
+#[derive(Debug)]
+pub struct MemInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub used_frames: usize,
+    /// Pages of the swap area, memory kept from the frame allocator
+    pub swap_pages: usize,
+    /// Pages swapped out
+    pub swap_used: usize,
+}
+
+pub fn sys_meminfo(buf: *mut MemInfo) -> isize {
+    let (total_frames, free_frames) = frame_usage();
+    let (swap_pages, swap_used) = swap_usage();
+    let mem_info = MemInfo {
+        total_frames,
+        free_frames,
+        used_frames: total_frames - free_frames,
+        swap_pages,
+        swap_used,
+    };
+    if !copy_to_user(current_user_token(), buf, &mem_info) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// Time of the processor since boot, in microseconds
+#[repr(C)]
+#[derive(Debug)]
+pub struct CpuStats {
+    pub total_us: usize,
+    /// Time with no task to run
+    pub idle_us: usize,
+}
+
+pub fn sys_cpu_stats(buf: *mut CpuStats) -> isize {
+    let (total_us, idle_us) = cpu_stats();
+    let cpu_stats = CpuStats { total_us, idle_us };
+    if !copy_to_user(current_user_token(), buf, &cpu_stats) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// Debug hook: the physical address `va` of the current task is mapped to,
+/// -1 if it is not mapped to user space. Tests use it to see which frame
+/// backs a page.
+pub fn sys_translate(va: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let va = VirtAddr::from(va);
+    let pte = inner.memory_set.exclusive_access().translate(va.floor());
+    match pte {
+        Some(pte) if pte.is_valid() && pte.is_user() => {
+            let pa: PhysAddr = pte.ppn().into();
+            (pa.0 + va.page_offset()) as isize
+        }
+        _ => -1,
+    }
+}
+
+/// What the page table says about a page
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct PteInfo {
+    pub ppn: usize,
+    pub flags_v: bool,
+    pub flags_r: bool,
+    pub flags_w: bool,
+    pub flags_x: bool,
+    pub flags_u: bool,
+    /// the page is in an area of the task, though a lazy one may have no
+    /// valid pte yet
+    pub present: bool,
+}
+
+/// Debug hook: walk the page table of the current task for `vaddr`. The
+/// flags are those of the pte, or all cleared if there is none. Return -1 if
+/// the page is neither in an area nor has a valid pte.
+pub fn sys_query_pte(vaddr: usize, out: *mut PteInfo) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let vpn = VirtAddr::from(vaddr).floor();
+    let mut info = PteInfo {
+        present: inner.memory_set.exclusive_access().in_area(vpn),
+        ..Default::default()
+    };
+    if let Some(pte) = inner.memory_set.exclusive_access().translate(vpn) {
+        info.ppn = pte.ppn().0;
+        info.flags_v = pte.is_valid();
+        info.flags_r = pte.readable();
+        info.flags_w = pte.writable();
+        info.flags_x = pte.executable();
+        info.flags_u = pte.is_user();
+    }
+    drop(inner);
+    if !info.present && !info.flags_v {
+        return -1;
+    }
+    if !copy_to_user(current_user_token(), out, &info) {
+        return -EFAULT;
+    }
+    0
+}
+
+/// Debug hook: print the areas of the current task, one `start-end perm name`
+/// line each, after a line with its pid and name. Unless `buf` is null the
+/// dump is also copied there, cut to `len` bytes, so tests can check it.
+/// Return the length of the whole dump.
+pub fn sys_vm_dump(buf: *mut u8, len: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let dump = inner.memory_set.exclusive_access().dump();
+    println!("[kernel] vm of pid {} ({}):", task.getpid(), inner.name);
+    drop(inner);
+    print!("{}", dump);
+    if !buf.is_null() {
+        let len = len.min(dump.len());
+        if !copy_bytes_to_user(current_user_token(), buf, &dump.as_bytes()[..len]) {
+            return -EFAULT;
+        }
+    }
+    dump.len() as isize
+}
+
+/// Set the program break to `new_brk` and return it, `sys_brk(0)` only
+/// queries the current break. Return -1 if the break can not be moved there,
+/// RLIMIT_AS included.
+pub fn sys_brk(new_brk: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut memory_set = inner.memory_set.exclusive_access();
+    if new_brk == 0 {
+        return memory_set.program_brk() as isize;
+    }
+    let old_end = VirtAddr::from(memory_set.program_brk()).ceil().0;
+    let new_end = VirtAddr::from(new_brk).ceil().0;
+    if new_end > old_end
+        && (memory_set.mapped_pages() + new_end - old_end).saturating_mul(PAGE_SIZE)
+            > inner.rlimits.address_space.cur
+    {
+        return -1;
+    }
+    match memory_set.change_program_brk(new_brk) {
+        Some(brk) => brk as isize,
+        None => -1,
+    }
+}
+
+/// Fill `out` with the scheduling state of process `pid`, or of the current
+/// task if `pid` is -1. Return -1 if there is no such process.
+pub fn sys_task_sched_info(pid: isize, out: *mut SchedInfo) -> isize {
+    let task = if pid == -1 {
+        current_task().unwrap()
+    } else {
+        match pid2task(pid as usize) {
+            Some(task) => task,
+            None => return -1,
+        }
+    };
+    let inner = task.inner_exclusive_access();
+    let info = SchedInfo {
+        priority: inner.priority.prio(),
+        stride: inner.priority.stride(),
+        pass: inner.priority.pass(),
+        switch_count: inner.addtion_info.switch_count,
+        last_run_tick: inner.last_run_tick,
+    };
+    // faulting in `out` may borrow the current task
+    drop(inner);
+    if !copy_to_user(current_user_token(), out, &info) {
+        return -EFAULT;
+    }
+    0
+}
+
+pub fn sys_getpriority() -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    inner.priority.prio() as isize
+}
+
+/// `flags` bit of sys_spawn: the child gets the priority of the caller
+/// rather than the default one
+const SPAWN_INHERIT_PRIO: usize = 1;
+
 //
 // YOUR JOB: 实现 sys_spawn 系统调用
 // ALERT: 注意在实现 SPAWN 时不需要复制父进程地址空间，SPAWN != FORK + EXEC
-pub fn sys_spawn(path: *const u8) -> isize {
+/// Load the app `path` in a new child process and return its pid, or -1 if
+/// there is no such app, `flags` has unknown bits or resources run out,
+/// RLIMIT_NPROC included.
+pub fn sys_spawn(path: *const u8, flags: usize) -> isize {
+    if flags & !SPAWN_INHERIT_PRIO != 0 {
+        return -1;
+    }
     let token = current_user_token();
-    let path = translated_str(token, path);
+    let path = match translated_str(token, path, PATH_MAX) {
+        Ok(path) => path,
+        Err(err) => {
+            error!("spawn err: {:?}", err);
+            return -1;
+        }
+    };
+    if task_count() >= MAX_TASKS {
+        return -1;
+    }
     if let Some(data) = get_app_data_by_name(path.as_str()) {
         let current_task = current_task().unwrap();
-        let new_task = current_task.spawn(data);
+        if !below_nproc_limit(&current_task) {
+            return -1;
+        }
+        let new_task =
+            match current_task.spawn(path.as_str(), data, flags & SPAWN_INHERIT_PRIO != 0) {
+                Ok(new_task) => new_task,
+                Err(err) => {
+                    error!("spawn err: {:?}", err);
+                    return -1;
+                }
+            };
         let new_pid = new_task.pid.0;
         // add new task to scheduler
         add_task(new_task);
@@ -224,3 +1411,153 @@ pub fn sys_spawn(path: *const u8) -> isize {
         -1
     }
 }
+
+/// Write the names of the apps that can be loaded to `buf`, each followed by a
+/// newline and cut to `cap` bytes in all. Return the length of the whole list,
+/// more than `cap` if it did not fit, or `-EFAULT` for a bad buffer.
+pub fn sys_list_apps(buf: *mut u8, cap: usize) -> isize {
+    let mut list = String::new();
+    for name in app_names() {
+        list.push_str(name);
+        list.push('\n');
+    }
+    let len = cap.min(list.len());
+    if !copy_bytes_to_user(current_user_token(), buf, &list.as_bytes()[..len]) {
+        return -EFAULT;
+    }
+    list.len() as isize
+}
+
+/// Zero the syscall counters of the current task and restart its running time,
+/// so the next `sys_task_info` only reports what happens after this call
+pub fn sys_reset_task_info() -> isize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .addtion_info
+        .reset();
+    0
+}
+
+/// Whether `task` is a child of `ancestor` or of one of its descendants
+fn is_descendant(task: &Arc<TaskControlBlock>, ancestor: &Arc<TaskControlBlock>) -> bool {
+    let parent_of = |task: &Arc<TaskControlBlock>| {
+        let inner = task.inner_exclusive_access();
+        inner.parent.as_ref().and_then(|parent| parent.upgrade())
+    };
+    let mut parent = parent_of(task);
+    while let Some(task) = parent {
+        if Arc::ptr_eq(&task, ancestor) {
+            return true;
+        }
+        parent = parent_of(&task);
+    }
+    false
+}
+
+/// Copy `len` bytes at `remote_addr` in the process `pid`, which has to be a
+/// descendant of the current task, to `local_buf`, so a debugger can look
+/// into it. The pages of the target are not faulted in. The trap context of
+/// a task the caller stopped with `sys_trace_step` is readable as well, at
+/// `TRAP_CONTEXT` for a main thread. Return `len`, -1 if
+/// `pid` is not a descendant or some page of the range is not mapped in it,
+/// `-EFAULT` if `local_buf` is not writable; nothing is copied then.
+pub fn sys_process_vm_read(
+    pid: usize,
+    remote_addr: usize,
+    local_buf: *mut u8,
+    len: usize,
+) -> isize {
+    let target = match pid2task(pid) {
+        Some(target) if is_descendant(&target, &current_task().unwrap()) => target,
+        _ => return -1,
+    };
+    // faulting in the local pages may swap out remote ones, which are not
+    // pinned, so the remote pages are looked up right before the copy
+    let local = match translated_user_buffer(current_user_token(), local_buf, len, true) {
+        Some(local) => local,
+        None => return -EFAULT,
+    };
+    let remote_token = target.inner_exclusive_access().get_user_token();
+    let remote = match traced_trap_cx(&target, remote_addr, len) {
+        Some(bytes) => vec![bytes],
+        None => match translated_foreign_buffer(remote_token, remote_addr, len) {
+            Some(remote) => remote,
+            None => return -1,
+        },
+    };
+    for (dst, src) in local
+        .into_iter()
+        .zip(remote.iter().flat_map(|bytes| bytes.iter()))
+    {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    len as isize
+}
+
+/// The `len` bytes at `addr` in the trap context of `tracee` if they are
+/// inside it and it is stopped by the current task. Its page is not one user
+/// space can read.
+fn traced_trap_cx(
+    tracee: &Arc<TaskControlBlock>,
+    addr: usize,
+    len: usize,
+) -> Option<&'static [u8]> {
+    let inner = tracee.inner_exclusive_access();
+    let offset = addr.checked_sub(thread_trap_cx_va(inner.slot))?;
+    if !inner.stopped_by(&current_task().unwrap())
+        || offset.checked_add(len)? > core::mem::size_of::<TrapContext>()
+    {
+        return None;
+    }
+    let trap_cx = inner.get_trap_cx() as *const TrapContext as *const u8;
+    Some(unsafe { core::slice::from_raw_parts(trap_cx.add(offset), len) })
+}
+
+/// Let the process `pid`, a descendant of the current task, run until it is
+/// about to make a syscall and stop it there, blocking the caller until then.
+/// Once stopped it makes that syscall on the next step. Its trap context can
+/// be read with `sys_process_vm_read` while it is stopped. Tracing lasts
+/// until either task exits. Return the id of the syscall it stopped at, or
+/// -1 if `pid` is not a descendant or is traced by another task, exits
+/// before it stops, or the caller is killed meanwhile.
+pub fn sys_trace_step(pid: usize) -> isize {
+    let current = current_task().unwrap();
+    let tracee = match pid2task(pid) {
+        Some(tracee) if is_descendant(&tracee, &current) => tracee,
+        _ => return -1,
+    };
+    let mut tracee_inner = tracee.inner_exclusive_access();
+    let resume = match tracee_inner.trace.as_mut() {
+        Some(trace) if trace.tracer.as_ptr() != Arc::as_ptr(&current) => return -1,
+        Some(trace) => core::mem::replace(&mut trace.stopped, false),
+        None => {
+            tracee_inner.trace = Some(Trace {
+                tracer: Arc::downgrade(&current),
+                stopped: false,
+            });
+            false
+        }
+    };
+    drop(tracee_inner);
+    if resume {
+        wakeup_task(tracee.clone());
+    }
+    loop {
+        let tracee_inner = tracee.inner_exclusive_access();
+        if tracee_inner.is_zombie() {
+            return -1;
+        }
+        if tracee_inner.stopped_by(&current) {
+            return tracee_inner.get_trap_cx().x[17] as isize;
+        }
+        drop(tracee_inner);
+        if check_signals_error_of_current().is_some() {
+            return -1;
+        }
+        current.inner_exclusive_access().waiting_for_tracee = true;
+        block_current_and_run_next();
+    }
+}
@@ -0,0 +1,204 @@
+//! Synchronization syscalls for the threads of a process
+
+use super::errno::{EAGAIN, EFAULT};
+use crate::mm::try_translated_refmut;
+use crate::sync::{futex_wait, futex_wake, Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore};
+use crate::task::{current_task, current_user_token};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Put `object` into the lowest free id of `table` and return the id
+fn alloc_id<T: ?Sized>(table: &mut Vec<Option<Arc<T>>>, object: Arc<T>) -> usize {
+    if let Some(id) = (0..table.len()).find(|id| table[*id].is_none()) {
+        table[id] = Some(object);
+        id
+    } else {
+        table.push(Some(object));
+        table.len() - 1
+    }
+}
+
+/// The mutex `mutex_id` of the current process, None if it does not exist
+fn get_mutex(mutex_id: usize) -> Option<Arc<dyn Mutex>> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mutex_list = inner.mutex_list.exclusive_access();
+    mutex_list.get(mutex_id).cloned().flatten()
+}
+
+/// Create a mutex and return its id, the ids of destroyed mutexes are reused.
+/// Waiters of a blocking mutex sleep, those of the other kind keep yielding.
+pub fn sys_mutex_create(blocking: bool) -> isize {
+    let mutex: Arc<dyn Mutex> = if blocking {
+        Arc::new(MutexBlocking::new())
+    } else {
+        Arc::new(MutexSpin::new())
+    };
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut mutex_list = inner.mutex_list.exclusive_access();
+    alloc_id(&mut mutex_list, mutex) as isize
+}
+
+/// Return -1 if the mutex does not exist or the current thread holds it
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let mutex = match get_mutex(mutex_id) {
+        Some(mutex) => mutex,
+        None => return -1,
+    };
+    let tid = current_task().unwrap().inner_exclusive_access().tid;
+    // no borrow is held here, locking may switch to other tasks
+    if mutex.lock(tid) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Return -1 if the mutex does not exist or the current thread does not hold it
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    let mutex = match get_mutex(mutex_id) {
+        Some(mutex) => mutex,
+        None => return -1,
+    };
+    let tid = current_task().unwrap().inner_exclusive_access().tid;
+    if mutex.unlock(tid) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Destroy a mutex, its id means nothing until it is reused. Return -1 if
+/// the mutex does not exist or is held.
+pub fn sys_mutex_destroy(mutex_id: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut mutex_list = inner.mutex_list.exclusive_access();
+    match mutex_list.get_mut(mutex_id) {
+        Some(slot) if slot.as_ref().map_or(false, |mutex| !mutex.is_locked()) => {
+            *slot = None;
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// The semaphore `sem_id` of the current process, None if it does not exist
+fn get_semaphore(sem_id: usize) -> Option<Arc<Semaphore>> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let semaphore_list = inner.semaphore_list.exclusive_access();
+    semaphore_list.get(sem_id).cloned().flatten()
+}
+
+/// Create a semaphore with `res_count` resources and return its id
+pub fn sys_semaphore_create(res_count: usize) -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut semaphore_list = inner.semaphore_list.exclusive_access();
+    alloc_id(&mut semaphore_list, Arc::new(Semaphore::new(res_count))) as isize
+}
+
+/// Return -1 if the semaphore does not exist
+pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    match get_semaphore(sem_id) {
+        Some(semaphore) => {
+            semaphore.up();
+            0
+        }
+        None => -1,
+    }
+}
+
+/// Return -1 if the semaphore does not exist
+pub fn sys_semaphore_down(sem_id: usize) -> isize {
+    match get_semaphore(sem_id) {
+        Some(semaphore) => {
+            // no borrow is held here, waiting switches to other tasks
+            semaphore.down();
+            0
+        }
+        None => -1,
+    }
+}
+
+/// The condvar `condvar_id` of the current process, None if it does not exist
+fn get_condvar(condvar_id: usize) -> Option<Arc<Condvar>> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let condvar_list = inner.condvar_list.exclusive_access();
+    condvar_list.get(condvar_id).cloned().flatten()
+}
+
+/// Create a condition variable and return its id
+pub fn sys_condvar_create() -> isize {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let mut condvar_list = inner.condvar_list.exclusive_access();
+    alloc_id(&mut condvar_list, Arc::new(Condvar::new())) as isize
+}
+
+/// Wake up a thread waiting on the condvar, return -1 if it does not exist
+pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+    match get_condvar(condvar_id) {
+        Some(condvar) => {
+            condvar.signal();
+            0
+        }
+        None => -1,
+    }
+}
+
+/// Release the mutex, wait for a signal on the condvar and take the mutex
+/// again. Return -1 if either does not exist or the current thread does not
+/// hold the mutex.
+pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    let (condvar, mutex) = match (get_condvar(condvar_id), get_mutex(mutex_id)) {
+        (Some(condvar), Some(mutex)) => (condvar, mutex),
+        _ => return -1,
+    };
+    let tid = current_task().unwrap().inner_exclusive_access().tid;
+    // no borrow is held here, waiting switches to other tasks
+    if condvar.wait(mutex, tid) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// `op` of sys_futex: block while the word is `val`
+const FUTEX_WAIT: usize = 0;
+/// `op` of sys_futex: wake up to `val` waiters
+const FUTEX_WAKE: usize = 1;
+
+/// Block until woken if the aligned word at `uaddr` is `val`, else return
+/// `-EAGAIN` at once; the check and the block are atomic. Or wake up to
+/// `val` tasks blocked on the word and return how many. The word needs to
+/// be writable, and the tasks meet on it through its physical address
+/// wherever it is mapped. Return -1 for another `op` or a misaligned
+/// `uaddr`, `-EFAULT` if it is not writable.
+pub fn sys_futex(uaddr: *mut u32, op: usize, val: u32) -> isize {
+    if uaddr as usize % core::mem::size_of::<u32>() != 0 {
+        return -1;
+    }
+    // faulted in for writing, a copy-on-write page gets its own frame now
+    // and not once the waiters sleep on the old one
+    let word = match try_translated_refmut(current_user_token(), uaddr) {
+        Some(word) => word,
+        None => return -EFAULT,
+    };
+    let key = word as *mut u32 as usize;
+    match op {
+        FUTEX_WAIT => {
+            // nothing else runs until the current task is in the queue
+            if *word != val {
+                return -EAGAIN;
+            }
+            futex_wait(key);
+            0
+        }
+        FUTEX_WAKE => futex_wake(key, val as usize) as isize,
+        _ => -1,
+    }
+}
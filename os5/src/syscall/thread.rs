@@ -0,0 +1,29 @@
+//! Thread management syscalls
+
+use crate::config::MAX_TASKS;
+use crate::task::{add_task, current_task, task_count};
+
+/// Create a thread in the current process, starting at `entry` with `arg` in
+/// `a0`. Return its tid, or -1 if there are too many tasks or frames run out.
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    // a thread holds a pid for its kernel stack like any other task
+    if task_count() >= MAX_TASKS {
+        return -1;
+    }
+    let task = current_task().unwrap();
+    let new_task = match task.create_thread(entry, arg) {
+        Ok(new_task) => new_task,
+        Err(err) => {
+            error!("thread_create err: {:?}", err);
+            return -1;
+        }
+    };
+    let new_tid = new_task.inner_exclusive_access().tid;
+    add_task(new_task);
+    new_tid as isize
+}
+
+/// The tid of the current thread in its process, 0 for the main thread
+pub fn sys_gettid() -> isize {
+    current_task().unwrap().inner_exclusive_access().tid as isize
+}
@@ -3,35 +3,118 @@
 //! It is only used to manage processes and schedule process based on ready queue.
 //! Other CPU process monitoring functions are in Processor.
 
-use super::TaskControlBlock;
+use super::{hart_id, TaskControlBlock};
+use crate::config::AGING_THRESHOLD;
 use crate::sync::UPSafeCell;
-use alloc::collections::BinaryHeap;
+use alloc::collections::{BTreeMap, VecDeque};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
 
-// pub struct TaskManager {
-//     ready_queue: VecDeque<Arc<TaskControlBlock>>,
-// }
+/// How the next task to run is picked from the ready queue
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// The smallest stride first, the stride of a task grows slower the
+    /// higher its priority. The default.
+    Stride,
+    /// First in first out, the priorities are ignored. Every task runs for
+    /// `BASE_TIME_SLICE` ticks before it is preempted.
+    RoundRobin,
+}
+
+/// Whether the affinity of `task` lets it run on this hart
+fn runs_here(task: &Arc<TaskControlBlock>) -> bool {
+    task.inner_exclusive_access().cpu_affinity & (1 << hart_id()) != 0
+}
 
 pub struct TaskManager {
-    ready_queue: BinaryHeap<Arc<TaskControlBlock>>,
+    /// Ready tasks in the order they got ready, whatever the policy
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+    /// Ready task to run before any in the queue, whatever its stride
+    next: Option<Arc<TaskControlBlock>>,
+    policy: SchedPolicy,
 }
 
-// YOUR JOB: FIFO->Stride
-/// A simple FIFO scheduler.
 impl TaskManager {
     pub fn new() -> Self {
         Self {
-            ready_queue: BinaryHeap::new(),
+            ready_queue: VecDeque::new(),
+            next: None,
+            policy: SchedPolicy::Stride,
         }
     }
     /// Add process back to ready queue
     pub fn add(&mut self, task: Arc<TaskControlBlock>) {
-        self.ready_queue.push(task);
+        self.ready_queue.push_back(task);
     }
-    /// Take a process out of the ready queue
+    /// Index in the ready queue of the task to run next under the policy,
+    /// of those whose affinity lets them run on this hart. Of the tasks with
+    /// the smallest stride the one ready first wins.
+    fn pick(&self) -> Option<usize> {
+        let mut runnable =
+            (0..self.ready_queue.len()).filter(|&idx| runs_here(&self.ready_queue[idx]));
+        match self.policy {
+            SchedPolicy::Stride => runnable.reduce(|best, idx| {
+                // the smaller stride is the greater task
+                if self.ready_queue[idx] > self.ready_queue[best] {
+                    idx
+                } else {
+                    best
+                }
+            }),
+            SchedPolicy::RoundRobin => runnable.next(),
+        }
+    }
+    /// Take a process out of the ready queue, the tasks that may not run on
+    /// this hart stay there
     pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
-        self.ready_queue.pop()
+        if let Some(task) = self.next.take() {
+            if runs_here(&task) {
+                return Some(task);
+            }
+            self.ready_queue.push_front(task);
+        }
+        let idx = self.pick()?;
+        self.ready_queue.remove(idx)
+    }
+    /// Make the ready task `pid` the next one to run, return false if it is
+    /// not in the ready queue. Its stride is left alone, it is charged as
+    /// usual once it has run.
+    pub fn move_to_front(&mut self, pid: usize) -> bool {
+        let found = self
+            .ready_queue
+            .iter()
+            .position(|task| task.getpid() == pid);
+        if let Some(idx) = found {
+            let task = self.ready_queue.remove(idx).unwrap();
+            // an earlier pick that has not run yet goes back to the queue
+            if let Some(next) = self.next.replace(task) {
+                self.ready_queue.push_front(next);
+            }
+        }
+        found.is_some()
+    }
+    /// Let the tasks that have not run for more than `AGING_THRESHOLD` rounds
+    /// before `round` catch up with the stride of the next task to run, so
+    /// they are not starved. Their order is what it is without aging
+    /// otherwise. Round-robin starves no one, so it does nothing then.
+    pub fn age(&mut self, round: usize) {
+        if self.policy != SchedPolicy::Stride {
+            return;
+        }
+        let stride = match self.pick() {
+            Some(idx) => self.ready_queue[idx]
+                .inner_exclusive_access()
+                .priority
+                .stride(),
+            None => return,
+        };
+        for task in self.ready_queue.iter() {
+            let mut inner = task.inner_exclusive_access();
+            if round - inner.last_run_tick > AGING_THRESHOLD {
+                inner.priority.age_to(stride);
+            }
+        }
     }
 }
 
@@ -39,6 +122,9 @@ lazy_static! {
     /// TASK_MANAGER instance through lazy_static!
     pub static ref TASK_MANAGER: UPSafeCell<TaskManager> =
         unsafe { UPSafeCell::new(TaskManager::new()) };
+    /// Map from pid to the TCB of every process that has not exited
+    pub static ref PID2TCB: UPSafeCell<BTreeMap<usize, Arc<TaskControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
 }
 
 pub fn add_task(task: Arc<TaskControlBlock>) {
@@ -48,3 +134,44 @@ pub fn add_task(task: Arc<TaskControlBlock>) {
 pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
     TASK_MANAGER.exclusive_access().fetch()
 }
+
+/// See `TaskManager::age`
+pub fn age_tasks(round: usize) {
+    TASK_MANAGER.exclusive_access().age(round)
+}
+
+/// Make the ready task `pid` the next one to run, false if it is not ready
+pub fn move_to_front(pid: usize) -> bool {
+    TASK_MANAGER.exclusive_access().move_to_front(pid)
+}
+
+pub fn sched_policy() -> SchedPolicy {
+    TASK_MANAGER.exclusive_access().policy
+}
+
+/// Switch to `policy`, return the one in use before. The ready tasks keep
+/// their strides, round-robin does not touch them.
+pub fn set_sched_policy(policy: SchedPolicy) -> SchedPolicy {
+    core::mem::replace(&mut TASK_MANAGER.exclusive_access().policy, policy)
+}
+
+pub fn pid2task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    let map = PID2TCB.exclusive_access();
+    map.get(&pid).map(Arc::clone)
+}
+
+/// All processes that have not exited, ordered by pid
+pub fn list_tasks() -> Vec<Arc<TaskControlBlock>> {
+    PID2TCB.exclusive_access().values().cloned().collect()
+}
+
+pub fn insert_into_pid2task(pid: usize, task: Arc<TaskControlBlock>) {
+    PID2TCB.exclusive_access().insert(pid, task);
+}
+
+pub fn remove_from_pid2task(pid: usize) {
+    let mut map = PID2TCB.exclusive_access();
+    if map.remove(&pid).is_none() {
+        panic!("cannot find pid {} in pid2task!", pid);
+    }
+}
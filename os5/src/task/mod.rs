@@ -13,24 +13,191 @@ mod context;
 mod manager;
 mod pid;
 mod processor;
+mod signal;
 mod switch;
 #[allow(clippy::module_inception)]
 mod task;
 
+use crate::sync::futex_remove_waiter;
 use crate::{loader::get_app_data_by_name, timer::get_time_us};
 use alloc::sync::Arc;
 use lazy_static::*;
-use manager::fetch_task;
+use manager::{age_tasks, fetch_task, remove_from_pid2task};
+use processor::park_exited_task;
+pub use signal::{SignalFlags, MAX_SIG};
 use switch::__switch;
-pub use task::{TaskControlBlock, TaskStatus};
+pub use task::{Rlimit, TaskControlBlock, TaskStatus, Trace};
 
 pub use context::TaskContext;
-pub use manager::add_task;
-pub use pid::{pid_alloc, KernelStack, PidHandle};
+pub use manager::{
+    add_task, insert_into_pid2task, list_tasks, move_to_front, pid2task, sched_policy,
+    set_sched_policy, SchedPolicy,
+};
+#[cfg(feature = "stack-overflow-test")]
+pub use pid::kernel_stack_overflow_test;
+pub use pid::{kernel_stack_canary_test, pid_alloc, task_count, KernelStack, PidHandle};
 pub use processor::{
-    current_task, current_trap_cx, current_user_token, run_tasks, schedule, take_current_task,
+    cpu_stats, current_task, current_task_name, current_trap_cx, current_trap_cx_user_va,
+    current_user_token, deterministic_sched, hart_id, run_tasks, schedule, set_deterministic_sched,
+    take_current_task,
 };
 
+/// Make current task blocked and switch to the next task,
+/// it runs again only after someone calls [`wakeup_task`] on it
+pub fn block_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    task_inner.priority.update();
+    task_inner.addtion_info.charge_kernel_time();
+    task_inner.task_status = TaskStatus::Blocking;
+    drop(task_inner);
+    schedule(task_cx_ptr);
+}
+
+/// Undo the fork of `child`, which has never run: it is dropped as if it had
+/// not been created
+pub fn discard_child(child: Arc<TaskControlBlock>) {
+    let parent = child.inner_exclusive_access().parent.clone();
+    if let Some(parent) = parent.and_then(|parent| parent.upgrade()) {
+        parent
+            .inner_exclusive_access()
+            .children
+            .retain(|task| !Arc::ptr_eq(task, &child));
+    }
+    remove_from_pid2task(child.getpid());
+    // break the reference cycle through its thread list
+    let threads = child.inner_exclusive_access().threads.clone();
+    threads.exclusive_access().clear();
+}
+
+/// Make a blocked task ready and push it back to the ready queue
+pub fn wakeup_task(task: Arc<TaskControlBlock>) {
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.task_status = TaskStatus::Ready;
+    drop(task_inner);
+    add_task(task);
+}
+
+/// Wake up `task` if it blocks in `sys_waitpid` waiting for a child to exit
+pub fn wakeup_child_waiter(task: Arc<TaskControlBlock>) {
+    let mut task_inner = task.inner_exclusive_access();
+    if !task_inner.waiting_for_child {
+        return;
+    }
+    task_inner.waiting_for_child = false;
+    drop(task_inner);
+    wakeup_task(task);
+}
+
+/// Wake up `task` if it blocks in `sys_trace_step` waiting for its tracee
+pub fn wakeup_tracer(task: Arc<TaskControlBlock>) {
+    let mut task_inner = task.inner_exclusive_access();
+    if !task_inner.waiting_for_tracee {
+        return;
+    }
+    task_inner.waiting_for_tracee = false;
+    drop(task_inner);
+    wakeup_task(task);
+}
+
+/// Clear why `task` blocks in `sys_trace_step` or at a syscall stop, false
+/// if it blocks for neither
+fn leave_trace_block(task: &Arc<TaskControlBlock>) -> bool {
+    let mut task_inner = task.inner_exclusive_access();
+    if task_inner.waiting_for_tracee {
+        task_inner.waiting_for_tracee = false;
+        return true;
+    }
+    match task_inner.trace.as_mut() {
+        Some(trace) if trace.stopped => {
+            trace.stopped = false;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Let the tasks traced by `tracer`, which exits, go on untraced
+fn release_tracees(tracer: &Arc<TaskControlBlock>) {
+    for task in list_tasks() {
+        if Arc::ptr_eq(&task, tracer) {
+            continue;
+        }
+        let mut task_inner = task.inner_exclusive_access();
+        let stopped = match task_inner.trace.as_ref() {
+            Some(trace) if trace.tracer.as_ptr() == Arc::as_ptr(tracer) => trace.stopped,
+            _ => continue,
+        };
+        task_inner.trace = None;
+        drop(task_inner);
+        if stopped {
+            wakeup_task(task);
+        }
+    }
+}
+
+/// Stop the current task before the syscall it is about to make if a tracer
+/// steps it: the tracer wakes up and the task blocks until the next step.
+/// Return false if a signal that terminates the task came meanwhile, the
+/// syscall is not made then.
+pub fn trace_stop_current() -> bool {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let tracer = match task_inner.trace.as_mut() {
+        Some(trace) if !trace.stopped => {
+            trace.stopped = true;
+            trace.tracer.upgrade()
+        }
+        _ => return true,
+    };
+    let tracer = match tracer {
+        Some(tracer) => tracer,
+        None => {
+            task_inner.trace = None;
+            return true;
+        }
+    };
+    drop(task_inner);
+    drop(task);
+    wakeup_tracer(tracer);
+    block_current_and_run_next();
+    check_signals_error_of_current().is_none()
+}
+
+/// Wake up a task blocked on a mutex, a semaphore or a condvar of its process,
+/// in `sys_waitpid`, in `sys_trace_step` or at a syscall stop, once a signal
+/// terminates it, so it gets back to the trap handler to exit
+pub fn wakeup_killed_task(task: Arc<TaskControlBlock>) {
+    let inner = task.inner_exclusive_access();
+    let mutex_list = inner.mutex_list.clone();
+    let semaphore_list = inner.semaphore_list.clone();
+    let condvar_list = inner.condvar_list.clone();
+    drop(inner);
+    let removed = mutex_list
+        .exclusive_access()
+        .iter()
+        .flatten()
+        .any(|mutex| mutex.remove_waiter(&task))
+        || semaphore_list
+            .exclusive_access()
+            .iter()
+            .flatten()
+            .any(|semaphore| semaphore.remove_waiter(&task))
+        || condvar_list
+            .exclusive_access()
+            .iter()
+            .flatten()
+            .any(|condvar| condvar.remove_waiter(&task))
+        || futex_remove_waiter(&task)
+        || leave_trace_block(&task);
+    if removed {
+        wakeup_task(task);
+    } else {
+        wakeup_child_waiter(task);
+    }
+}
+
 /// Make current task suspended and switch to the next task
 pub fn suspend_current_and_run_next() {
     // There must be an application running.
@@ -41,6 +208,7 @@ pub fn suspend_current_and_run_next() {
     let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
     // Change status to Ready
     task_inner.priority.update();
+    task_inner.addtion_info.charge_kernel_time();
     task_inner.task_status = TaskStatus::Ready;
     drop(task_inner);
     // ---- release current PCB
@@ -51,36 +219,125 @@ pub fn suspend_current_and_run_next() {
     schedule(task_cx_ptr);
 }
 
-/// Exit current task, recycle process resources and switch to the next task
+/// Charge a timer tick to the current task, return true if its time slice
+/// has run out and it should be preempted
+pub fn tick_current_task() -> bool {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.priority.tick()
+}
+
+/// Exit current task with `exit_code`, recycle process resources and switch
+/// to the next task
+///
+/// The exit of the main thread ends the process: the other threads are
+/// killed and the address space goes away with the last of them.
 pub fn exit_current_and_run_next(exit_code: i32) {
+    // WEXITSTATUS is the low 8 bits of the code in bits 8..16
+    exit_current_with_status(exit_code, (exit_code & 0xff) << 8);
+}
+
+/// Terminate current task by signal `signum` like `exit_current_and_run_next`
+pub fn kill_current_and_run_next(signum: i32) {
+    // WTERMSIG is in the low 7 bits
+    exit_current_with_status(-signum, signum & 0x7f);
+}
+
+/// Exit current task with `exit_code`, `status` is its encoded form that
+/// sys_waitpid reports with WSTATUS
+fn exit_current_with_status(exit_code: i32, status: i32) {
     // take from Processor
     let task = take_current_task().unwrap();
     // **** access current TCB exclusively
     let mut inner = task.inner_exclusive_access();
+    // the syscall it exits in never returns
+    inner.unpin_user_pages();
     // Change status to Zombie
     inner.task_status = TaskStatus::Zombie;
     inner.addtion_info.time = get_time_us() - inner.addtion_info.time;
+    inner.addtion_info.charge_kernel_time();
     // Record exit code
     inner.exit_code = exit_code;
-    // do not move to its parent but under initproc
+    inner.exit_status = status;
+    // the parent finds the zombie once it runs again, it can not have
+    // looked in between and missed it
+    if let Some(parent) = inner.parent.as_ref().and_then(|parent| parent.upgrade()) {
+        wakeup_child_waiter(parent);
+    }
+    if let Some(tracer) = inner
+        .trace
+        .as_ref()
+        .and_then(|trace| trace.tracer.upgrade())
+    {
+        wakeup_tracer(tracer);
+    }
+    // do not move to its parent but under initproc, zombie children will
+    // never be waited for and are dropped right away to free them
 
     // ++++++ access initproc TCB exclusively
     {
         let mut initproc_inner = INITPROC.inner_exclusive_access();
         for child in inner.children.iter() {
-            child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
+            let mut child_inner = child.inner_exclusive_access();
+            if child_inner.is_zombie() {
+                continue;
+            }
+            child_inner.parent = Some(Arc::downgrade(&INITPROC));
             initproc_inner.children.push(child.clone());
         }
     }
     // ++++++ release parent PCB
 
     inner.children.clear();
-    // deallocate user space
-    inner.memory_set.recycle_data_pages();
+    let threads = inner.threads.clone();
+    if inner.tid != 0 {
+        // only the thread goes away, the process goes on
+        let slot = inner.slot;
+        inner.memory_set.exclusive_access().unmap_thread_slot(slot);
+        if let Some(thread) = threads.exclusive_access().get_mut(inner.tid) {
+            *thread = None;
+        }
+    } else {
+        // signals can not be sent to it any more
+        remove_from_pid2task(task.getpid());
+        // the other threads die before they get back to user space, clearing
+        // the list also breaks the reference cycle through it
+        for thread in threads.exclusive_access().drain(..).flatten() {
+            if !Arc::ptr_eq(&thread, &task) {
+                thread.inner_exclusive_access().signals |= SignalFlags::SIGKILL;
+                // one waiting on a futex wakes up to die as well
+                if futex_remove_waiter(&thread) {
+                    wakeup_task(thread);
+                }
+            }
+        }
+        // killed threads waiting for its mutexes, semaphores and condvars
+        // wake up to die, the tables go away with it
+        let mutexes = core::mem::take(&mut *inner.mutex_list.exclusive_access());
+        for mutex in mutexes.into_iter().flatten() {
+            mutex.wake_all_waiters();
+        }
+        let semaphores = core::mem::take(&mut *inner.semaphore_list.exclusive_access());
+        for semaphore in semaphores.into_iter().flatten() {
+            semaphore.wake_all_waiters();
+        }
+        let condvars = core::mem::take(&mut *inner.condvar_list.exclusive_access());
+        for condvar in condvars.into_iter().flatten() {
+            condvar.wake_all_waiters();
+        }
+        // close the files, so readers of its pipes see the end of them
+        inner.fd_table.exclusive_access().clear();
+        // deallocate user space, unless killed threads still run in it
+        if Arc::strong_count(&inner.memory_set) == 1 {
+            inner.memory_set.exclusive_access().recycle_data_pages();
+        }
+    }
     drop(inner);
     // **** release current PCB
-    // drop task manually to maintain rc correctly
-    drop(task);
+    release_tracees(&task);
+    // the last reference to a thread may be this one, keep it until the
+    // switch away from its kernel stack
+    park_exited_task(task);
     // we do not have to save task context
     let mut _unused = TaskContext::zero_init();
     schedule(&mut _unused as *mut _);
@@ -91,11 +348,96 @@ lazy_static! {
     ///
     /// the name "initproc" may be changed to any other app name like "usertests",
     /// but we have user_shell, so we don't need to change it.
-    pub static ref INITPROC: Arc<TaskControlBlock> = Arc::new(TaskControlBlock::new(
-        get_app_data_by_name("ch5b_initproc").unwrap()
-    ));
+    pub static ref INITPROC: Arc<TaskControlBlock> =
+        TaskControlBlock::new("ch5b_initproc", get_app_data_by_name("ch5b_initproc").unwrap());
 }
 
 pub fn add_initproc() {
+    insert_into_pid2task(INITPROC.getpid(), INITPROC.clone());
     add_task(INITPROC.clone());
 }
+
+/// Raise SIGALRM for the current task if its alarm has gone off. Alarms are
+/// only checked on timer interrupts while their task is running.
+pub fn check_alarm_of_current() {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    if let Some(expire_us) = task_inner.alarm_expire_us {
+        if expire_us <= get_time_us() {
+            task_inner.alarm_expire_us = None;
+            task_inner.signals |= SignalFlags::SIGALRM;
+        }
+    }
+}
+
+/// Raise SIGXCPU for the current task once its user and kernel time is over
+/// its soft RLIMIT_CPU and again each second of it after that, or SIGKILL if
+/// over the hard one. Like alarms, budgets are only checked on timer
+/// interrupts while their task is running, so one may be overrun by a tick.
+pub fn check_cpu_limit_of_current() {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let cpu_us = task_inner.addtion_info.utime + task_inner.addtion_info.stime;
+    let cpu_secs = cpu_us / 1_000_000;
+    let limit = task_inner.rlimits.cpu;
+    if cpu_secs >= limit.max {
+        task_inner.signals |= SignalFlags::SIGKILL;
+    } else if cpu_secs >= limit.cur
+        && task_inner
+            .xcpu_raised_us
+            .map_or(true, |raised_us| cpu_us - raised_us >= 1_000_000)
+    {
+        task_inner.signals |= SignalFlags::SIGXCPU;
+        task_inner.xcpu_raised_us = Some(cpu_us);
+    }
+}
+
+/// Divert the current task to the handler of a pending signal it has one
+/// for, with the signal number in `a0`. Masked signals stay pending. The
+/// interrupted trap context is saved for `sys_sigreturn`, handlers do not
+/// nest.
+pub fn handle_signals_of_current() {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    if task_inner.trap_ctx_backup.is_some() {
+        return;
+    }
+    for signum in 1..=MAX_SIG {
+        let flag = SignalFlags::from_signum(signum as i32).unwrap();
+        let handler = task_inner.signal_actions[signum];
+        let pending = task_inner.signals - task_inner.signal_mask;
+        if handler != 0 && pending.contains(flag) {
+            task_inner.signals.remove(flag);
+            let trap_cx = task_inner.get_trap_cx();
+            task_inner.trap_ctx_backup = Some(*trap_cx);
+            trap_cx.sepc = handler;
+            trap_cx.x[10] = signum;
+            return;
+        }
+    }
+}
+
+/// Record a fault of the current task at `addr` with cause `cause` for
+/// `sys_last_fault`, and raise SIGSEGV if the task has a handler for it that
+/// can run now. Return false if the task has to die of the fault instead.
+pub fn fault_current(addr: usize, cause: usize) -> bool {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.last_fault = Some((addr, cause));
+    let flag = SignalFlags::SIGSEGV;
+    let handler = task_inner.signal_actions[flag.bits().trailing_zeros() as usize];
+    // a fault in a handler would only fault again, handlers do not nest
+    if handler == 0 || task_inner.signal_mask.contains(flag) || task_inner.trap_ctx_backup.is_some()
+    {
+        return false;
+    }
+    task_inner.signals |= flag;
+    true
+}
+
+/// Signal number and message if a pending signal terminates the current task
+pub fn check_signals_error_of_current() -> Option<(i32, &'static str)> {
+    let task = current_task().unwrap();
+    let task_inner = task.inner_exclusive_access();
+    (task_inner.signals - task_inner.signal_mask).check_error()
+}
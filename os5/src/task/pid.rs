@@ -4,7 +4,7 @@
 //! is determined according to the PID.
 
 use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE};
-use crate::mm::{MapPermission, VirtAddr, KERNEL_SPACE};
+use crate::mm::{MapError, MapPermission, MapType, VirtAddr, KERNEL_SPACE};
 use crate::sync::UPSafeCell;
 use alloc::vec::Vec;
 use lazy_static::*;
@@ -32,6 +32,10 @@ impl PidAllocator {
             PidHandle(self.current - 1)
         }
     }
+    /// Number of pids that have not been deallocated
+    pub fn in_use(&self) -> usize {
+        self.current - self.recycled.len()
+    }
     pub fn dealloc(&mut self, pid: usize) {
         assert!(pid < self.current);
         assert!(
@@ -63,6 +67,11 @@ pub fn pid_alloc() -> PidHandle {
     PID_ALLOCATOR.exclusive_access().alloc()
 }
 
+/// Number of tasks holding a pid, zombies included until they are reaped
+pub fn task_count() -> usize {
+    PID_ALLOCATOR.exclusive_access().in_use()
+}
+
 /// Return (bottom, top) of a kernel stack in kernel space.
 pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
     let top = TRAMPOLINE - app_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
@@ -70,6 +79,27 @@ pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
     (bottom, top)
 }
 
+/// Written over the low end of each kernel stack in debug builds, a stack
+/// that grows over it has overflowed before it reaches the guard page below
+const STACK_CANARY: usize = 0x5afe_57ac_c0de_f00d;
+/// Bytes of the canary, more than a frame so that a frame which grows over
+/// it can not skip it without storing to it
+const CANARY_SIZE: usize = 256;
+
+lazy_static! {
+    /// Called with the pid and the address of the canary when a check finds
+    /// it overwritten
+    static ref CANARY_FAILURE: UPSafeCell<fn(usize, usize)> =
+        unsafe { UPSafeCell::new(canary_panic) };
+}
+
+fn canary_panic(pid: usize, canary: usize) {
+    panic!(
+        "[kernel] kernel stack of pid {} overflowed, its canary at {:#x} is overwritten",
+        pid, canary
+    );
+}
+
 /// KernelStack corresponding to PID
 pub struct KernelStack {
     pid: usize,
@@ -77,14 +107,47 @@ pub struct KernelStack {
 
 impl KernelStack {
     pub fn new(pid_handle: &PidHandle) -> Self {
+        Self::try_new(pid_handle).unwrap()
+    }
+    /// Fail without mapping anything if frames run out.
+    pub fn try_new(pid_handle: &PidHandle) -> Result<Self, MapError> {
         let pid = pid_handle.0;
         let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(pid);
-        KERNEL_SPACE.exclusive_access().insert_framed_area(
+        KERNEL_SPACE.exclusive_access().try_map_range(
             kernel_stack_bottom.into(),
             kernel_stack_top.into(),
+            MapType::Framed,
             MapPermission::R | MapPermission::W,
-        );
-        KernelStack { pid: pid_handle.0 }
+            "kernel stack",
+        )?;
+        let kernel_stack = KernelStack { pid: pid_handle.0 };
+        if cfg!(debug_assertions) {
+            kernel_stack.canary().fill(STACK_CANARY);
+        }
+        Ok(kernel_stack)
+    }
+    /// The lowest `CANARY_SIZE` bytes of the stack
+    #[allow(clippy::mut_from_ref)]
+    fn canary(&self) -> &mut [usize] {
+        let (kernel_stack_bottom, _) = kernel_stack_position(self.pid);
+        unsafe {
+            core::slice::from_raw_parts_mut(
+                kernel_stack_bottom as *mut usize,
+                CANARY_SIZE / core::mem::size_of::<usize>(),
+            )
+        }
+    }
+    /// Whether the canary is still there, always true in release builds
+    fn canary_intact(&self) -> bool {
+        !cfg!(debug_assertions) || self.canary().iter().all(|&word| word == STACK_CANARY)
+    }
+    /// Report the stack to `CANARY_FAILURE` if it has grown over its canary,
+    /// called on every trap entry and context switch in debug builds
+    pub fn check_canary(&self) {
+        if !self.canary_intact() {
+            let failure = *CANARY_FAILURE.exclusive_access();
+            failure(self.pid, self.canary().as_ptr() as usize);
+        }
     }
     #[allow(unused)]
     /// Push a variable of type T into the top of the KernelStack and return its raw pointer
@@ -105,6 +168,96 @@ impl KernelStack {
     }
 }
 
+/// Overwrite the canary of a new kernel stack as an overflow would and check
+/// that it is noticed, in debug builds
+pub fn kernel_stack_canary_test() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let pid_handle = pid_alloc();
+    let kernel_stack = KernelStack::new(&pid_handle);
+    assert!(kernel_stack.canary_intact());
+    // the deepest frame of an overflowing stack lands on the lowest word
+    kernel_stack.canary()[0] = 0;
+    assert!(!kernel_stack.canary_intact());
+    kernel_stack.canary()[0] = STACK_CANARY;
+    kernel_stack.check_canary();
+    info!("kernel_stack_canary_test passed!");
+}
+
+/// Pid of the stack `note_overflow` was called for
+#[cfg(feature = "stack-overflow-test")]
+static mut OVERFLOWED: Option<usize> = None;
+
+#[cfg(feature = "stack-overflow-test")]
+fn note_overflow(pid: usize, _canary: usize) {
+    unsafe {
+        OVERFLOWED = Some(pid);
+    }
+}
+
+/// Recurse until the stack has grown over the canary above `bottom`. The
+/// frames only take a few words and nothing is called but itself, so the
+/// stack grows by small steps and never past `bottom`.
+#[cfg(feature = "stack-overflow-test")]
+#[inline(never)]
+fn overflow(bottom: usize, caller_sp: usize) {
+    let sp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, sp", out(reg) sp);
+    }
+    let mut word = bottom;
+    while word < bottom + CANARY_SIZE {
+        if unsafe { *(word as *const usize) } != STACK_CANARY {
+            return;
+        }
+        word += core::mem::size_of::<usize>();
+    }
+    // the next frame is as large as this one
+    if sp - (caller_sp - sp) >= bottom {
+        overflow(bottom, sp);
+    }
+}
+
+#[cfg(feature = "stack-overflow-test")]
+extern "C" fn overflow_entry(bottom: usize) {
+    let sp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, sp", out(reg) sp);
+    }
+    overflow(bottom, sp);
+}
+
+/// Overflow a new kernel stack by deep recursion and check that its canary
+/// notices, built with the `stack-overflow-test` feature
+#[cfg(feature = "stack-overflow-test")]
+pub fn kernel_stack_overflow_test() {
+    let pid_handle = pid_alloc();
+    let kernel_stack = KernelStack::new(&pid_handle);
+    let (bottom, top) = kernel_stack_position(pid_handle.0);
+    let failure = core::mem::replace(&mut *CANARY_FAILURE.exclusive_access(), note_overflow);
+    // run on the new stack, s2 is preserved by the callee and keeps the
+    // stack pointer to return to
+    unsafe {
+        core::arch::asm!(
+            "mv s2, sp",
+            "mv sp, {top}",
+            "jalr {entry}",
+            "mv sp, s2",
+            top = in(reg) top,
+            entry = in(reg) overflow_entry as usize,
+            in("a0") bottom,
+            out("s2") _,
+            clobber_abi("C"),
+        );
+    }
+    assert!(!kernel_stack.canary_intact());
+    kernel_stack.check_canary();
+    *CANARY_FAILURE.exclusive_access() = failure;
+    assert_eq!(unsafe { OVERFLOWED }, Some(pid_handle.0));
+    info!("kernel_stack_overflow_test passed!");
+}
+
 impl Drop for KernelStack {
     fn drop(&mut self) {
         let (kernel_stack_bottom, _) = kernel_stack_position(self.pid);
@@ -5,13 +5,19 @@
 //! and the replacement and transfer of control flow of different applications are executed.
 
 use super::__switch;
-use super::{fetch_task, TaskStatus};
+use super::{age_tasks, fetch_task, sched_policy, TaskStatus};
 use super::{TaskContext, TaskControlBlock};
+use crate::config::{AGING_INTERVAL, DETERMINISTIC_SCHED};
+use crate::console::console_flush;
+use crate::mm::thread_trap_cx_va;
 use crate::sync::UPSafeCell;
-use crate::timer::get_time_us;
+use crate::timer::{check_timer, get_time_us, set_next_trigger};
 use crate::trap::TrapContext;
-use alloc::sync::Arc;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
 use lazy_static::*;
+use riscv::asm::wfi;
+use riscv::register::sip;
 
 /// Processor management structure
 pub struct Processor {
@@ -19,6 +25,18 @@ pub struct Processor {
     current: Option<Arc<TaskControlBlock>>,
     /// The basic control flow of each core, helping to select and switch process
     idle_task_cx: TaskContext,
+    /// The task that ran last, a task picked again right after it is not
+    /// counted as switched in
+    last: Option<Weak<TaskControlBlock>>,
+    /// The task that exited last, it is dropped only after the switch away
+    /// from its kernel stack
+    exited: Option<Arc<TaskControlBlock>>,
+    /// Time spent with nothing to run, in microseconds
+    idle_us: usize,
+    /// Scheduling rounds so far, a round picks a task to run
+    rounds: usize,
+    /// Timer interrupts do not preempt the running task
+    deterministic: bool,
 }
 
 impl Processor {
@@ -26,6 +44,11 @@ impl Processor {
         Self {
             current: None,
             idle_task_cx: TaskContext::zero_init(),
+            last: None,
+            exited: None,
+            idle_us: 0,
+            rounds: 0,
+            deterministic: DETERMINISTIC_SCHED,
         }
     }
     fn get_idle_task_cx_ptr(&mut self) -> *mut TaskContext {
@@ -51,7 +74,19 @@ lazy_static! {
 pub fn run_tasks() {
     loop {
         let mut processor = PROCESSOR.exclusive_access();
+        // the task that has just switched away may have overflowed its
+        // kernel stack, an exited one is still parked with it
+        if let Some(last) = processor.last.as_ref().and_then(Weak::upgrade) {
+            last.kernel_stack.check_canary();
+        }
+        // back on the idle control flow, no one runs on its kernel stack now
+        drop(processor.exited.take());
         if let Some(task) = fetch_task() {
+            processor.rounds += 1;
+            if processor.rounds % AGING_INTERVAL == 0 {
+                age_tasks(processor.rounds);
+            }
+            task.kernel_stack.check_canary();
             let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
             // access coming task TCB exclusively
             let mut task_inner = task.inner_exclusive_access();
@@ -60,31 +95,95 @@ pub fn run_tasks() {
             if task_inner.addtion_info.time == 0 {
                 task_inner.addtion_info.time = get_time_us();
             }
+            task_inner.addtion_info.restart_time_stamp();
+            task_inner.priority.refill_slice(sched_policy());
+            task_inner.last_run_tick = processor.rounds;
+            let rerun = processor
+                .last
+                .as_ref()
+                .map_or(false, |last| last.ptr_eq(&Arc::downgrade(&task)));
+            if !rerun {
+                task_inner.addtion_info.switch_count += 1;
+            }
 
             task_inner.task_status = TaskStatus::Running;
 
             drop(task_inner);
             // release coming task TCB manually
+            processor.last = Some(Arc::downgrade(&task));
             processor.current = Some(task);
             // release processor manually
             drop(processor);
             unsafe {
                 __switch(idle_task_cx_ptr, next_task_cx_ptr);
             }
+        } else {
+            drop(processor);
+            // nothing to run, wait for an interrupt and count the time as
+            // idle. Interrupts are off here, a timer interrupt only ends the
+            // wait, so the next one is set and the sleeping tasks are woken
+            // by hand.
+            let start = get_time_us();
+            unsafe {
+                wfi();
+            }
+            PROCESSOR.exclusive_access().idle_us += get_time_us() - start;
+            if sip::read().stimer() {
+                set_next_trigger();
+            }
+            check_timer();
         }
     }
 }
 
+/// The hart this code runs on, there is only hart 0 for now
+pub fn hart_id() -> usize {
+    0
+}
+
 /// Get current task through take, leaving a None in its place
 pub fn take_current_task() -> Option<Arc<TaskControlBlock>> {
     PROCESSOR.exclusive_access().take_current()
 }
 
+/// Keep the exited current task until the switch away from it, a thread
+/// may hold the last reference to itself
+pub fn park_exited_task(task: Arc<TaskControlBlock>) {
+    PROCESSOR.exclusive_access().exited = Some(task);
+}
+
 /// Get a copy of the current task
 pub fn current_task() -> Option<Arc<TaskControlBlock>> {
     PROCESSOR.exclusive_access().current()
 }
 
+/// Pid and name of the current task, if any. Never panics, so the panic
+/// handler can report it even if the processor or the task is borrowed.
+pub fn current_task_name() -> Option<(usize, String)> {
+    let task = PROCESSOR.try_exclusive_access()?.current()?;
+    let name = task.try_inner_exclusive_access()?.name.clone();
+    Some((task.getpid(), name))
+}
+
+/// Microseconds since boot and how many of them the processor was idle
+pub fn cpu_stats() -> (usize, usize) {
+    (get_time_us(), PROCESSOR.exclusive_access().idle_us)
+}
+
+/// Whether timer interrupts leave the running task alone, see
+/// `DETERMINISTIC_SCHED`
+pub fn deterministic_sched() -> bool {
+    PROCESSOR.exclusive_access().deterministic
+}
+
+/// Turn the deterministic mode on or off, return whether it was on
+pub fn set_deterministic_sched(deterministic: bool) -> bool {
+    core::mem::replace(
+        &mut PROCESSOR.exclusive_access().deterministic,
+        deterministic,
+    )
+}
+
 /// Get token of the address space of current task
 pub fn current_user_token() -> usize {
     let task = current_task().unwrap();
@@ -100,8 +199,15 @@ pub fn current_trap_cx() -> &'static mut TrapContext {
         .get_trap_cx()
 }
 
+/// Get the user space address of the trap context of current task
+pub fn current_trap_cx_user_va() -> usize {
+    let slot = current_task().unwrap().inner_exclusive_access().slot;
+    thread_trap_cx_va(slot)
+}
+
 /// Return to idle control flow for new scheduling
 pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
+    console_flush();
     let mut processor = PROCESSOR.exclusive_access();
     let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
     drop(processor);
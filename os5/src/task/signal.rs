@@ -0,0 +1,71 @@
+//! Signals sent to processes by [`crate::syscall`]'s `sys_kill`
+//!
+//! A signal is only recorded as pending here. It is checked on the way back
+//! to user space unless the task masks it, then a user handler registered by
+//! `sys_sigaction` runs for it, or else SIGKILL, SIGTERM, SIGALRM and
+//! SIGXCPU terminate the task.
+
+/// The largest signal number
+pub const MAX_SIG: usize = 31;
+
+bitflags! {
+    /// Pending signals, bit `signum` stands for signal `signum`
+    pub struct SignalFlags: u32 {
+        const SIGHUP = 1 << 1;
+        const SIGINT = 1 << 2;
+        const SIGQUIT = 1 << 3;
+        const SIGILL = 1 << 4;
+        const SIGTRAP = 1 << 5;
+        const SIGABRT = 1 << 6;
+        const SIGBUS = 1 << 7;
+        const SIGFPE = 1 << 8;
+        const SIGKILL = 1 << 9;
+        const SIGUSR1 = 1 << 10;
+        const SIGSEGV = 1 << 11;
+        const SIGUSR2 = 1 << 12;
+        const SIGPIPE = 1 << 13;
+        const SIGALRM = 1 << 14;
+        const SIGTERM = 1 << 15;
+        const SIGSTKFLT = 1 << 16;
+        const SIGCHLD = 1 << 17;
+        const SIGCONT = 1 << 18;
+        const SIGSTOP = 1 << 19;
+        const SIGTSTP = 1 << 20;
+        const SIGTTIN = 1 << 21;
+        const SIGTTOU = 1 << 22;
+        const SIGURG = 1 << 23;
+        const SIGXCPU = 1 << 24;
+        const SIGXFSZ = 1 << 25;
+        const SIGVTALRM = 1 << 26;
+        const SIGPROF = 1 << 27;
+        const SIGWINCH = 1 << 28;
+        const SIGIO = 1 << 29;
+        const SIGPWR = 1 << 30;
+        const SIGSYS = 1 << 31;
+    }
+}
+
+impl SignalFlags {
+    /// The flag of signal `signum`, None if `signum` is out of range
+    pub fn from_signum(signum: i32) -> Option<Self> {
+        if (1..=MAX_SIG as i32).contains(&signum) {
+            Self::from_bits(1 << signum)
+        } else {
+            None
+        }
+    }
+    /// Signal number and message if a pending signal terminates the task
+    pub fn check_error(&self) -> Option<(i32, &'static str)> {
+        if self.contains(Self::SIGKILL) {
+            Some((9, "Killed, SIGKILL=9"))
+        } else if self.contains(Self::SIGTERM) {
+            Some((15, "Terminated, SIGTERM=15"))
+        } else if self.contains(Self::SIGALRM) {
+            Some((14, "Alarm clock, SIGALRM=14"))
+        } else if self.contains(Self::SIGXCPU) {
+            Some((24, "CPU time limit exceeded, SIGXCPU=24"))
+        } else {
+            None
+        }
+    }
+}
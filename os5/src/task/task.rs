@@ -1,12 +1,24 @@
 //! Types related to task management & Functions for completely changing TCB
 
 use super::TaskContext;
-use super::{pid_alloc, KernelStack, PidHandle};
-use crate::config::{BIG_STRIDE, MAX_SYSCALL_NUM, PRIORITY, TRAP_CONTEXT};
-use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE};
-use crate::sync::UPSafeCell;
+use super::{
+    insert_into_pid2task, pid_alloc, KernelStack, PidHandle, SchedPolicy, SignalFlags, MAX_SIG,
+};
+use crate::config::{
+    ALL_HARTS, BASE_TIME_SLICE, BIG_STRIDE, MAX_FD, MAX_SYSCALL_NUM, MAX_TIME_SLICE, PRIORITY,
+    TASK_NAME_LEN, TRAP_CONTEXT,
+};
+use crate::fs::{File, Stdin, Stdout};
+use crate::mm::{
+    copy_bytes_to_user, copy_to_user, thread_stack_top, thread_trap_cx_va, MapError, MemorySet,
+    PhysPageNum, VirtAddr, VirtPageNum, KERNEL_SPACE,
+};
+use crate::sync::{Condvar, Mutex, Semaphore, UPSafeCell};
+use crate::timer::get_time_us;
 use crate::trap::{trap_handler, TrapContext};
+use alloc::string::String;
 use alloc::sync::{Arc, Weak};
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefMut;
 
@@ -17,6 +29,8 @@ pub struct TaskControlBlock {
     // immutable
     /// Process identifier
     pub pid: PidHandle,
+    /// Pid of the process the thread belongs to, that of its main thread
+    pub process_pid: usize,
     /// Kernel stack corresponding to PID
     pub kernel_stack: KernelStack,
     // mutable
@@ -30,6 +44,9 @@ pub struct TaskControlBlock {
 pub struct TaskControlBlockInner {
     /// The physical page number of the frame where the trap context is placed
     pub trap_cx_ppn: PhysPageNum,
+    /// Slot of the user stack and the trap context of the thread, 0 for
+    /// those of the main thread
+    pub slot: usize,
     /// Application data can only appear in areas
     /// where the application address space is lower than base_size
     pub base_size: usize,
@@ -37,25 +54,109 @@ pub struct TaskControlBlockInner {
     pub task_cx: TaskContext,
     /// Maintain the execution status of the current process
     pub task_status: TaskStatus,
-    /// Application address space
-    pub memory_set: MemorySet,
+    /// Application address space, shared by the threads of the process
+    pub memory_set: Arc<UPSafeCell<MemorySet>>,
     /// Parent process of the current process.
     /// Weak will not affect the reference count of the parent
     pub parent: Option<Weak<TaskControlBlock>>,
     /// A vector containing TCBs of all child processes of the current process
     pub children: Vec<Arc<TaskControlBlock>>,
-    /// It is set when active exit or execution error occurs
+    /// It is set when active exit or execution error occurs, the negated
+    /// signal number if a signal terminates the task
     pub exit_code: i32,
+    /// `exit_code` encoded for WIFEXITED and friends, what sys_waitpid
+    /// reports with WSTATUS
+    pub exit_status: i32,
 
     pub addtion_info: TaskControlBlockAddtionInfo,
 
     // prio
     pub priority: Priority,
+    /// Scheduling round the task last ran in, see `AGING_THRESHOLD`
+    pub last_run_tick: usize,
+    /// Bit i is set if the task may run on hart i, see `HART_COUNT`
+    pub cpu_affinity: usize,
+    pub rlimits: ResourceLimits,
+    /// Blocked in `sys_waitpid` until one of its children exits
+    pub waiting_for_child: bool,
+    /// The tracer stepping the task through its syscalls, if any
+    pub trace: Option<Trace>,
+    /// Blocked in `sys_trace_step` until its tracee stops or exits
+    pub waiting_for_tracee: bool,
+    /// `stval` and `scause` of the last fault the kernel could not resolve,
+    /// reported by `sys_last_fault`
+    pub last_fault: Option<(usize, usize)>,
+
+    /// Signals received but not handled yet
+    pub signals: SignalFlags,
+
+    /// When the pending alarm raises SIGALRM, in microseconds
+    pub alarm_expire_us: Option<usize>,
+    /// User and kernel time in microseconds when SIGXCPU was last raised,
+    /// None before the task went over its soft RLIMIT_CPU
+    pub xcpu_raised_us: Option<usize>,
+    /// Pages the syscall in progress has handed to the kernel, which are
+    /// not swapped out until it returns
+    pub pinned_pages: Vec<VirtPageNum>,
+
+    /// Entry points of the user signal handlers indexed by signal number,
+    /// 0 for the default action
+    pub signal_actions: [usize; MAX_SIG + 1],
+    /// Trap context of the code a signal handler interrupted, restored by
+    /// `sys_sigreturn`
+    pub trap_ctx_backup: Option<TrapContext>,
+    /// Signals kept pending instead of being handled, never SIGKILL
+    pub signal_mask: SignalFlags,
+
+    /// Open files indexed by fd, None for a closed fd, shared by the threads
+    /// and copied by a fork
+    pub fd_table: IdTable<dyn File + Send + Sync>,
+
+    /// Id of the thread in its process, the main thread is 0
+    pub tid: usize,
+    /// Live threads of the process indexed by tid, shared by all of them
+    pub threads: IdTable<TaskControlBlock>,
+    /// Mutexes of the process indexed by id
+    pub mutex_list: IdTable<dyn Mutex>,
+    /// Semaphores of the process indexed by id
+    pub semaphore_list: IdTable<Semaphore>,
+    /// Condition variables of the process indexed by id
+    pub condvar_list: IdTable<Condvar>,
+
+    /// Name of the task, at most `TASK_NAME_LEN` bytes, the app name by
+    /// default
+    pub name: String,
 }
 
+/// Objects of a process indexed by their ids and shared by its threads,
+/// None for an id that is free
+pub type IdTable<T> = Arc<UPSafeCell<Vec<Option<Arc<T>>>>>;
+
 pub struct TaskControlBlockAddtionInfo {
     pub time: usize,
+    /// Times each syscall has been made, indexed by the syscall id
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
+    /// CPU time spent in user mode, in microseconds
+    pub utime: usize,
+    /// CPU time spent in kernel mode, in microseconds
+    pub stime: usize,
+    /// `utime` of the reaped children and their own children
+    pub cutime: usize,
+    /// `stime` of the reaped children and their own children
+    pub cstime: usize,
+    /// Page faults resolved without I/O: lazy allocation, copy-on-write and
+    /// stack growth
+    pub minflt: usize,
+    /// `minflt` of the reaped children and their own children
+    pub cminflt: usize,
+    /// Highest peak of resident pages among the reaped children and their
+    /// own children
+    pub cmaxrss: usize,
+    /// When the CPU time was last charged to the task
+    time_stamp: usize,
+    /// Times the task has been switched in, not counting being picked again
+    /// right after it ran
+    pub switch_count: usize,
 }
 
 impl TaskControlBlockAddtionInfo {
@@ -63,11 +164,50 @@ impl TaskControlBlockAddtionInfo {
         Self {
             time: 0,
             syscall_times: [0; MAX_SYSCALL_NUM],
+            utime: 0,
+            stime: 0,
+            cutime: 0,
+            cstime: 0,
+            minflt: 0,
+            cminflt: 0,
+            cmaxrss: 0,
+            time_stamp: 0,
+            switch_count: 0,
         }
     }
 
+    /// Start charging CPU time from now, e.g. when the task is switched in
+    pub fn restart_time_stamp(&mut self) {
+        self.time_stamp = get_time_us();
+    }
+
+    /// Charge the time since the last stamp to user mode, on trap entry
+    pub fn charge_user_time(&mut self) {
+        let now = get_time_us();
+        self.utime += now - self.time_stamp;
+        self.time_stamp = now;
+    }
+
+    /// Charge the time since the last stamp to kernel mode, on trap return or
+    /// when the task is switched out
+    pub fn charge_kernel_time(&mut self) {
+        let now = get_time_us();
+        self.stime += now - self.time_stamp;
+        self.time_stamp = now;
+    }
+
+    /// Count a syscall of the task. Ids beyond `MAX_SYSCALL_NUM` can not be
+    /// reported in `TaskInfo`, so they are not counted at all.
     pub fn update_syscall_times(&mut self, syscall_id: usize) {
-        self.syscall_times[syscall_id] += 1;
+        if let Some(times) = self.syscall_times.get_mut(syscall_id) {
+            *times += 1;
+        }
+    }
+
+    /// Start counting syscalls and running time over from now
+    pub fn reset(&mut self) {
+        self.syscall_times = [0; MAX_SYSCALL_NUM];
+        self.time = get_time_us();
     }
 }
 
@@ -82,7 +222,22 @@ impl TaskControlBlockInner {
         self.trap_cx_ppn.get_mut()
     }
     pub fn get_user_token(&self) -> usize {
-        self.memory_set.token()
+        self.memory_set.exclusive_access().token()
+    }
+    /// Keep the page `vpn` from being swapped out until the syscall in
+    /// progress returns, the kernel may be holding on to its frame
+    pub fn pin_user_page(&mut self, vpn: VirtPageNum) {
+        if !self.pinned_pages.contains(&vpn) {
+            self.pinned_pages.push(vpn);
+            self.memory_set.exclusive_access().pin(vpn);
+        }
+    }
+    /// Let the pages pinned by the syscall in progress be swapped out again
+    pub fn unpin_user_pages(&mut self) {
+        let mut memory_set = self.memory_set.exclusive_access();
+        for vpn in self.pinned_pages.drain(..) {
+            memory_set.unpin(vpn);
+        }
     }
     fn get_status(&self) -> TaskStatus {
         self.task_status
@@ -90,6 +245,42 @@ impl TaskControlBlockInner {
     pub fn is_zombie(&self) -> bool {
         self.get_status() == TaskStatus::Zombie
     }
+    /// Whether the task is stopped at a syscall by the tracer `tracer`
+    pub fn stopped_by(&self, tracer: &Arc<TaskControlBlock>) -> bool {
+        self.trace.as_ref().map_or(false, |trace| {
+            trace.stopped && trace.tracer.as_ptr() == Arc::as_ptr(tracer)
+        })
+    }
+    /// The lowest closed fd, the table grows if every fd is open, None if
+    /// `MAX_FD` fds are open already
+    pub fn alloc_fd(&self) -> Option<usize> {
+        let mut fd_table = self.fd_table.exclusive_access();
+        if let Some(fd) = (0..fd_table.len()).find(|fd| fd_table[*fd].is_none()) {
+            Some(fd)
+        } else if fd_table.len() < MAX_FD {
+            fd_table.push(None);
+            Some(fd_table.len() - 1)
+        } else {
+            None
+        }
+    }
+    /// A copy of the fd table for a new process, the files are shared
+    fn fork_fd_table(&self) -> IdTable<dyn File + Send + Sync> {
+        Arc::new(unsafe { UPSafeCell::new(self.fd_table.exclusive_access().clone()) })
+    }
+    /// Number of live threads in the process, including this one
+    pub fn thread_count(&self) -> usize {
+        self.threads.exclusive_access().iter().flatten().count()
+    }
+    /// Rename the task, a name longer than `TASK_NAME_LEN` bytes is cut at the
+    /// last whole character that fits
+    pub fn set_name(&mut self, name: &str) {
+        let mut len = name.len().min(TASK_NAME_LEN);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.name = String::from(&name[..len]);
+    }
 }
 
 impl TaskControlBlock {
@@ -98,10 +289,16 @@ impl TaskControlBlock {
         self.inner.exclusive_access()
     }
 
+    /// Like `inner_exclusive_access`, but None instead of a panic if the
+    /// inner is already borrowed
+    pub fn try_inner_exclusive_access(&self) -> Option<RefMut<'_, TaskControlBlockInner>> {
+        self.inner.try_exclusive_access()
+    }
+
     /// Create a new process
     ///
     /// At present, it is only used for the creation of initproc
-    pub fn new(elf_data: &[u8]) -> Self {
+    pub fn new(name: &str, elf_data: &[u8]) -> Arc<Self> {
         // memory_set with elf program headers/trampoline/trap context/user stack
         let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
         let trap_cx_ppn = memory_set
@@ -113,26 +310,61 @@ impl TaskControlBlock {
         let kernel_stack = KernelStack::new(&pid_handle);
         let kernel_stack_top = kernel_stack.get_top();
         // push a task context which goes to trap_return to the top of kernel stack
-        let task_control_block = Self {
+        let task_control_block = Arc::new(Self {
+            process_pid: pid_handle.0,
             pid: pid_handle,
             kernel_stack,
             inner: unsafe {
                 UPSafeCell::new(TaskControlBlockInner {
                     trap_cx_ppn,
+                    slot: 0,
                     base_size: user_sp,
                     task_cx: TaskContext::goto_trap_return(kernel_stack_top),
                     task_status: TaskStatus::Ready,
-                    memory_set,
+                    memory_set: Arc::new(UPSafeCell::new(memory_set)),
                     parent: None,
                     children: Vec::new(),
                     exit_code: 0,
+                    exit_status: 0,
                     addtion_info: TaskControlBlockAddtionInfo::new(),
                     priority: Priority::new(),
+                    last_run_tick: 0,
+                    cpu_affinity: ALL_HARTS,
+                    rlimits: ResourceLimits::new(),
+                    waiting_for_child: false,
+                    trace: None,
+                    waiting_for_tracee: false,
+                    last_fault: None,
+                    signals: SignalFlags::empty(),
+                    alarm_expire_us: None,
+                    xcpu_raised_us: None,
+                    pinned_pages: Vec::new(),
+                    signal_actions: [0; MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    signal_mask: SignalFlags::empty(),
+                    fd_table: Arc::new(UPSafeCell::new(vec![
+                        // 0 -> stdin
+                        Some(Arc::new(Stdin)),
+                        // 1 -> stdout
+                        Some(Arc::new(Stdout)),
+                        // 2 -> stderr
+                        Some(Arc::new(Stdout)),
+                    ])),
+                    tid: 0,
+                    threads: Arc::new(UPSafeCell::new(Vec::new())),
+                    mutex_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    semaphore_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    condvar_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    name: String::new(),
                 })
             },
-        };
+        });
+        task_control_block.start_threads();
+        let mut inner = task_control_block.inner_exclusive_access();
+        inner.set_name(name);
         // prepare TrapContext in user space
-        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        let trap_cx = inner.get_trap_cx();
+        drop(inner);
         *trap_cx = TrapContext::app_init_context(
             entry_point,
             user_sp,
@@ -143,9 +375,25 @@ impl TaskControlBlock {
         task_control_block
     }
     /// Load a new elf to replace the original application address space and start execution
-    pub fn exec(&self, elf_data: &[u8]) {
+    ///
+    /// `args` and `envs` are pushed on the user stack: `argc` at the stack
+    /// pointer, then the null-terminated `argv` pointers and the
+    /// null-terminated `envp` pointers, with the strings above them. `a0`,
+    /// `a1` and `a2` are `argc`, `argv` and `envp`.
+    ///
+    /// The task is renamed after `name`, that of the new app.
+    ///
+    /// Fail if frames run out, leaving the original address space intact.
+    pub fn exec(
+        &self,
+        name: &str,
+        elf_data: &[u8],
+        args: Vec<String>,
+        envs: Vec<String>,
+    ) -> Result<(), MapError> {
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, mut user_sp, entry_point) = MemorySet::try_from_elf(elf_data)?;
+        let token = memory_set.token();
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
@@ -153,12 +401,45 @@ impl TaskControlBlock {
 
         // **** access inner exclusively
         let mut inner = self.inner_exclusive_access();
-        // substitute memory_set
-        inner.memory_set = memory_set;
-        // update trap_cx ppn
+        // the pages of the path and the arguments are pinned in the old one
+        inner.unpin_user_pages();
+        // substitute memory_set, the old heap goes away with it
+        inner.memory_set = Arc::new(unsafe { UPSafeCell::new(memory_set) });
+        // update trap_cx ppn, the caller may have been a thread in another slot
         inner.trap_cx_ppn = trap_cx_ppn;
+        inner.slot = 0;
+        // the handlers of the old program are gone
+        inner.signal_actions = [0; MAX_SIG + 1];
+        inner.trap_ctx_backup = None;
+        // the synchronization objects of the old program go away with it
+        inner.mutex_list = Arc::new(unsafe { UPSafeCell::new(Vec::new()) });
+        inner.semaphore_list = Arc::new(unsafe { UPSafeCell::new(Vec::new()) });
+        inner.condvar_list = Arc::new(unsafe { UPSafeCell::new(Vec::new()) });
+        inner.set_name(name);
+        drop(inner);
+        // the strings at the top of the stack, argv and envp point to them
+        let mut ptrs: Vec<usize> = Vec::new();
+        for strs in [&args, &envs] {
+            for s in strs.iter() {
+                user_sp -= s.len() + 1;
+                ptrs.push(user_sp);
+                copy_bytes_to_user(token, user_sp as *mut u8, s.as_bytes());
+                copy_bytes_to_user(token, (user_sp + s.len()) as *mut u8, &[0]);
+            }
+            ptrs.push(0);
+        }
+        // then argc, argv and envp, keeping the stack pointer 16 bytes aligned
+        user_sp -= (ptrs.len() + 1) * core::mem::size_of::<usize>();
+        user_sp -= user_sp % 16;
+        let argv_base = user_sp + core::mem::size_of::<usize>();
+        let envp_base = argv_base + (args.len() + 1) * core::mem::size_of::<usize>();
+        copy_to_user(token, user_sp as *mut usize, &args.len());
+        for (i, ptr) in ptrs.iter().enumerate() {
+            let dst = argv_base + i * core::mem::size_of::<usize>();
+            copy_to_user(token, dst as *mut usize, ptr);
+        }
         // initialize trap_cx
-        let trap_cx = inner.get_trap_cx();
+        let trap_cx = self.inner_exclusive_access().get_trap_cx();
         *trap_cx = TrapContext::app_init_context(
             entry_point,
             user_sp,
@@ -166,63 +447,229 @@ impl TaskControlBlock {
             self.kernel_stack.get_top(),
             trap_handler as usize,
         );
-        // **** release inner automatically
+        trap_cx.x[10] = args.len();
+        trap_cx.x[11] = argv_base;
+        trap_cx.x[12] = envp_base;
+        Ok(())
     }
     /// Fork from parent to child
-    pub fn fork(self: &Arc<TaskControlBlock>) -> Arc<TaskControlBlock> {
+    ///
+    /// The child keeps the priority of the parent. Fail if frames run out, the frames, the pid and the kernel stack
+    /// allocated until then are all released.
+    pub fn fork(self: &Arc<TaskControlBlock>) -> Result<Arc<TaskControlBlock>, MapError> {
+        let main_thread = self.main_thread();
         // ---- access parent PCB exclusively
         let mut parent_inner = self.inner_exclusive_access();
-        // copy user space(include trap context)
-        let memory_set = MemorySet::from_existed_user(&parent_inner.memory_set);
+        // share user space copy-on-write (trap context is copied)
+        let memory_set =
+            MemorySet::try_from_existed_user(&mut parent_inner.memory_set.exclusive_access())?;
+        // the child goes on as the main thread, with the stack and the trap
+        // context of the forking thread
+        let slot = parent_inner.slot;
         let trap_cx_ppn = memory_set
-            .translate(VirtAddr::from(TRAP_CONTEXT).into())
+            .translate(VirtAddr::from(thread_trap_cx_va(slot)).into())
             .unwrap()
             .ppn();
         // alloc a pid and a kernel stack in kernel space
         let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack = KernelStack::try_new(&pid_handle)?;
         let kernel_stack_top = kernel_stack.get_top();
         let task_control_block = Arc::new(TaskControlBlock {
+            process_pid: pid_handle.0,
             pid: pid_handle,
             kernel_stack,
             inner: unsafe {
                 UPSafeCell::new(TaskControlBlockInner {
                     trap_cx_ppn,
+                    slot,
                     base_size: parent_inner.base_size,
                     task_cx: TaskContext::goto_trap_return(kernel_stack_top),
                     task_status: TaskStatus::Ready,
-                    memory_set,
-                    parent: Some(Arc::downgrade(self)),
+                    memory_set: Arc::new(UPSafeCell::new(memory_set)),
+                    parent: Some(Arc::downgrade(&main_thread)),
                     children: Vec::new(),
                     exit_code: 0,
+                    exit_status: 0,
                     addtion_info: TaskControlBlockAddtionInfo::new(),
-                    priority: Priority::new(),
+                    priority: Priority::child_of(&parent_inner.priority, true),
+                    last_run_tick: 0,
+                    cpu_affinity: parent_inner.cpu_affinity,
+                    rlimits: parent_inner.rlimits,
+                    waiting_for_child: false,
+                    trace: None,
+                    waiting_for_tracee: false,
+                    last_fault: None,
+                    signals: SignalFlags::empty(),
+                    alarm_expire_us: None,
+                    xcpu_raised_us: None,
+                    pinned_pages: Vec::new(),
+                    signal_actions: parent_inner.signal_actions,
+                    // a child forked in a handler returns from it as well
+                    trap_ctx_backup: parent_inner.trap_ctx_backup,
+                    signal_mask: parent_inner.signal_mask,
+                    fd_table: parent_inner.fork_fd_table(),
+                    tid: 0,
+                    threads: Arc::new(UPSafeCell::new(Vec::new())),
+                    mutex_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    semaphore_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    condvar_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    name: parent_inner.name.clone(),
                 })
             },
         });
+        task_control_block.start_threads();
         // add child
-        parent_inner.children.push(task_control_block.clone());
+        if Arc::ptr_eq(&main_thread, self) {
+            parent_inner.children.push(task_control_block.clone());
+        } else {
+            main_thread
+                .inner_exclusive_access()
+                .children
+                .push(task_control_block.clone());
+        }
+        insert_into_pid2task(task_control_block.getpid(), task_control_block.clone());
         // modify kernel_sp in trap_cx
         // **** access children PCB exclusively
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
         trap_cx.kernel_sp = kernel_stack_top;
         // return
-        task_control_block
+        Ok(task_control_block)
         // ---- release parent PCB automatically
         // **** release children PCB automatically
     }
     pub fn getpid(&self) -> usize {
         self.pid.0
     }
+    /// The main thread of the process, whose children are those of every
+    /// thread. The task itself once the main thread has exited.
+    pub fn main_thread(self: &Arc<Self>) -> Arc<Self> {
+        let inner = self.inner_exclusive_access();
+        let main_thread = inner.threads.exclusive_access().first().cloned().flatten();
+        main_thread.unwrap_or_else(|| self.clone())
+    }
+    /// Make the task the main thread of its own thread list
+    fn start_threads(self: &Arc<Self>) {
+        let inner = self.inner_exclusive_access();
+        inner.threads.exclusive_access().push(Some(self.clone()));
+    }
+    /// Create a thread in the process of the task, sharing its address space
+    /// and its open files. It starts at `entry` with `arg` in `a0`, on a user
+    /// stack of its own.
+    ///
+    /// Fail if frames run out, the thread stack, the pid and the kernel
+    /// stack allocated until then are all released.
+    pub fn create_thread(
+        self: &Arc<TaskControlBlock>,
+        entry: usize,
+        arg: usize,
+    ) -> Result<Arc<TaskControlBlock>, MapError> {
+        // ---- access creator PCB exclusively
+        let inner = self.inner_exclusive_access();
+        let slot = inner.memory_set.exclusive_access().map_thread_slot()?;
+        let trap_cx_ppn = inner
+            .memory_set
+            .exclusive_access()
+            .translate(VirtAddr::from(thread_trap_cx_va(slot)).into())
+            .unwrap()
+            .ppn();
+        // alloc a pid and a kernel stack in kernel space
+        let pid_handle = pid_alloc();
+        let kernel_stack = match KernelStack::try_new(&pid_handle) {
+            Ok(kernel_stack) => kernel_stack,
+            Err(err) => {
+                inner.memory_set.exclusive_access().unmap_thread_slot(slot);
+                return Err(err);
+            }
+        };
+        let kernel_stack_top = kernel_stack.get_top();
+        // the lowest free tid, the main thread keeps 0
+        let tid = {
+            let threads = inner.threads.exclusive_access();
+            (1..threads.len())
+                .find(|tid| threads[*tid].is_none())
+                .unwrap_or(threads.len())
+        };
+        let task_control_block = Arc::new(TaskControlBlock {
+            process_pid: self.process_pid,
+            pid: pid_handle,
+            kernel_stack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    trap_cx_ppn,
+                    slot,
+                    base_size: inner.base_size,
+                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+                    task_status: TaskStatus::Ready,
+                    memory_set: inner.memory_set.clone(),
+                    parent: inner.parent.clone(),
+                    children: Vec::new(),
+                    exit_code: 0,
+                    exit_status: 0,
+                    addtion_info: TaskControlBlockAddtionInfo::new(),
+                    priority: Priority::new(),
+                    last_run_tick: 0,
+                    cpu_affinity: inner.cpu_affinity,
+                    rlimits: inner.rlimits,
+                    waiting_for_child: false,
+                    trace: None,
+                    waiting_for_tracee: false,
+                    last_fault: None,
+                    signals: SignalFlags::empty(),
+                    alarm_expire_us: None,
+                    xcpu_raised_us: None,
+                    pinned_pages: Vec::new(),
+                    signal_actions: inner.signal_actions,
+                    trap_ctx_backup: None,
+                    signal_mask: inner.signal_mask,
+                    fd_table: inner.fd_table.clone(),
+                    tid,
+                    threads: inner.threads.clone(),
+                    mutex_list: inner.mutex_list.clone(),
+                    semaphore_list: inner.semaphore_list.clone(),
+                    condvar_list: inner.condvar_list.clone(),
+                    name: inner.name.clone(),
+                })
+            },
+        });
+        {
+            let mut threads = inner.threads.exclusive_access();
+            if tid == threads.len() {
+                threads.push(None);
+            }
+            threads[tid] = Some(task_control_block.clone());
+        }
+        // prepare TrapContext in user space
+        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        *trap_cx = TrapContext::app_init_context(
+            entry,
+            thread_stack_top(slot),
+            KERNEL_SPACE.exclusive_access().token(),
+            kernel_stack_top,
+            trap_handler as usize,
+        );
+        trap_cx.x[10] = arg;
+        Ok(task_control_block)
+        // ---- release creator PCB automatically
+    }
 }
 
 impl TaskControlBlock {
     /// spawn from parent to child
-    pub fn spawn(self: &Arc<TaskControlBlock>, elf_data: &[u8]) -> Arc<TaskControlBlock> {
+    ///
+    /// The child has the default priority, or that of the parent if
+    /// `inherit_prio`. Fail if frames run out, the frames, the pid and the
+    /// kernel stack allocated until then are all released.
+    pub fn spawn(
+        self: &Arc<TaskControlBlock>,
+        name: &str,
+        elf_data: &[u8],
+        inherit_prio: bool,
+    ) -> Result<Arc<TaskControlBlock>, MapError> {
         // ---- access parent PCB exclusively
+        let main_thread = self.main_thread();
         let mut parent_inner = self.inner_exclusive_access();
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, user_sp, entry_point) = MemorySet::try_from_elf(elf_data)?;
         let trap_cx_ppn = memory_set
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
@@ -230,30 +677,66 @@ impl TaskControlBlock {
 
         // alloc a pid and a kernel stack in kernel space
         let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let kernel_stack = KernelStack::try_new(&pid_handle)?;
         let kernel_stack_top = kernel_stack.get_top();
         let task_control_block = Arc::new(TaskControlBlock {
+            process_pid: pid_handle.0,
             pid: pid_handle,
             kernel_stack,
             inner: unsafe {
                 UPSafeCell::new(TaskControlBlockInner {
                     trap_cx_ppn,
+                    slot: 0,
                     base_size: parent_inner.base_size,
                     task_cx: TaskContext::goto_trap_return(kernel_stack_top),
                     task_status: TaskStatus::Ready,
-                    memory_set,
-                    parent: Some(Arc::downgrade(self)),
+                    memory_set: Arc::new(UPSafeCell::new(memory_set)),
+                    parent: Some(Arc::downgrade(&main_thread)),
                     children: Vec::new(),
                     exit_code: 0,
+                    exit_status: 0,
                     addtion_info: TaskControlBlockAddtionInfo::new(),
-                    priority: Priority::new(),
+                    priority: Priority::child_of(&parent_inner.priority, inherit_prio),
+                    last_run_tick: 0,
+                    cpu_affinity: parent_inner.cpu_affinity,
+                    rlimits: parent_inner.rlimits,
+                    waiting_for_child: false,
+                    trace: None,
+                    waiting_for_tracee: false,
+                    last_fault: None,
+                    signals: SignalFlags::empty(),
+                    alarm_expire_us: None,
+                    xcpu_raised_us: None,
+                    pinned_pages: Vec::new(),
+                    signal_actions: [0; MAX_SIG + 1],
+                    trap_ctx_backup: None,
+                    signal_mask: SignalFlags::empty(),
+                    fd_table: parent_inner.fork_fd_table(),
+                    tid: 0,
+                    threads: Arc::new(UPSafeCell::new(Vec::new())),
+                    mutex_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    semaphore_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    condvar_list: Arc::new(UPSafeCell::new(Vec::new())),
+                    name: String::new(),
                 })
             },
         });
+        task_control_block.start_threads();
         // add child
-        parent_inner.children.push(task_control_block.clone());
+        if Arc::ptr_eq(&main_thread, self) {
+            parent_inner.children.push(task_control_block.clone());
+        } else {
+            main_thread
+                .inner_exclusive_access()
+                .children
+                .push(task_control_block.clone());
+        }
+        insert_into_pid2task(task_control_block.getpid(), task_control_block.clone());
+        let mut inner = task_control_block.inner_exclusive_access();
+        inner.set_name(name);
         // prepare TrapContext in user space
-        let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
+        let trap_cx = inner.get_trap_cx();
+        drop(inner);
         *trap_cx = TrapContext::app_init_context(
             entry_point,
             user_sp,
@@ -262,25 +745,84 @@ impl TaskControlBlock {
             trap_handler as usize,
         );
         // return
-        task_control_block
+        Ok(task_control_block)
         // ---- release parent PCB automatically
         // **** release children PCB automatically
     }
 }
 
 #[derive(Copy, Clone, PartialEq)]
-/// task status: UnInit, Ready, Running, Exited
+/// task status: UnInit, Ready, Running, Exited, Blocking
+///
+/// The order is shared with the user library through `sys_task_info`.
 pub enum TaskStatus {
     UnInit,
     Ready,
     Running,
     Zombie,
+    Blocking,
+}
+
+/// No limit on a resource
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// Soft and hard limit of a resource, the soft one is enforced and can be
+/// raised up to the hard one
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Rlimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+impl Rlimit {
+    const INFINITY: Rlimit = Rlimit {
+        cur: RLIM_INFINITY,
+        max: RLIM_INFINITY,
+    };
 }
 
+/// The resource limits of a task, inherited by its children and threads
+#[derive(Copy, Clone, Debug)]
+pub struct ResourceLimits {
+    /// Bytes the areas of the address space may cover
+    pub address_space: Rlimit,
+    /// Children that have not been reaped, zombies included
+    pub nproc: Rlimit,
+    /// Seconds of user and kernel time, see `check_cpu_limit_of_current`
+    pub cpu: Rlimit,
+}
+
+impl ResourceLimits {
+    /// No limits at all
+    fn new() -> Self {
+        Self {
+            address_space: Rlimit::INFINITY,
+            nproc: Rlimit::INFINITY,
+            cpu: Rlimit::INFINITY,
+        }
+    }
+}
+
+/// How `sys_trace_step` holds a task, which stops at its next syscall
+pub struct Trace {
+    pub tracer: Weak<TaskControlBlock>,
+    /// Blocked before a syscall until the next step
+    pub stopped: bool,
+}
+
+/// Stride scheduling state. `stride` is a wrapping counter: the strides of
+/// runnable tasks never differ by more than `BIG_STRIDE`, so comparing their
+/// signed difference orders them correctly even after one has wrapped.
 #[derive(Debug)]
 pub struct Priority {
     stride: u64,
     pass: u64,
+    prio: usize,
+    /// Timer ticks left before the task is preempted
+    slice_left: usize,
+    /// Timer ticks of the current time slice, used and left
+    slice_len: usize,
 }
 
 impl Priority {
@@ -290,15 +832,88 @@ impl Priority {
         Priority {
             stride: 0,
             pass: pass,
+            prio: PRIORITY,
+            slice_left: BASE_TIME_SLICE,
+            slice_len: BASE_TIME_SLICE,
+        }
+    }
+
+    /// State of a new child of `parent`: its priority if `inherit`, the
+    /// default otherwise. The stride starts level with the parent's, so the
+    /// child neither runs ahead of everyone with a stride of 0 nor is owed
+    /// the parent's time.
+    pub fn child_of(parent: &Priority, inherit: bool) -> Priority {
+        let mut priority = Priority::new();
+        if inherit {
+            priority.set_prio(parent.prio);
         }
+        priority.stride = parent.stride;
+        priority
     }
 
+    /// Take the priority `prio`. The next charge of the stride is of its
+    /// pass already; `resize_slice` changes the running time slice too.
     pub fn set_prio(&mut self, prio: usize) {
         self.pass = BIG_STRIDE / prio as u64;
+        self.prio = prio;
+    }
+
+    pub fn prio(&self) -> usize {
+        self.prio
+    }
+
+    /// Length of the time slice in timer ticks, longer for a higher priority
+    pub fn time_slice(&self) -> usize {
+        (self.prio / PRIORITY)
+            .saturating_mul(BASE_TIME_SLICE)
+            .clamp(1, MAX_TIME_SLICE)
+    }
+
+    /// Length of the time slice under `policy`, round-robin gives every
+    /// task the same slice whatever its priority
+    fn slice_under(&self, policy: SchedPolicy) -> usize {
+        match policy {
+            SchedPolicy::Stride => self.time_slice(),
+            SchedPolicy::RoundRobin => BASE_TIME_SLICE,
+        }
+    }
+
+    /// Start a new time slice, when the task is switched in
+    pub fn refill_slice(&mut self, policy: SchedPolicy) {
+        self.slice_len = self.slice_under(policy);
+        self.slice_left = self.slice_len;
+    }
+
+    /// Make the running time slice as long as that of the priority now,
+    /// keeping the ticks used. At least one tick is left.
+    pub fn resize_slice(&mut self, policy: SchedPolicy) {
+        let used = self.slice_len - self.slice_left;
+        self.slice_len = self.slice_under(policy);
+        self.slice_left = self.slice_len.saturating_sub(used).max(1);
+    }
+
+    /// Charge a timer tick, return true if the time slice has run out
+    pub fn tick(&mut self) -> bool {
+        self.slice_left = self.slice_left.saturating_sub(1);
+        self.slice_left == 0
     }
 
     pub fn update(&mut self) {
-        self.stride += self.pass;
+        self.stride = self.stride.wrapping_add(self.pass);
+    }
+
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    pub fn pass(&self) -> u64 {
+        self.pass
+    }
+
+    /// Catch up with `stride`, that of the next task to run, after waiting
+    /// too long behind tasks of higher priorities
+    pub fn age_to(&mut self, stride: u64) {
+        self.stride = stride;
     }
 }
 
@@ -312,13 +927,14 @@ impl Eq for Priority {}
 
 impl PartialOrd for Priority {
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
-        other.stride.partial_cmp(&self.stride)
+        Some(self.cmp(other))
     }
 }
 
+/// The smaller stride is the greater priority, it is popped first from the heap
 impl Ord for Priority {
     fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-        other.stride.cmp(&self.stride)
+        (other.stride.wrapping_sub(self.stride) as i64).cmp(&0)
     }
 }
 
@@ -2,10 +2,18 @@
 
 use crate::config::CLOCK_FREQ;
 use crate::sbi::set_timer;
+use crate::sync::UPSafeCell;
+use crate::task::{wakeup_task, TaskControlBlock};
+use alloc::collections::BinaryHeap;
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use core::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use lazy_static::*;
 use riscv::register::time;
 
 const TICKS_PER_SEC: usize = 100;
 const MICRO_PER_SEC: usize = 1_000_000;
+const NANO_PER_SEC: usize = 1_000_000_000;
 
 /// read the `mtime` register
 pub fn get_time() -> usize {
@@ -17,7 +25,124 @@ pub fn get_time_us() -> usize {
     time::read() / (CLOCK_FREQ / MICRO_PER_SEC)
 }
 
+/// get current time in nanoseconds
+pub fn get_time_ns() -> usize {
+    let ticks = time::read();
+    // `ticks * NANO_PER_SEC` would overflow after a few minutes, the
+    // remainder is below CLOCK_FREQ so its product is safe
+    ticks / CLOCK_FREQ * NANO_PER_SEC + ticks % CLOCK_FREQ * NANO_PER_SEC / CLOCK_FREQ
+}
+
+/// Nanoseconds from the epoch to boot. There is no real-time clock to read
+/// it from, so the wall clock starts at the epoch.
+const REALTIME_OFFSET_NS: usize = 0;
+
+/// get the wall-clock time in nanoseconds
+pub fn get_realtime_ns() -> usize {
+    get_time_ns() + REALTIME_OFFSET_NS
+}
+
 /// set the next timer interrupt
 pub fn set_next_trigger() {
     set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
 }
+
+/// Timer interrupts since boot
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// Count a timer interrupt, called by its handler
+pub fn tick() {
+    TICKS.fetch_add(1, AtomicOrdering::Relaxed);
+}
+
+/// Timer interrupts since boot, `TICKS_PER_SEC` of them a second. One that
+/// comes while the previous is still being handled counts once.
+pub fn get_ticks() -> u64 {
+    TICKS.load(AtomicOrdering::Relaxed)
+}
+
+/// A sleeping task and the time it should be woken up at
+pub struct TimerCondVar {
+    pub expire_us: usize,
+    pub task: Arc<TaskControlBlock>,
+    /// A signal may wake the task up before the deadline
+    pub interruptible: bool,
+}
+
+impl PartialEq for TimerCondVar {
+    fn eq(&self, other: &Self) -> bool {
+        self.expire_us == other.expire_us
+    }
+}
+impl Eq for TimerCondVar {}
+impl PartialOrd for TimerCondVar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The earliest deadline is the greatest, it is on the top of the heap
+impl Ord for TimerCondVar {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.expire_us.cmp(&self.expire_us)
+    }
+}
+
+lazy_static! {
+    /// Sleeping tasks ordered by their deadlines
+    static ref TIMERS: UPSafeCell<BinaryHeap<TimerCondVar>> =
+        unsafe { UPSafeCell::new(BinaryHeap::<TimerCondVar>::new()) };
+}
+
+/// Wake `task` up once `get_time_us()` reaches `expire_us`
+pub fn add_timer(expire_us: usize, task: Arc<TaskControlBlock>) {
+    let mut timers = TIMERS.exclusive_access();
+    timers.push(TimerCondVar {
+        expire_us,
+        task,
+        interruptible: false,
+    });
+}
+
+/// Like `add_timer`, but `interrupt_sleep` may wake `task` up earlier
+pub fn add_interruptible_timer(expire_us: usize, task: Arc<TaskControlBlock>) {
+    let mut timers = TIMERS.exclusive_access();
+    timers.push(TimerCondVar {
+        expire_us,
+        task,
+        interruptible: true,
+    });
+}
+
+/// Wake `task` up now if it sleeps on an interruptible timer, return whether
+/// it did
+pub fn interrupt_sleep(task: &Arc<TaskControlBlock>) -> bool {
+    let mut timers = TIMERS.exclusive_access();
+    let mut all = core::mem::take(&mut *timers).into_vec();
+    let found = all
+        .iter()
+        .position(|timer| timer.interruptible && Arc::ptr_eq(&timer.task, task));
+    if let Some(idx) = found {
+        all.swap_remove(idx);
+    }
+    *timers = BinaryHeap::from(all);
+    drop(timers);
+    if found.is_some() {
+        wakeup_task(Arc::clone(task));
+    }
+    found.is_some()
+}
+
+/// Wake up every sleeping task whose deadline has passed
+pub fn check_timer() {
+    let current_us = get_time_us();
+    let mut timers = TIMERS.exclusive_access();
+    while let Some(timer) = timers.peek() {
+        if timer.expire_us <= current_us {
+            wakeup_task(Arc::clone(&timer.task));
+            timers.pop();
+        } else {
+            break;
+        }
+    }
+}
@@ -14,13 +14,17 @@
 
 mod context;
 
-use crate::config::{TRAMPOLINE, TRAP_CONTEXT};
+use crate::config::TRAMPOLINE;
+use crate::mm::{copy_on_write, grow_user_stack, lazy_alloc, user_stack_guard_page, VirtAddr};
 use crate::syscall::syscall;
 use crate::task::{
-    current_task, current_trap_cx, current_user_token, exit_current_and_run_next,
-    suspend_current_and_run_next,
+    check_alarm_of_current, check_cpu_limit_of_current, check_signals_error_of_current,
+    current_task, current_trap_cx, current_trap_cx_user_va, current_user_token,
+    deterministic_sched, exit_current_and_run_next, fault_current, handle_signals_of_current,
+    kill_current_and_run_next, suspend_current_and_run_next, tick_current_task, trace_stop_current,
 };
-use crate::timer::set_next_trigger;
+use crate::timer::{check_timer, set_next_trigger, tick};
+use core::fmt::Debug;
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
@@ -54,6 +58,15 @@ pub fn enable_timer_interrupt() {
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
+    // traps from the kernel itself never get here, so each entry closes
+    // exactly one stretch of user time
+    {
+        let task = current_task().unwrap();
+        task.kernel_stack.check_canary();
+        task.inner_exclusive_access()
+            .addtion_info
+            .charge_user_time();
+    }
     let scause = scause::read();
     let stval = stval::read();
     match scause.cause() {
@@ -66,37 +79,73 @@ pub fn trap_handler() -> ! {
                 inner.addtion_info.update_syscall_times(cx.x[17])
             }
 
-            let mut cx = current_trap_cx();
-            cx.sepc += 4;
-            // get system call return value
-            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]);
-            // cx is changed during sys_exec, so we have to call it again
-            cx = current_trap_cx();
-            cx.x[10] = result as usize;
+            // a traced task stops at the ecall, and never makes the syscall
+            // if it is killed while stopped
+            if trace_stop_current() {
+                let mut cx = current_trap_cx();
+                cx.sepc += 4;
+                // get system call return value
+                let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12], cx.x[13]]);
+                // cx is changed during sys_exec, so we have to call it again
+                cx = current_trap_cx();
+                cx.x[10] = result as usize;
+                current_task()
+                    .unwrap()
+                    .inner_exclusive_access()
+                    .unpin_user_pages();
+            }
+        }
+        Trap::Exception(
+            exception @ (Exception::StorePageFault
+            | Exception::LoadPageFault
+            | Exception::InstructionPageFault),
+        ) => {
+            handle_page_fault(exception, stval);
         }
         Trap::Exception(Exception::StoreFault)
-        | Trap::Exception(Exception::StorePageFault)
         | Trap::Exception(Exception::InstructionFault)
-        | Trap::Exception(Exception::InstructionPageFault)
-        | Trap::Exception(Exception::LoadFault)
-        | Trap::Exception(Exception::LoadPageFault) => {
+        | Trap::Exception(Exception::LoadFault) => {
+            // a SIGSEGV handler may recover, its signal is handled on the way out
+            if !fault_current(stval, scause.bits()) {
+                println!(
+                    "[kernel] {:?} in application (pid {}), bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+                    scause.cause(),
+                    current_task().unwrap().getpid(),
+                    stval,
+                    current_trap_cx().sepc,
+                );
+                // page fault exit code
+                exit_current_and_run_next(-2);
+            }
+        }
+        Trap::Exception(Exception::IllegalInstruction) => {
             println!(
-                "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
-                scause.cause(),
-                stval,
+                "[kernel] IllegalInstruction in application (pid {}), bad instruction = {:#x}, core dumped.",
+                current_task().unwrap().getpid(),
                 current_trap_cx().sepc,
             );
-            // page fault exit code
-            exit_current_and_run_next(-2);
-        }
-        Trap::Exception(Exception::IllegalInstruction) => {
-            println!("[kernel] IllegalInstruction in application, core dumped.");
             // illegal instruction exit code
             exit_current_and_run_next(-3);
         }
+        Trap::Exception(Exception::InstructionMisaligned)
+        | Trap::Exception(Exception::StoreMisaligned) => {
+            kill_misaligned(&scause.cause(), stval);
+        }
+        // the riscv crate has no variant for it
+        Trap::Exception(Exception::Unknown) if scause.bits() == LOAD_MISALIGNED => {
+            kill_misaligned(&format_args!("LoadMisaligned"), stval);
+        }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            tick();
             set_next_trigger();
-            suspend_current_and_run_next();
+            check_timer();
+            check_alarm_of_current();
+            check_cpu_limit_of_current();
+            // the slice is still charged, so it is full length after the
+            // deterministic mode is turned off
+            if tick_current_task() && !deterministic_sched() {
+                suspend_current_and_run_next();
+            }
         }
         _ => {
             panic!(
@@ -106,13 +155,94 @@ pub fn trap_handler() -> ! {
             );
         }
     }
+    // a signal with a user handler is handled instead of its default action
+    handle_signals_of_current();
+    // a pending SIGKILL, SIGTERM, SIGALRM or SIGXCPU terminates the task before
+    // it gets back
+    if let Some((signum, msg)) = check_signals_error_of_current() {
+        println!("[kernel] {}", msg);
+        kill_current_and_run_next(signum);
+    }
     trap_return();
 }
 
+/// Exception code of a misaligned load
+const LOAD_MISALIGNED: usize = 4;
+
+/// Kill the current task for a misaligned access at `stval`
+fn kill_misaligned(cause: &dyn Debug, stval: usize) {
+    println!(
+        "[kernel] {:?} in application (pid {}), bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+        cause,
+        current_task().unwrap().getpid(),
+        stval,
+        current_trap_cx().sepc,
+    );
+    // misaligned access exit code
+    exit_current_and_run_next(-4);
+}
+
+/// Resolve a page fault of user space by copy-on-write, lazy allocation or
+/// stack growth depending on the access, and kill the task if none applies.
+fn handle_page_fault(exception: Exception, stval: usize) {
+    let va = VirtAddr::from(stval);
+    let access = match exception {
+        Exception::StorePageFault => "store",
+        Exception::LoadPageFault => "load",
+        _ => "instruction fetch",
+    };
+    debug!("[kernel] {} page fault at {:#x}", access, stval);
+    let resolved = match exception {
+        // a store may hit a copy-on-write page, retry it on the private copy
+        Exception::StorePageFault => copy_on_write(va) || lazy_alloc(va) || grow_user_stack(va),
+        Exception::LoadPageFault => lazy_alloc(va) || grow_user_stack(va),
+        // code is never on the stack
+        _ => lazy_alloc(va),
+    };
+    if resolved {
+        current_task()
+            .unwrap()
+            .inner_exclusive_access()
+            .addtion_info
+            .minflt += 1;
+        return;
+    }
+    // a SIGSEGV handler may recover, its signal is handled on the way out
+    if fault_current(stval, scause::read().bits()) {
+        return;
+    }
+    let pid = current_task().unwrap().getpid();
+    if exception != Exception::InstructionPageFault && va.floor() == user_stack_guard_page() {
+        println!(
+            "[kernel] stack overflow in application (pid {}), bad {} addr = {:#x}, bad instruction = {:#x}, core dumped.",
+            pid,
+            access,
+            stval,
+            current_trap_cx().sepc,
+        );
+    } else {
+        println!(
+            "[kernel] {:?} in application (pid {}), bad {} addr = {:#x}, bad instruction = {:#x}, core dumped.",
+            exception,
+            pid,
+            access,
+            stval,
+            current_trap_cx().sepc,
+        );
+    }
+    // page fault exit code
+    exit_current_and_run_next(-2);
+}
+
 #[no_mangle]
 pub fn trap_return() -> ! {
     set_user_trap_entry();
-    let trap_cx_ptr = TRAP_CONTEXT;
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .addtion_info
+        .charge_kernel_time();
+    let trap_cx_ptr = current_trap_cx_user_va();
     let user_satp = current_user_token();
     extern "C" {
         fn __alltraps();
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    fork, kill, mmap, munmap, sched_getaffinity, sched_setaffinity, sleep, waitpid, MAP_SHARED,
+    SIGKILL,
+};
+
+/*
+理想结果：新进程的亲和性掩码为 1（只有 hart 0）。把子进程的掩码设为 2 后，
+它不再在唯一的 hart 上运行，计数不变；恢复为 1 后计数继续增加。
+掩码为 0 或进程不存在时返回 -1。输出 Test affinity OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(sched_getaffinity(0), 1);
+    assert_eq!(sched_setaffinity(0, 0), -1);
+    assert_eq!(sched_setaffinity(9999, 1), -1);
+    assert_eq!(sched_getaffinity(9999), -1);
+
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, 4096, 3 | MAP_SHARED));
+    let counter = start as *mut usize;
+    unsafe { counter.write_volatile(0) };
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            unsafe { counter.write_volatile(counter.read_volatile() + 1) };
+        }
+    }
+    let pid = pid as usize;
+    assert_eq!(sched_getaffinity(pid), 1);
+    sleep(30);
+    assert!(unsafe { counter.read_volatile() } > 0);
+
+    // the child never runs while it may only run on hart 1
+    assert_eq!(sched_setaffinity(pid, 0b10), 0);
+    assert_eq!(sched_getaffinity(pid), 0b10);
+    let frozen = unsafe { counter.read_volatile() };
+    sleep(100);
+    assert_eq!(unsafe { counter.read_volatile() }, frozen);
+
+    assert_eq!(sched_setaffinity(pid, 1), 0);
+    sleep(100);
+    assert!(unsafe { counter.read_volatile() } > frozen);
+
+    assert_eq!(kill(pid, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(0, munmap(start, 4096));
+    println!("Test affinity OK!");
+    0
+}
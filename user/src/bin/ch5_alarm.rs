@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{alarm, fork, get_time, waitpid};
+
+/*
+理想结果：重新设置 alarm 返回上一个 alarm 剩余的秒数；子进程设置 1 秒的 alarm 后死循环，
+约 1 秒后被 SIGALRM 终止，waitpid 得到的退出码为 -14。输出 Test alarm OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(alarm(0), 0);
+    assert_eq!(alarm(5), 0);
+    assert_eq!(alarm(3), 5);
+    assert_eq!(alarm(0), 3);
+    assert_eq!(alarm(0), 0);
+    let start = get_time();
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(alarm(10), 0);
+        assert_eq!(alarm(1), 10);
+        #[allow(clippy::empty_loop)]
+        loop {}
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    let elapsed = get_time() - start;
+    assert_eq!(exit_code, -14);
+    assert!((900..2000).contains(&elapsed));
+    println!("Test alarm OK!");
+    0
+}
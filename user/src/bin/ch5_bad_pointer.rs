@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, sys_waitpid, syscall, waitpid, yield_, EFAULT, SYSCALL_GETTIMEOFDAY,
+    SYSCALL_TASK_INFO,
+};
+
+/*
+理想结果：向 sys_get_time、sys_task_info、sys_waitpid 传入空指针或只读地址时返回 -14（EFAULT），
+内核不会 panic，子进程也不会因此被回收。输出 Test bad pointer OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // null
+    assert_eq!(-EFAULT, syscall(SYSCALL_GETTIMEOFDAY, [0, 0, 0]));
+    assert_eq!(-EFAULT, syscall(SYSCALL_TASK_INFO, [0, 0, 0]));
+    // read-only code
+    let text = main as usize;
+    assert_eq!(-EFAULT, syscall(SYSCALL_GETTIMEOFDAY, [text, 0, 0]));
+    assert_eq!(-EFAULT, syscall(SYSCALL_TASK_INFO, [text, 0, 0]));
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    loop {
+        match sys_waitpid(pid, core::ptr::null_mut()) {
+            -2 => {
+                yield_();
+            }
+            n => {
+                assert_eq!(n, -EFAULT);
+                break;
+            }
+        }
+    }
+    // the child has not been reaped by the failed wait
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    println!("Test bad pointer OK!");
+    0
+}
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{brk, exit, fork, waitpid};
+
+/*
+理想结果：堆可以增长、查询和收缩，收缩后访问被释放的页会导致进程被杀死；
+低于堆底或撞上用户栈的请求返回 -1。输出 Test brk OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let base = brk(0);
+    assert!(base > 0);
+    let base = base as usize;
+    // grow by a bit more than three pages
+    let top = base + PAGE_SIZE * 3 + 100;
+    assert_eq!(brk(top), top as isize);
+    assert_eq!(brk(0), top as isize);
+    for addr in base..top {
+        let addr: *mut u8 = addr as *mut u8;
+        unsafe {
+            *addr = addr as usize as u8;
+        }
+    }
+    for addr in base..top {
+        let addr: *mut u8 = addr as *mut u8;
+        unsafe {
+            assert_eq!(*addr, addr as usize as u8);
+        }
+    }
+    // shrink back into the first page
+    assert_eq!(brk(base + 10), (base + 10) as isize);
+    unsafe {
+        assert_eq!(*((base + 5) as *const u8), (base + 5) as u8);
+    }
+    let pid = fork();
+    if pid == 0 {
+        // the third page has been freed
+        unsafe {
+            *((base + PAGE_SIZE * 2) as *mut u8) = 0;
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+    // below the heap bottom
+    assert_eq!(brk(base - 1), -1);
+    // into the user stack
+    assert_eq!(brk(0x40_0000_0000 - PAGE_SIZE * 2), -1);
+    assert_eq!(brk(0), (base + 10) as isize);
+    println!("Test brk OK!");
+    0
+}
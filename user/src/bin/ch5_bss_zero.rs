@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/*
+理想结果：未初始化的全局数组在程序开始时全部为 0，与 .data 相邻的部分也是如此。
+输出 Test bss zero OK!
+*/
+
+const LEN: usize = 64 * 1024;
+
+/// spans many pages of .bss, most of them without any file data
+static mut BSS: [u8; LEN] = [0; LEN];
+/// in .data, the page it ends in may be shared with .bss
+static mut DATA: [u8; 3] = [1, 2, 3];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    unsafe {
+        let bss = &*core::ptr::addr_of!(BSS);
+        assert!(bss.iter().all(|b| *b == 0));
+        let data = &*core::ptr::addr_of!(DATA);
+        assert_eq!(*data, [1, 2, 3]);
+        // the array is really writable memory of its own
+        let bss = &mut *core::ptr::addr_of_mut!(BSS);
+        bss[LEN - 1] = 0xaa;
+        assert_eq!(bss[LEN - 1], 0xaa);
+        assert_eq!(bss[LEN - 2], 0);
+    }
+    println!("Test bss zero OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, mmap, yield_, TimeSpec, CLOCK_MONOTONIC, CLOCK_REALTIME};
+
+/*
+理想结果：两次读取 CLOCK_MONOTONIC 之间 yield，第二次严格大于第一次；
+CLOCK_REALTIME 可用，未知时钟返回 -1，跨页的 TimeSpec 也能正确写入。
+输出 Test clock_gettime OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+fn ns(time: &TimeSpec) -> usize {
+    time.sec * 1_000_000_000 + time.nsec
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut t1 = TimeSpec::new();
+    let mut t2 = TimeSpec::new();
+    assert_eq!(0, clock_gettime(CLOCK_MONOTONIC, &mut t1));
+    yield_();
+    assert_eq!(0, clock_gettime(CLOCK_MONOTONIC, &mut t2));
+    assert!(t1.nsec < 1_000_000_000 && t2.nsec < 1_000_000_000);
+    assert!(ns(&t2) > ns(&t1));
+    let mut real = TimeSpec::new();
+    assert_eq!(0, clock_gettime(CLOCK_REALTIME, &mut real));
+    assert!(ns(&real) >= ns(&t2));
+    assert_eq!(-1, clock_gettime(2, &mut real));
+    assert_eq!(-1, clock_gettime(usize::MAX, &mut real));
+    // across a page boundary
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, PAGE_SIZE * 2, 3));
+    let straddle = unsafe { &mut *((start + PAGE_SIZE - 4) as *mut TimeSpec) };
+    assert_eq!(0, clock_gettime(CLOCK_MONOTONIC, straddle));
+    let t3: TimeSpec = unsafe { core::ptr::read_unaligned(straddle) };
+    assert!(ns(&t3) >= ns(&t2));
+    println!("Test clock_gettime OK!");
+    0
+}
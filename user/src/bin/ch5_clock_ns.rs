@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, TimeSpec, CLOCK_MONOTONIC};
+
+/*
+理想结果：连续两次读取纳秒时钟结果不同（一次系统调用远长于一个时钟周期），
+且 sec * 1e9 + nsec 单调递增。输出 Test clock ns OK!
+*/
+
+fn ns(time: &TimeSpec) -> usize {
+    time.sec * 1_000_000_000 + time.nsec
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut t1 = TimeSpec::new();
+    let mut t2 = TimeSpec::new();
+    assert_eq!(0, clock_gettime(CLOCK_MONOTONIC, &mut t1));
+    assert_eq!(0, clock_gettime(CLOCK_MONOTONIC, &mut t2));
+    assert!(ns(&t2) > ns(&t1));
+    let mut last = ns(&t2);
+    for _ in 0..1000 {
+        assert_eq!(0, clock_gettime(CLOCK_MONOTONIC, &mut t1));
+        assert!(t1.nsec < 1_000_000_000);
+        let now = ns(&t1);
+        assert!(now >= last);
+        last = now;
+    }
+    println!("Test clock ns OK!");
+    0
+}
@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    condvar_create, condvar_signal, condvar_wait, exit, mutex_blocking_create, mutex_lock,
+    mutex_unlock, thread_create, yield_,
+};
+
+/*
+理想结果：生产者与消费者线程用一把互斥锁和一个条件变量传递单格队列中的数据，
+消费者按顺序收到全部数据；未持有互斥锁时 condvar_wait 返回 -1。输出 Test condvar OK!
+*/
+
+const ITEMS: usize = 32;
+
+/// The single slot of the queue, None when it is empty
+static mut SLOT: Option<usize> = None;
+static MUTEX: AtomicUsize = AtomicUsize::new(0);
+static CONDVAR: AtomicUsize = AtomicUsize::new(0);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+static IN_ORDER: AtomicUsize = AtomicUsize::new(0);
+
+fn slot() -> Option<usize> {
+    unsafe { core::ptr::read_volatile(&SLOT) }
+}
+
+fn set_slot(value: Option<usize>) {
+    unsafe { core::ptr::write_volatile(&mut SLOT, value) }
+}
+
+fn producer(_arg: usize) -> ! {
+    let (mutex, condvar) = (MUTEX.load(Ordering::SeqCst), CONDVAR.load(Ordering::SeqCst));
+    for item in 0..ITEMS {
+        mutex_lock(mutex);
+        while slot().is_some() {
+            assert_eq!(condvar_wait(condvar, mutex), 0);
+        }
+        set_slot(Some(item));
+        condvar_signal(condvar);
+        mutex_unlock(mutex);
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+fn consumer(_arg: usize) -> ! {
+    let (mutex, condvar) = (MUTEX.load(Ordering::SeqCst), CONDVAR.load(Ordering::SeqCst));
+    for item in 0..ITEMS {
+        mutex_lock(mutex);
+        while slot().is_none() {
+            assert_eq!(condvar_wait(condvar, mutex), 0);
+        }
+        if slot() == Some(item) {
+            IN_ORDER.fetch_add(1, Ordering::SeqCst);
+        }
+        set_slot(None);
+        condvar_signal(condvar);
+        mutex_unlock(mutex);
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mutex = mutex_blocking_create();
+    let condvar = condvar_create();
+    assert!(mutex >= 0 && condvar >= 0);
+    // waiting needs the mutex held
+    assert_eq!(condvar_wait(condvar as usize, mutex as usize), -1);
+    MUTEX.store(mutex as usize, Ordering::SeqCst);
+    CONDVAR.store(condvar as usize, Ordering::SeqCst);
+    assert!(thread_create(consumer as usize, 0) > 0);
+    assert!(thread_create(producer as usize, 0) > 0);
+    while DONE.load(Ordering::SeqCst) < 2 {
+        yield_();
+    }
+    assert_eq!(IN_ORDER.load(Ordering::SeqCst), ITEMS);
+    println!("Test condvar OK!");
+    0
+}
@@ -0,0 +1,26 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{write, yield_, STDOUT};
+
+/*
+理想结果：不带换行的长输出在换行、缓冲区满或任务切换时完整且按顺序地输出，
+先输出由 0123456789 重复 100 次组成的一行，然后输出 Test console buffer OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let digits = b"0123456789";
+    // more than the kernel buffers at once, without a newline
+    for _ in 0..100 {
+        assert_eq!(write(STDOUT, digits), digits.len() as isize);
+    }
+    // the task switch flushes what is left of the line
+    yield_();
+    assert_eq!(write(STDOUT, b"\n"), 1);
+    println!("Test console buffer OK!");
+    0
+}
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid};
+
+/*
+理想结果：父子进程对同一全局数组各自写入不同的值，互不影响；
+未写入的页在父子进程中内容保持一致。输出 Test cow OK!
+*/
+
+const PAGES: usize = 4;
+const WORDS: usize = 4096 / core::mem::size_of::<usize>();
+
+static mut DATA: [usize; PAGES * WORDS] = [0; PAGES * WORDS];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    unsafe {
+        for page in 0..PAGES {
+            DATA[page * WORDS] = 0x1000 + page;
+        }
+    }
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            // only the first page is written by the child
+            DATA[0] = 0xc0de;
+            assert_eq!(DATA[0], 0xc0de);
+            for page in 1..PAGES {
+                assert_eq!(DATA[page * WORDS], 0x1000 + page);
+            }
+        }
+        exit(0);
+    }
+    assert!(pid > 0);
+    unsafe {
+        // and the last one by the parent
+        DATA[(PAGES - 1) * WORDS] = 0xbeef;
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    unsafe {
+        assert_eq!(DATA[0], 0x1000);
+        for page in 1..PAGES - 1 {
+            assert_eq!(DATA[page * WORDS], 0x1000 + page);
+        }
+        assert_eq!(DATA[(PAGES - 1) * WORDS], 0xbeef);
+    }
+    println!("Test cow OK!");
+    0
+}
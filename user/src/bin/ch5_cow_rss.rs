@@ -0,0 +1,91 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, task_stats, waitpid, yield_, TaskStats, MAP_SHARED};
+
+/*
+理想结果：fork 之后父子进程共享的页计入 shared_pages 而不是 rss_pages；
+子进程写入 8 页触发复制后，双方的 shared_pages 都减少 8，rss_pages 都增加 8。
+输出 Test cow rss OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 8;
+
+/// Where parent and child hand over to each other
+#[repr(C)]
+struct Shared {
+    stage: usize,
+}
+
+fn wait_stage(shared: &Shared, stage: usize) {
+    while unsafe { core::ptr::read_volatile(&shared.stage) } != stage {
+        yield_();
+    }
+}
+
+fn set_stage(shared: &mut Shared, stage: usize) {
+    unsafe { core::ptr::write_volatile(&mut shared.stage, stage) };
+}
+
+fn fill(start: usize, value: u8) {
+    for i in 0..PAGES {
+        unsafe {
+            ((start + i * PAGE_SIZE) as *mut u8).write_volatile(value);
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let data: usize = 0x10000000;
+    let sync: usize = 0x20000000;
+    assert_eq!(0, mmap(data, PAGES * PAGE_SIZE, 3));
+    assert_eq!(0, mmap(sync, PAGE_SIZE, 3 | MAP_SHARED));
+    let shared = unsafe { &mut *(sync as *mut Shared) };
+    fill(data, 1);
+    let mut before = TaskStats::default();
+    let mut after = TaskStats::default();
+    assert_eq!(0, task_stats(&mut before));
+    // nothing is shared before fork
+    assert_eq!(before.shared_pages, 0);
+    let pid = fork();
+    if pid == 0 {
+        // copy the pages the measurements themselves write to first
+        task_stats(&mut after);
+        task_stats(&mut before);
+        assert!(before.shared_pages >= PAGES + 1);
+        set_stage(shared, 1);
+        wait_stage(shared, 2);
+        fill(data, 2);
+        task_stats(&mut after);
+        assert_eq!(after.shared_pages, before.shared_pages - PAGES);
+        assert_eq!(after.rss_pages, before.rss_pages + PAGES);
+        set_stage(shared, 3);
+        // stay alive until the parent measured, exit frees the child's frames
+        wait_stage(shared, 4);
+        exit(0);
+    }
+    wait_stage(shared, 1);
+    task_stats(&mut after);
+    task_stats(&mut before);
+    assert!(before.shared_pages >= PAGES + 1);
+    set_stage(shared, 2);
+    wait_stage(shared, 3);
+    // the child copied the pages, so the originals are the parent's alone
+    task_stats(&mut after);
+    assert_eq!(after.shared_pages, before.shared_pages - PAGES);
+    assert_eq!(after.rss_pages, before.rss_pages + PAGES);
+    set_stage(shared, 4);
+    unsafe {
+        assert_eq!((data as *const u8).read_volatile(), 1);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test cow rss OK!");
+    0
+}
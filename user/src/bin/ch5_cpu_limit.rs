@@ -0,0 +1,97 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    exit, fork, getrusage, prlimit, sigaction, sigreturn, sleep_blocking, waitpid, Rlimit, Rusage,
+    RLIMIT_CPU, RLIM_INFINITY, RUSAGE_CHILDREN, RUSAGE_SELF, SIGXCPU,
+};
+
+/*
+理想结果：RLIMIT_CPU 设为 1 秒后，空转的子进程在用掉约 1 秒 CPU 时间时被 SIGXCPU 杀死（退出码 -24），
+超过硬限制时被 SIGKILL 杀死（退出码 -9）；同样限制下大部分时间在睡眠的子进程正常退出；
+处理 SIGXCPU 的子进程用掉 2.5 秒 CPU 时间期间只收到两次信号。
+输出 Test cpu limit OK!
+*/
+
+/// Run `f` in a child whose cpu time is limited to `limit` and return its
+/// exit code
+fn run_limited(limit: Rlimit, f: fn()) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(prlimit(RLIMIT_CPU, Some(&limit), None), 0);
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn spin() {
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Longer than the budget, almost all of it asleep
+fn nap() {
+    for _ in 0..6 {
+        sleep_blocking(250);
+    }
+}
+
+/// User and kernel time of `who` so far, in microseconds
+fn cpu_time(who: isize) -> usize {
+    let mut usage = Rusage::default();
+    assert_eq!(getrusage(who, &mut usage), 0);
+    usage.utime + usage.stime
+}
+
+static XCPU_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn xcpu_handler(_signum: usize) {
+    XCPU_COUNT.fetch_add(1, Ordering::SeqCst);
+    sigreturn();
+}
+
+/// Survive SIGXCPU for 2.5 seconds of cpu time, exit with the number of
+/// times it came
+fn spin_handled() {
+    assert_eq!(sigaction(SIGXCPU, xcpu_handler as usize, None), 0);
+    while cpu_time(RUSAGE_SELF) < 2_500_000 {
+        core::hint::spin_loop();
+    }
+    exit(XCPU_COUNT.load(Ordering::SeqCst) as i32);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut limit = Rlimit::default();
+    assert_eq!(prlimit(RLIMIT_CPU, None, Some(&mut limit)), 0);
+    assert_eq!(limit.cur, RLIM_INFINITY);
+    assert_eq!(limit.max, RLIM_INFINITY);
+
+    let soft = Rlimit {
+        cur: 1,
+        max: RLIM_INFINITY,
+    };
+    let before = cpu_time(RUSAGE_CHILDREN);
+    assert_eq!(run_limited(soft, spin), -24);
+    let spent = cpu_time(RUSAGE_CHILDREN) - before;
+    // checked on each 10ms tick
+    assert!(spent >= 1_000_000 && spent < 1_200_000, "spent {}us", spent);
+
+    let hard = Rlimit { cur: 1, max: 1 };
+    assert_eq!(run_limited(hard, spin), -9);
+
+    assert_eq!(run_limited(soft, nap), 0);
+
+    // at 1 and 2 seconds, not on every tick in between
+    assert_eq!(run_limited(soft, spin_handled), 2);
+    println!("Test cpu limit OK!");
+    0
+}
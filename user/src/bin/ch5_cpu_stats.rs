@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{cpu_stats, exit, fork, get_time, sleep_blocking, waitpid, CpuStats};
+
+/*
+理想结果：所有子进程与自身都睡眠时，空闲时间不减少且不超过总时间，总时间随之增长。
+initproc 与 shell 在 wait 中轮询，CPU 未必真正空闲，所以只打印空闲时间的增量。
+输出 Test cpu stats OK!
+*/
+
+const CHILDREN: usize = 4;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = CpuStats::default();
+    assert_eq!(cpu_stats(&mut before), 0);
+    assert!(before.idle_us <= before.total_us);
+    let start = get_time();
+    let mut pids = [0; CHILDREN];
+    for pid in pids.iter_mut() {
+        *pid = fork();
+        if *pid == 0 {
+            sleep_blocking(50);
+            exit(0);
+        }
+    }
+    sleep_blocking(100);
+    for pid in pids.iter() {
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(*pid as usize, &mut exit_code), *pid);
+    }
+    let mut after = CpuStats::default();
+    assert_eq!(cpu_stats(&mut after), 0);
+    let elapsed_us = (get_time() - start) as usize * 1000;
+    assert!(after.idle_us <= after.total_us);
+    assert!(after.idle_us >= before.idle_us);
+    // get_time rounds down to milliseconds
+    assert!(after.total_us - before.total_us + 1000 >= elapsed_us);
+    assert!(after.idle_us - before.idle_us <= after.total_us - before.total_us);
+    println!(
+        "idle {} us of {} us",
+        after.idle_us - before.idle_us,
+        after.total_us - before.total_us
+    );
+    println!("Test cpu stats OK!");
+    0
+}
@@ -0,0 +1,26 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::{dup, write};
+
+/*
+理想结果：复制 stdout 得到最小的空闲 fd 3，通过它写出的内容出现在控制台；
+复制未打开的 fd 返回 -1。输出 Test dup OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 0, 1 and 2 are taken by stdin, stdout and stderr
+    let fd = dup(1);
+    assert_eq!(fd, 3);
+    assert_eq!(dup(fd as usize), 4);
+    assert_eq!(dup(5), -1);
+    assert_eq!(dup(usize::MAX), -1);
+    // stdin is not writable
+    assert_eq!(write(0, b"x"), -1);
+    let msg = b"Test dup OK!\n";
+    assert_eq!(write(fd as usize, msg), msg.len() as isize);
+    0
+}
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, syscall, waitpid, yield_, EFAULT, SYSCALL_CLOCK_GETTIME, SYSCALL_GETTIMEOFDAY,
+    SYSCALL_PIPE, SYSCALL_READ, SYSCALL_TASK_INFO, SYSCALL_UPTIME, SYSCALL_WAITPID, SYSCALL_WRITE,
+};
+
+/*
+理想结果：向需要写入用户内存的系统调用传入空指针、未映射地址或只读地址，向需要读取的传入空指针
+或未映射地址，都返回 -14（EFAULT），内核不会 panic，等待失败的子进程也不会被回收。
+输出 Test efault OK!
+*/
+
+const UNMAPPED: usize = 0x5000_0000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // read-only code
+    let text = main as usize;
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    for bad in [0, UNMAPPED, text] {
+        assert_eq!(-EFAULT, syscall(SYSCALL_GETTIMEOFDAY, [bad, 0, 0]));
+        assert_eq!(-EFAULT, syscall(SYSCALL_TASK_INFO, [bad, 0, 0]));
+        assert_eq!(-EFAULT, syscall(SYSCALL_CLOCK_GETTIME, [0, bad, 0]));
+        assert_eq!(-EFAULT, syscall(SYSCALL_UPTIME, [bad, 0, 0]));
+        assert_eq!(-EFAULT, syscall(SYSCALL_PIPE, [bad, 0, 0]));
+        assert_eq!(-EFAULT, syscall(SYSCALL_READ, [0, bad, 1]));
+        loop {
+            match syscall(SYSCALL_WAITPID, [pid as usize, bad, 0]) {
+                -2 => {
+                    yield_();
+                }
+                n => {
+                    assert_eq!(n, -EFAULT);
+                    break;
+                }
+            }
+        }
+    }
+    // writing only needs the buffer readable
+    for bad in [0, UNMAPPED] {
+        assert_eq!(-EFAULT, syscall(SYSCALL_WRITE, [1, bad, 1]));
+    }
+    // the failed pipes have not taken fds
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(user_lib::pipe(&mut pipe_fd), 0);
+    assert_eq!(pipe_fd[0], 3);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    println!("Test efault OK!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, exit, fork, waitpid};
+
+/*
+辅助程序 ch5_exec_args_echo 以 argc 作为退出码。
+理想结果：带参数与不带参数的 exec 均能把 argc、argv 正确传给新程序。
+输出 Test exec args OK!
+*/
+
+fn run(args: &[*const u8]) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exec("ch5_exec_args_echo\0", args);
+        println!("exec failed, Test exec args fail!");
+        exit(-1);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let args = [
+        "ch5_exec_args_echo\0".as_ptr(),
+        "hello\0".as_ptr(),
+        "world\0".as_ptr(),
+        core::ptr::null::<u8>(),
+    ];
+    assert_eq!(run(&args), 3);
+    assert_eq!(run(&[core::ptr::null::<u8>()]), 0);
+    println!("Test exec args OK!");
+    0
+}
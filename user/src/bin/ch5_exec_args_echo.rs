@@ -0,0 +1,20 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/// 辅助测例，打印参数并以 argc 作为退出码，由 ch5_exec_args 调用。
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    assert_eq!(argc, argv.len());
+    for (i, arg) in argv.iter().enumerate() {
+        println!("argv[{}] = {}", i, arg);
+    }
+    if argc == 3 {
+        assert_eq!(argv[1], "hello");
+        assert_eq!(argv[2], "world");
+    }
+    argc as i32
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{execve, exit, fork, waitpid};
+
+/*
+辅助程序 ch5_exec_env_echo 以环境变量 GREETING 的长度作为退出码，未设置时退出码为 255。
+理想结果：exec 能把环境变量传给新程序，空环境也能正确处理。输出 Test exec env OK!
+*/
+
+fn run(args: &[*const u8], envs: &[*const u8]) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        execve("ch5_exec_env_echo\0", args, envs);
+        println!("exec failed, Test exec env fail!");
+        exit(-1);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let args = [
+        "ch5_exec_env_echo\0".as_ptr(),
+        "hello\0".as_ptr(),
+        core::ptr::null::<u8>(),
+    ];
+    let envs = [
+        "PATH=/bin\0".as_ptr(),
+        "GREETING=hello world\0".as_ptr(),
+        core::ptr::null::<u8>(),
+    ];
+    assert_eq!(run(&args, &envs), 11);
+    // an empty value is still set
+    let envs = ["GREETING=\0".as_ptr(), core::ptr::null::<u8>()];
+    assert_eq!(run(&args, &envs), 0);
+    // only the whole key matches
+    let envs = ["GREETINGS=hi\0".as_ptr(), core::ptr::null::<u8>()];
+    assert_eq!(run(&args, &envs), 255);
+    assert_eq!(run(&args, &[core::ptr::null::<u8>()]), 255);
+    println!("Test exec env OK!");
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::getenv;
+
+/// 辅助测例，以环境变量 GREETING 的长度作为退出码，未设置时为 255，由 ch5_exec_env 调用。
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    // the environment does not disturb the arguments
+    assert_eq!(argc, 2);
+    assert_eq!(argv[1], "hello");
+    match getenv("GREETING") {
+        Some(greeting) => {
+            println!("GREETING = {}", greeting);
+            greeting.len() as i32
+        }
+        None => 255,
+    }
+}
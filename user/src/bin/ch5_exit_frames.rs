@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{meminfo, spawn, waitpid, MemInfo};
+
+/*
+理想结果：反复 spawn 一个小程序并回收 100 次后，空闲物理页帧数回到初始值（至多相差几页）。
+输出 Test exit frames OK!
+*/
+
+const TIMES: usize = 100;
+/// Frames the kernel may keep for good, like the page tables mapping the
+/// kernel stacks of new pids
+const TOLERANCE: usize = 4;
+
+fn spawn_and_wait() {
+    let cpid = spawn("ch5_exit0\0");
+    assert!(cpid > 0, "child pid invalid");
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(cpid as usize, &mut exit_code), cpid);
+    assert_eq!(exit_code, 66778);
+}
+
+fn free_frames() -> usize {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info.free_frames
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the first child may map the kernel stack of a pid never used before
+    spawn_and_wait();
+    let baseline = free_frames();
+    for _ in 0..TIMES {
+        spawn_and_wait();
+    }
+    let free = free_frames();
+    println!("free frames: {} before, {} after", baseline, free);
+    assert!(free + TOLERANCE >= baseline);
+    println!("Test exit frames OK!");
+    0
+}
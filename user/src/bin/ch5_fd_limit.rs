@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, getdtablesize, pipe, EMFILE};
+
+/*
+理想结果：不断 dup 直到打开的 fd 数达到上限，之后 dup 与 pipe 均返回 -EMFILE；
+关闭其中一个后 dup 重新得到这个 fd。输出 Test fd limit OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let limit = getdtablesize();
+    assert!(limit > 3);
+    // stdin, stdout and stderr are open
+    let mut opened = 3;
+    let mut last = 0;
+    loop {
+        let fd = dup(1);
+        if fd < 0 {
+            assert_eq!(fd, -EMFILE);
+            break;
+        }
+        assert!(fd < limit);
+        last = fd;
+        opened += 1;
+    }
+    println!("{} fds open, the limit is {}", opened, limit);
+    assert_eq!(opened, limit);
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), -EMFILE);
+    assert_eq!(close(last as usize), 0);
+    assert_eq!(close(last as usize), -1);
+    assert_eq!(dup(1), last);
+    assert_eq!(dup(1), -EMFILE);
+    // one free fd is not enough for a pipe, and the read end is not leaked
+    assert_eq!(close(last as usize), 0);
+    assert_eq!(pipe(&mut pipe_fd), -EMFILE);
+    assert_eq!(dup(1), last);
+    for fd in 3..limit {
+        assert_eq!(close(fd as usize), 0);
+    }
+    println!("Test fd limit OK!");
+    0
+}
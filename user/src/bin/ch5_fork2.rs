@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork2, kill, sleep_blocking, waitpid, EAGAIN, SIGKILL};
+
+/*
+理想结果：fork2 成功时返回 0，父进程得到子进程的 pid，子进程得到 0；
+任务数达到上限后 fork2 返回 -EAGAIN 而不是一个像 pid 的值。输出 Test fork2 OK!
+*/
+
+/// MAX_TASKS in the kernel
+const MAX_TASKS: usize = 128;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pid: isize = -1;
+    assert_eq!(fork2(&mut pid), 0);
+    if pid == 0 {
+        exit(7);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    // fork until there is no pid left
+    let mut children = [0usize; MAX_TASKS];
+    let mut count = 0;
+    let err = loop {
+        let mut pid: isize = -1;
+        let ret = fork2(&mut pid);
+        if ret != 0 {
+            break ret;
+        }
+        if pid == 0 {
+            loop {
+                sleep_blocking(10);
+            }
+        }
+        assert!(count < MAX_TASKS, "fork2 never fails");
+        children[count] = pid as usize;
+        count += 1;
+    };
+    assert_eq!(err, -EAGAIN);
+    assert!(count > 0);
+    for &pid in children[..count].iter() {
+        assert_eq!(kill(pid, SIGKILL), 0);
+        assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+        assert_eq!(exit_code, -9);
+    }
+    println!("Test fork2 OK!");
+    0
+}
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, kill, sleep_blocking, spawn, waitpid, SIGKILL};
+
+/*
+理想结果：不断 fork 最终返回 -1 而内核不会崩溃，父进程不受影响；
+杀死并回收若干子进程后 fork 重新成功。输出 Test fork limit OK!
+*/
+
+/// MAX_TASKS in the kernel
+const MAX_TASKS: usize = 128;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut children = [0usize; MAX_TASKS];
+    let mut count = 0;
+    loop {
+        let pid = fork();
+        if pid == 0 {
+            loop {
+                sleep_blocking(10);
+            }
+        }
+        if pid < 0 {
+            break;
+        }
+        assert!(count < MAX_TASKS, "fork never fails");
+        children[count] = pid as usize;
+        count += 1;
+    }
+    assert!(count > 0);
+    assert_eq!(-1, spawn("ch5_exec_args_echo\0"));
+    // a zombie still counts until it is reaped
+    let mut exit_code: i32 = 0;
+    for &pid in children[..2].iter() {
+        assert_eq!(kill(pid, SIGKILL), 0);
+    }
+    for &pid in children[..2].iter() {
+        assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+        assert_eq!(exit_code, -9);
+    }
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    for &pid in children[2..count].iter() {
+        assert_eq!(kill(pid, SIGKILL), 0);
+        assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    }
+    println!("Test fork limit OK!");
+    0
+}
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_priority, sched_deterministic, set_priority, spawn, spawn_with,
+    task_sched_info, waitpid, SchedInfo, SPAWN_INHERIT_PRIO,
+};
+
+/*
+理想结果：fork 出的子进程继承父进程的优先级 50，stride 从父进程当前的 stride 重新开始；
+spawn 默认给子进程默认优先级 16，带 SPAWN_INHERIT_PRIO 时继承父进程的优先级。输出 Test fork priority OK!
+*/
+
+const BIG_STRIDE: u64 = 0x1111_1111;
+const DEFAULT_PRIORITY: usize = 16;
+
+fn sched_info(pid: isize) -> SchedInfo {
+    let mut info = SchedInfo::default();
+    assert_eq!(task_sched_info(pid, &mut info), 0);
+    info
+}
+
+fn reap(pid: isize) {
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_priority(50), 50);
+    // the parent is not charged a pass until it gives up the processor
+    let was_deterministic = sched_deterministic(true);
+    let parent = sched_info(-1);
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(get_priority(), 50);
+        let child = sched_info(-1);
+        assert_eq!(child.pass, BIG_STRIDE / 50);
+        assert_eq!(child.stride, parent.stride);
+        exit(0);
+    }
+    reap(pid);
+
+    let parent = sched_info(-1);
+    let pid = spawn("ch5_exit0\0");
+    assert!(pid > 0);
+    let child = sched_info(pid);
+    assert_eq!(child.priority, DEFAULT_PRIORITY);
+    assert_eq!(child.stride, parent.stride);
+    reap(pid);
+    let pid = spawn_with("ch5_exit0\0", SPAWN_INHERIT_PRIO);
+    assert!(pid > 0);
+    assert_eq!(sched_info(pid).priority, 50);
+    reap(pid);
+    assert_eq!(spawn_with("ch5_exit0\0", 2), -1);
+    sched_deterministic(was_deterministic);
+    println!("Test fork priority OK!");
+    0
+}
@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use user_lib::{exit, futex_wait, futex_wake, thread_create, yield_, EAGAIN};
+
+/*
+理想结果：两个线程用 CAS 快速路径加 futex 慢速路径实现的锁保护同一个非原子计数器，
+临界区内主动让出处理器，最终计数器恰为 2 * ROUNDS，且确实有线程在 futex 上睡眠过；
+值不符时 FUTEX_WAIT 立即返回 -EAGAIN。输出 Test futex OK!
+*/
+
+const ROUNDS: usize = 200;
+
+/// 0: free, 1: held, 2: held and there may be waiters
+static LOCK: AtomicU32 = AtomicU32::new(0);
+static mut COUNTER: usize = 0;
+/// Times a thread went to sleep on the futex
+static SLEEPS: AtomicUsize = AtomicUsize::new(0);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+
+fn lock() {
+    // fast path, no syscall without contention
+    if LOCK
+        .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+        .is_ok()
+    {
+        return;
+    }
+    // slow path: mark the lock contended and sleep until it is free
+    while LOCK.swap(2, Ordering::Acquire) != 0 {
+        if futex_wait(&LOCK, 2) == 0 {
+            SLEEPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn unlock() {
+    if LOCK.swap(0, Ordering::Release) == 2 {
+        futex_wake(&LOCK, 1);
+    }
+}
+
+fn worker() -> ! {
+    for _ in 0..ROUNDS {
+        lock();
+        let value = unsafe { core::ptr::read_volatile(&COUNTER) };
+        // let the other thread find the lock held
+        yield_();
+        unsafe { core::ptr::write_volatile(&mut COUNTER, value + 1) };
+        unlock();
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let word = AtomicU32::new(5);
+    assert_eq!(futex_wait(&word, 4), -EAGAIN);
+    assert_eq!(futex_wake(&word, 1), 0);
+
+    let tid1 = thread_create(worker as usize, 0);
+    let tid2 = thread_create(worker as usize, 0);
+    assert!(tid1 > 0 && tid2 > 0);
+    while DONE.load(Ordering::SeqCst) < 2 {
+        yield_();
+    }
+    assert_eq!(unsafe { COUNTER }, 2 * ROUNDS);
+    assert_eq!(LOCK.load(Ordering::Relaxed), 0);
+    println!("{} sleeps on the futex", SLEEPS.load(Ordering::Relaxed));
+    assert!(SLEEPS.load(Ordering::Relaxed) > 0);
+    println!("Test futex OK!");
+    0
+}
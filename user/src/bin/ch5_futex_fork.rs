@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use user_lib::{
+    exit, fork, futex_wait, futex_wake, mmap, thread_create, waitpid, yield_, MAP_SHARED,
+};
+
+/*
+理想结果：一个线程在 futex 上睡眠时主线程 fork，子进程存活期间主线程写入 futex 所在的页并唤醒，
+恰好唤醒那个线程；子进程看到的仍是 fork 时的值。输出 Test futex fork OK!
+*/
+
+static WORD: AtomicU32 = AtomicU32::new(0);
+static READY: AtomicUsize = AtomicUsize::new(0);
+static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+fn waiter() -> ! {
+    READY.store(1, Ordering::SeqCst);
+    while WORD.load(Ordering::SeqCst) == 0 {
+        futex_wait(&WORD, 0);
+    }
+    WOKEN.store(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let sync: usize = 0x20000000;
+    assert_eq!(0, mmap(sync, 4096, 3 | MAP_SHARED));
+    let stage = unsafe { &*(sync as *const AtomicUsize) };
+    assert!(thread_create(waiter as usize, 0) > 0);
+    while READY.load(Ordering::SeqCst) == 0 {
+        yield_();
+    }
+    // let it fall asleep
+    for _ in 0..10 {
+        yield_();
+    }
+    let pid = fork();
+    if pid == 0 {
+        while stage.load(Ordering::SeqCst) == 0 {
+            yield_();
+        }
+        exit(WORD.load(Ordering::SeqCst) as i32);
+    }
+    // the child still shares the pages, the store must not move the futex
+    WORD.store(1, Ordering::SeqCst);
+    assert_eq!(futex_wake(&WORD, 1), 1);
+    while WOKEN.load(Ordering::SeqCst) == 0 {
+        yield_();
+    }
+    stage.store(1, Ordering::SeqCst);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test futex fork OK!");
+    0
+}
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, getppid, mmap, waitpid, yield_, MAP_SHARED};
+
+/*
+理想结果：子进程的 getppid 返回父进程的 pid；父进程退出后孙进程被挂到 initproc 下，
+getppid 返回 initproc 的 pid 0。输出 Test getppid OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(getppid() as i32);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code as isize, getpid());
+
+    // the grandchild reports through a shared page
+    let report: usize = 0x10000000;
+    assert_eq!(0, mmap(report, 4096, 3 | MAP_SHARED));
+    let report = report as *mut isize;
+    unsafe {
+        report.write_volatile(-1);
+    }
+    let pid = fork();
+    if pid == 0 {
+        let parent = getpid();
+        if fork() == 0 {
+            while getppid() == parent {
+                yield_();
+            }
+            unsafe {
+                report.write_volatile(getppid());
+            }
+            exit(0);
+        }
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    while unsafe { report.read_volatile() } == -1 {
+        yield_();
+    }
+    assert_eq!(unsafe { report.read_volatile() }, 0);
+    println!("Test getppid OK!");
+    0
+}
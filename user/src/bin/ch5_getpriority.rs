@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_priority, set_priority, waitpid};
+
+/*
+理想结果：设置的优先级可以读回，上限 1024 以内的值可以设置，非法值不会改变优先级；新 fork 的进程继承父进程的优先级。
+输出 Test getpriority OK!
+*/
+
+const DEFAULT_PRIORITY: isize = 16;
+const MAX_PRIORITY: isize = 1024;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(get_priority(), DEFAULT_PRIORITY);
+    assert_eq!(set_priority(42), 42);
+    assert_eq!(get_priority(), 42);
+    assert_eq!(set_priority(1), -1);
+    assert_eq!(set_priority(MAX_PRIORITY + 1), -1);
+    assert_eq!(get_priority(), 42);
+    assert_eq!(set_priority(MAX_PRIORITY), MAX_PRIORITY);
+    assert_eq!(set_priority(42), 42);
+    let pid = fork();
+    if pid == 0 {
+        exit(get_priority() as i32);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 42);
+    println!("Test getpriority OK!");
+    0
+}
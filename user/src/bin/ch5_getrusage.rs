@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getrusage, mmap, munmap, waitpid, Rusage, MAP_LAZY, RUSAGE_CHILDREN, RUSAGE_SELF,
+};
+
+/*
+理想结果：访问惰性映射的页使 minflt 和 maxrss 随之增加，munmap 后 maxrss 作为峰值保持不变；
+子进程被回收后，其用量计入 RUSAGE_CHILDREN。输出 Test getrusage OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 16;
+
+fn usage(who: isize) -> Rusage {
+    let mut usage = Rusage::default();
+    assert_eq!(getrusage(who, &mut usage), 0);
+    usage
+}
+
+/// Map `pages` lazy pages and write to each, a fault apiece
+fn touch(start: usize, pages: usize) {
+    assert_eq!(mmap(start, pages * PAGE_SIZE, 3 | MAP_LAZY), 0);
+    for i in 0..pages {
+        unsafe {
+            ((start + i * PAGE_SIZE) as *mut u8).write_volatile(i as u8);
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let before = usage(RUSAGE_SELF);
+    touch(start, PAGES);
+    let after = usage(RUSAGE_SELF);
+    assert!(after.minflt >= before.minflt + PAGES);
+    assert!(after.maxrss >= before.maxrss + PAGES);
+    assert_eq!(after.majflt, 0);
+    assert_eq!(munmap(start, PAGES * PAGE_SIZE), 0);
+    // a high-water mark
+    assert_eq!(usage(RUSAGE_SELF).maxrss, after.maxrss);
+
+    let children = usage(RUSAGE_CHILDREN);
+    assert_eq!(children.minflt, 0);
+    assert_eq!(children.maxrss, 0);
+    let pid = fork();
+    if pid == 0 {
+        touch(start, PAGES * 2);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    let children = usage(RUSAGE_CHILDREN);
+    assert!(children.minflt >= PAGES * 2);
+    assert!(children.maxrss >= PAGES * 2);
+    assert_eq!(getrusage(1, &mut Rusage::default()), -1);
+    println!("Test getrusage OK!");
+    0
+}
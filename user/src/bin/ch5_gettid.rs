@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use user_lib::{exit, fork, getpid, gettid, thread_create, waitpid, yield_};
+
+/*
+理想结果：主线程与两个新线程的 tid 分别为 0、1、2，getpid 在各线程中相同；
+线程 fork 出的子进程 tid 从 0 重新开始。输出 Test gettid OK!
+*/
+
+static TIDS: [AtomicIsize; 3] = [
+    AtomicIsize::new(-1),
+    AtomicIsize::new(-1),
+    AtomicIsize::new(-1),
+];
+static PIDS: [AtomicIsize; 3] = [
+    AtomicIsize::new(-1),
+    AtomicIsize::new(-1),
+    AtomicIsize::new(-1),
+];
+static FORKED_TID: AtomicIsize = AtomicIsize::new(-1);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+
+fn report(index: usize) {
+    TIDS[index].store(gettid(), Ordering::SeqCst);
+    PIDS[index].store(getpid(), Ordering::SeqCst);
+}
+
+fn worker(index: usize) -> ! {
+    report(index);
+    if index == 2 {
+        let pid = fork();
+        if pid == 0 {
+            exit(gettid() as i32);
+        }
+        let mut exit_code: i32 = -1;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        FORKED_TID.store(exit_code as isize, Ordering::SeqCst);
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    report(0);
+    assert!(thread_create(worker as usize, 1) > 0);
+    assert!(thread_create(worker as usize, 2) > 0);
+    while DONE.load(Ordering::SeqCst) < 2 {
+        yield_();
+    }
+    let mut tids = [0isize; 3];
+    for (tid, reported) in tids.iter_mut().zip(TIDS.iter()) {
+        *tid = reported.load(Ordering::SeqCst);
+    }
+    tids.sort_unstable();
+    assert_eq!(tids, [0, 1, 2]);
+    assert_eq!(TIDS[0].load(Ordering::SeqCst), 0);
+    for pid in PIDS.iter() {
+        assert_eq!(pid.load(Ordering::SeqCst), getpid());
+    }
+    assert_eq!(FORKED_TID.load(Ordering::SeqCst), 0);
+    println!("Test gettid OK!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid, yield_};
+
+/*
+理想结果：执行非法指令的子进程以 -3 退出，其兄弟进程与父进程照常运行。
+输出 Test illegal instruction OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let bad = fork();
+    if bad == 0 {
+        // privileged in user mode
+        unsafe {
+            core::arch::asm!("sret");
+        }
+        println!("Should cause error, Test illegal instruction fail!");
+        exit(0);
+    }
+    let sibling = fork();
+    if sibling == 0 {
+        for _ in 0..10 {
+            yield_();
+        }
+        exit(7);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(bad as usize, &mut exit_code), bad);
+    assert_eq!(exit_code, -3);
+    assert_eq!(waitpid(sibling as usize, &mut exit_code), sibling);
+    assert_eq!(exit_code, 7);
+    println!("Test illegal instruction OK!");
+    0
+}
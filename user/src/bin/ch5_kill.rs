@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{fork, kill, waitpid, yield_, SIGKILL, SIGTERM};
+
+/*
+理想结果：父进程用 SIGKILL、SIGTERM 杀死死循环的子进程，waitpid 得到的退出码为 -9、-15；
+不存在的进程或非法信号返回 -1。输出 Test kill OK!
+*/
+
+fn spin() -> ! {
+    let mut i: usize = 0;
+    loop {
+        i = i.wrapping_add(1);
+        unsafe {
+            core::ptr::write_volatile(&mut i, i);
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for (signum, expected) in [(SIGKILL, -9), (SIGTERM, -15)] {
+        let pid = fork();
+        if pid == 0 {
+            spin();
+        }
+        // let the child start spinning
+        for _ in 0..10 {
+            yield_();
+        }
+        assert_eq!(kill(pid as usize, 0), -1);
+        assert_eq!(kill(pid as usize, 32), -1);
+        assert_eq!(kill(pid as usize, signum), 0);
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, expected);
+        assert_eq!(kill(pid as usize, signum), -1);
+    }
+    println!("Test kill OK!");
+    0
+}
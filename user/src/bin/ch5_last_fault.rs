@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{exit, fork, last_fault, mmap, sigaction, sigreturn, waitpid, FaultInfo, SIGSEGV};
+
+/*
+理想结果：没有 SIGSEGV 处理函数的进程访问非法地址后被杀死，退出码为 -2；注册处理函数后，
+处理函数通过 last_fault 读到准确的出错地址和原因，映射该页后返回，访问重新执行并成功。
+输出 Test last fault OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const LOAD_PAGE_FAULT: usize = 13;
+const STORE_PAGE_FAULT: usize = 15;
+
+static FAULT_ADDR: AtomicUsize = AtomicUsize::new(0);
+static FAULT_CAUSE: AtomicUsize = AtomicUsize::new(0);
+static FAULT_WRITE: AtomicUsize = AtomicUsize::new(0);
+
+/// Note the fault and map the page, so the access succeeds when retried
+fn handler(signum: usize) {
+    assert_eq!(signum, SIGSEGV as usize);
+    let mut info = FaultInfo::default();
+    assert_eq!(last_fault(&mut info), 0);
+    FAULT_ADDR.store(info.addr, Ordering::SeqCst);
+    FAULT_CAUSE.store(info.cause, Ordering::SeqCst);
+    FAULT_WRITE.store(info.is_write as usize, Ordering::SeqCst);
+    assert_eq!(mmap(info.addr & !(PAGE_SIZE - 1), PAGE_SIZE, 3), 0);
+    sigreturn();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut info = FaultInfo::default();
+    assert_eq!(last_fault(&mut info), -1);
+    let load_addr: usize = 0x3000_0123;
+    let store_addr: usize = 0x3000_2008;
+    // without a handler the fault is fatal as usual
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            (load_addr as *const u8).read_volatile();
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+
+    assert_eq!(sigaction(SIGSEGV, handler as usize, None), 0);
+    let value = unsafe { (load_addr as *const u8).read_volatile() };
+    assert_eq!(value, 0);
+    assert_eq!(FAULT_ADDR.load(Ordering::SeqCst), load_addr);
+    assert_eq!(FAULT_CAUSE.load(Ordering::SeqCst), LOAD_PAGE_FAULT);
+    assert_eq!(FAULT_WRITE.load(Ordering::SeqCst), 0);
+    unsafe {
+        (store_addr as *mut usize).write_volatile(7);
+        assert_eq!((store_addr as *const usize).read_volatile(), 7);
+    }
+    assert_eq!(FAULT_ADDR.load(Ordering::SeqCst), store_addr);
+    assert_eq!(FAULT_CAUSE.load(Ordering::SeqCst), STORE_PAGE_FAULT);
+    assert_eq!(FAULT_WRITE.load(Ordering::SeqCst), 1);
+    assert_eq!(last_fault(&mut info), 0);
+    assert_eq!(info.addr, store_addr);
+    println!("Test last fault OK!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::slice::from_raw_parts_mut;
+use user_lib::{list_apps, mmap};
+
+/*
+理想结果：应用名列表以换行分隔，包含 ch5b_initproc 与本测例；缓冲区跨页时完整写入，
+缓冲区不足时截断并返回总长度。输出 Test list apps OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, PAGE_SIZE * 2, 3));
+    // the list starts 16 bytes before the page boundary
+    let buf = unsafe { from_raw_parts_mut((start + PAGE_SIZE - 16) as *mut u8, PAGE_SIZE) };
+    let total = list_apps(buf);
+    assert!(total > 16 && total as usize <= buf.len());
+    let list = core::str::from_utf8(&buf[..total as usize]).unwrap();
+    assert!(list.ends_with('\n'));
+    assert!(list.lines().any(|name| name == "ch5b_initproc"));
+    assert!(list.lines().any(|name| name == "ch5_list_apps"));
+    assert!(list.lines().all(|name| !name.is_empty()));
+    // only 8 bytes fit, the total length is still returned
+    let mut small = [0xaau8; 9];
+    assert_eq!(list_apps(&mut small[..8]), total);
+    assert_eq!(&small[..8], &list.as_bytes()[..8]);
+    assert_eq!(small[8], 0xaa);
+    println!("Test list apps OK!");
+    0
+}
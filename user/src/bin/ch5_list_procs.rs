@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, list_procs, set_priority, sleep_blocking, waitpid, ProcEntry, TaskStatus,
+};
+
+/*
+理想结果：三个子进程都出现在进程列表中，ppid 为当前进程，优先级与设置的一致；
+缓冲区不足时返回总数。输出 Test list procs OK!
+*/
+
+const MAX_PROCS: usize = 32;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let me = getpid() as usize;
+    let mut children = [0usize; 3];
+    for (i, child) in children.iter_mut().enumerate() {
+        let pid = fork();
+        if pid == 0 {
+            set_priority(10 + i as isize);
+            sleep_blocking(100);
+            exit(0);
+        }
+        *child = pid as usize;
+    }
+    // let the children set their priorities
+    sleep_blocking(20);
+    let mut procs = [ProcEntry::new(); MAX_PROCS];
+    let total = list_procs(&mut procs);
+    assert!(total >= 4 && total as usize <= MAX_PROCS);
+    let procs = &procs[..total as usize];
+    let entry = procs.iter().find(|p| p.pid == me).unwrap();
+    assert!(entry.status == TaskStatus::Running);
+    for (i, &child) in children.iter().enumerate() {
+        let entry = procs.iter().find(|p| p.pid == child).unwrap();
+        assert_eq!(entry.ppid, me);
+        assert_eq!(entry.priority, 10 + i);
+        assert!(entry.status == TaskStatus::Blocking);
+    }
+    // only one fits, the total count is still returned
+    let mut one = [ProcEntry::new(); 1];
+    assert!(list_procs(&mut one) >= 4);
+    // ordered by pid, initproc comes first
+    assert_eq!(one[0].pid, 0);
+    for &child in children.iter() {
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(child, &mut exit_code), child as isize);
+    }
+    let mut procs = [ProcEntry::new(); MAX_PROCS];
+    let after = list_procs(&mut procs);
+    assert!(after >= 1 && after as usize <= MAX_PROCS);
+    for child in children.iter() {
+        assert!(procs[..after as usize].iter().all(|p| p.pid != *child));
+    }
+    println!("Test list procs OK!");
+    0
+}
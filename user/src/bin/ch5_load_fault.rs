@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::read_volatile;
+use user_lib::{exit, fork, waitpid, yield_};
+
+/*
+理想结果：从未映射地址读取的子进程被杀死，其兄弟进程与父进程照常运行。
+输出 Test load fault OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let bad = fork();
+    if bad == 0 {
+        let value = unsafe { read_volatile(0x5000_0000 as *const u8) };
+        println!("Should cause error, Test load fault fail! {}", value);
+        exit(0);
+    }
+    let sibling = fork();
+    if sibling == 0 {
+        for _ in 0..10 {
+            yield_();
+        }
+        exit(7);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(bad as usize, &mut exit_code), bad);
+    assert_eq!(exit_code, -2);
+    assert_eq!(waitpid(sibling as usize, &mut exit_code), sibling);
+    assert_eq!(exit_code, 7);
+    println!("Test load fault OK!");
+    0
+}
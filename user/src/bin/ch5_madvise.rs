@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{madvise, mmap, munmap, task_stats, TaskStats, MADV_DONTNEED};
+
+/*
+理想结果：MADV_DONTNEED 释放范围内的物理页但保留映射，再次访问读到全零，只有再次访问的页重新占用物理页；
+范围内有页未映射或 advice 未知时返回 -1。输出 Test madvise OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 4;
+
+fn rss_pages() -> usize {
+    let mut stats = TaskStats::default();
+    assert_eq!(task_stats(&mut stats), 0);
+    stats.rss_pages
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len = PAGE_SIZE * PAGES;
+    assert_eq!(0, mmap(start, len, 3));
+    let data = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, len) };
+    data.fill(0x5a);
+    let rss = rss_pages();
+    // the middle two pages
+    assert_eq!(madvise(start + PAGE_SIZE, PAGE_SIZE * 2, MADV_DONTNEED), 0);
+    assert_eq!(rss_pages(), rss - 2);
+    assert!(data[..PAGE_SIZE].iter().all(|&b| b == 0x5a));
+    assert!(data[PAGE_SIZE * 3..].iter().all(|&b| b == 0x5a));
+    // one page touched again
+    assert!(data[PAGE_SIZE..PAGE_SIZE * 2].iter().all(|&b| b == 0));
+    assert_eq!(rss_pages(), rss - 1);
+    data[PAGE_SIZE * 2..PAGE_SIZE * 3].fill(0x33);
+    assert_eq!(rss_pages(), rss);
+    assert!(data[PAGE_SIZE * 2..PAGE_SIZE * 3]
+        .iter()
+        .all(|&b| b == 0x33));
+    // twice is fine
+    assert_eq!(madvise(start, len, MADV_DONTNEED), 0);
+    assert_eq!(madvise(start, len, MADV_DONTNEED), 0);
+    assert!(data.iter().all(|&b| b == 0));
+    // partly unmapped, unknown advice
+    assert_eq!(madvise(start, len + PAGE_SIZE, MADV_DONTNEED), -1);
+    assert_eq!(madvise(start, len, 0), -1);
+    assert_eq!(0, munmap(start, len));
+    assert_eq!(madvise(start, len, MADV_DONTNEED), -1);
+    println!("Test madvise OK!");
+    0
+}
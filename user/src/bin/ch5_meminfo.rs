@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{meminfo, mmap, munmap, MemInfo};
+
+/*
+理想结果：total = free + used；mmap N 页后 free_frames 恰好减少 N，munmap 后恢复。
+输出 Test meminfo OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+const N: usize = 16;
+
+fn free_frames() -> usize {
+    let mut info = MemInfo::default();
+    assert_eq!(0, meminfo(&mut info));
+    assert_eq!(info.total_frames, info.free_frames + info.used_frames);
+    info.free_frames
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the page table of the range stays after munmap, so later mappings
+    // only take the frames of the pages
+    assert_eq!(0, mmap(START, N * PAGE_SIZE, 3));
+    assert_eq!(0, munmap(START, N * PAGE_SIZE));
+    let free = free_frames();
+    assert_eq!(0, mmap(START, N * PAGE_SIZE, 3));
+    assert_eq!(free_frames(), free - N);
+    assert_eq!(0, munmap(START, N * PAGE_SIZE));
+    assert_eq!(free_frames(), free);
+    assert_eq!(
+        -user_lib::EFAULT,
+        user_lib::syscall(user_lib::SYSCALL_MEMINFO, [0, 0, 0])
+    );
+    println!("Test meminfo OK!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid, yield_};
+
+/*
+理想结果：从未对齐地址读取的子进程以 -4 退出，其兄弟进程与父进程照常运行。
+输出 Test misaligned load OK!
+*/
+
+static WORDS: [u32; 2] = [0; 2];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let bad = fork();
+    if bad == 0 {
+        // a plain load may be fixed up below the kernel, lr.w has to be aligned
+        let addr = WORDS.as_ptr() as usize + 1;
+        let value: usize;
+        unsafe {
+            core::arch::asm!("lr.w {}, ({})", out(reg) value, in(reg) addr);
+        }
+        println!(
+            "Should cause error, got {}, Test misaligned load fail!",
+            value
+        );
+        exit(0);
+    }
+    let sibling = fork();
+    if sibling == 0 {
+        for _ in 0..10 {
+            yield_();
+        }
+        exit(7);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(bad as usize, &mut exit_code), bad);
+    assert_eq!(exit_code, -4);
+    assert_eq!(waitpid(sibling as usize, &mut exit_code), sibling);
+    assert_eq!(exit_code, 7);
+    println!("Test misaligned load OK!");
+    0
+}
@@ -0,0 +1,113 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{fork, mmap, mprotect, munmap, task_stats, waitpid, TaskStats, MAP_HUGE, MAP_LAZY};
+
+/*
+理想结果：用 MAP_HUGE 映射 4MiB 只需要现有的页表，4KiB 页映射同样大小要新增页表页；
+大页可以读写，部分 mprotect / munmap 会把大页拆成 4KiB 页，fork 后父子进程互不影响；
+未对齐的范围退回普通页，MAP_HUGE 与 MAP_LAZY 同时使用返回 -1。
+输出 Test mmap huge OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const HUGE_PAGE_SIZE: usize = 0x20_0000;
+const LEN: usize = HUGE_PAGE_SIZE * 2;
+
+fn page_table_frames() -> usize {
+    let mut stats = TaskStats::default();
+    assert_eq!(0, task_stats(&mut stats));
+    stats.page_table_frames
+}
+
+fn rss_pages() -> usize {
+    let mut stats = TaskStats::default();
+    assert_eq!(0, task_stats(&mut stats));
+    stats.rss_pages
+}
+
+fn fill(start: usize, len: usize, seed: u8) {
+    for addr in (start..start + len).step_by(PAGE_SIZE) {
+        unsafe { *(addr as *mut u8) = seed.wrapping_add((addr / PAGE_SIZE) as u8) };
+    }
+}
+
+fn check(start: usize, len: usize, seed: u8) {
+    for addr in (start..start + len).step_by(PAGE_SIZE) {
+        assert_eq!(
+            unsafe { *(addr as *const u8) },
+            seed.wrapping_add((addr / PAGE_SIZE) as u8)
+        );
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 4KiB pages need a page table page for every 2MiB
+    let small: usize = 0x1000_0000;
+    let before = page_table_frames();
+    assert_eq!(0, mmap(small, LEN, 3));
+    let small_frames = page_table_frames() - before;
+    fill(small, LEN, 1);
+    check(small, LEN, 1);
+    assert_eq!(0, munmap(small, LEN));
+
+    // megapages in the same 1GiB need none
+    let huge: usize = 0x1080_0000;
+    let before = page_table_frames();
+    let rss = rss_pages();
+    assert_eq!(0, mmap(huge, LEN, 3 | MAP_HUGE));
+    let huge_frames = page_table_frames() - before;
+    assert!(small_frames >= LEN / HUGE_PAGE_SIZE);
+    assert_eq!(huge_frames, 0);
+    assert_eq!(rss_pages() - rss, LEN / PAGE_SIZE);
+    fill(huge, LEN, 2);
+    check(huge, LEN, 2);
+
+    // the child gets its own copy
+    let pid = fork();
+    if pid == 0 {
+        check(huge, LEN, 2);
+        fill(huge, LEN, 3);
+        check(huge, LEN, 3);
+        return 0;
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    check(huge, LEN, 2);
+
+    // a single page in a megapage splits it
+    let before = page_table_frames();
+    assert_eq!(0, mprotect(huge + PAGE_SIZE * 7, PAGE_SIZE, 1));
+    assert_eq!(page_table_frames() - before, 1);
+    check(huge, LEN, 2);
+    assert_eq!(0, munmap(huge + HUGE_PAGE_SIZE + PAGE_SIZE, PAGE_SIZE));
+    check(huge, PAGE_SIZE * 7, 2);
+    check(huge + HUGE_PAGE_SIZE, PAGE_SIZE, 2);
+    check(
+        huge + HUGE_PAGE_SIZE + PAGE_SIZE * 2,
+        HUGE_PAGE_SIZE - PAGE_SIZE * 2,
+        2,
+    );
+    assert_eq!(0, munmap(huge, HUGE_PAGE_SIZE + PAGE_SIZE));
+    assert_eq!(
+        0,
+        munmap(
+            huge + HUGE_PAGE_SIZE + PAGE_SIZE * 2,
+            HUGE_PAGE_SIZE - PAGE_SIZE * 2
+        )
+    );
+
+    // unaligned ranges fall back to ordinary pages
+    assert_eq!(0, mmap(huge + PAGE_SIZE, HUGE_PAGE_SIZE, 3 | MAP_HUGE));
+    fill(huge + PAGE_SIZE, HUGE_PAGE_SIZE, 4);
+    check(huge + PAGE_SIZE, HUGE_PAGE_SIZE, 4);
+    assert_eq!(0, munmap(huge + PAGE_SIZE, HUGE_PAGE_SIZE));
+    assert_eq!(-1, mmap(huge, HUGE_PAGE_SIZE, 3 | MAP_HUGE | MAP_LAZY));
+    println!("Test mmap huge OK!");
+    0
+}
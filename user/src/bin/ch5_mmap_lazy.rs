@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, MAP_LAZY};
+
+/*
+理想结果：远大于物理内存的惰性映射可以成功，只有被访问的页才分配物理页帧，
+munmap 后这些页帧被回收，反复映射不会耗尽内存。输出 Test mmap lazy OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    // 1 GiB, far more than the physical memory
+    let len: usize = 1 << 30;
+    assert_eq!(0, mmap(start, len, 3 | MAP_LAZY));
+    let first = start as *mut usize;
+    let last = (start + len - PAGE_SIZE) as *mut usize;
+    unsafe {
+        *first = 0xdead;
+        *last = 0xbeef;
+        assert_eq!(*first, 0xdead);
+        assert_eq!(*last, 0xbeef);
+        // an untouched page is read as zero
+        assert_eq!(*((start + PAGE_SIZE) as *const usize), 0);
+    }
+    assert_eq!(0, munmap(start, len));
+    // 32 MiB touched each round, 320 MiB in total
+    let pages: usize = 8192;
+    for _ in 0..10 {
+        assert_eq!(0, mmap(start, len, 3 | MAP_LAZY));
+        for i in 0..pages {
+            unsafe {
+                *((start + i * PAGE_SIZE) as *mut usize) = i;
+            }
+        }
+        for i in 0..pages {
+            unsafe {
+                assert_eq!(*((start + i * PAGE_SIZE) as *const usize), i);
+            }
+        }
+        assert_eq!(0, munmap(start, len));
+    }
+    println!("Test mmap lazy OK!");
+    0
+}
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap};
+
+/*
+理想结果：物理页帧在映射中途耗尽时 mmap 返回 -1，且不残留任何已映射的页，
+之后同一区域可以重新映射。输出 Test mmap oom OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    // 512 MiB, frames run out in the middle of the range
+    let len: usize = 1 << 29;
+    assert_eq!(-1, mmap(start, len, 3));
+    // nothing is left behind at either end of the range
+    assert_eq!(-1, munmap(start, PAGE_SIZE));
+    assert_eq!(-1, munmap(start + len - PAGE_SIZE, PAGE_SIZE));
+    // and the frames are back: 32 MiB can be mapped in the same place
+    let len: usize = 1 << 25;
+    assert_eq!(0, mmap(start, len, 3));
+    for i in (start..start + len).step_by(PAGE_SIZE) {
+        unsafe {
+            *(i as *mut usize) = i;
+        }
+    }
+    for i in (start..start + len).step_by(PAGE_SIZE) {
+        unsafe {
+            assert_eq!(*(i as *const usize), i);
+        }
+    }
+    assert_eq!(0, munmap(start, len));
+    println!("Test mmap oom OK!");
+    0
+}
@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, mprotect, mremap, munmap};
+
+/*
+理想结果：start + len 溢出或超出用户地址空间（Sv39 低半部分）的请求被干净地拒绝，返回 -1，
+紧贴用户地址空间上界之下的合法请求不受影响。输出 Test mmap overflow OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const USER_SPACE_END: usize = 0x40_0000_0000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let top = usize::MAX - (PAGE_SIZE - 1);
+    assert_eq!(mmap(top, PAGE_SIZE * 2, 3), -1);
+    assert_eq!(munmap(top, PAGE_SIZE * 2), -1);
+    assert_eq!(mprotect(top, PAGE_SIZE * 2, 1), -1);
+    // len rounds up past usize::MAX
+    let start: usize = 0x10000000;
+    assert_eq!(mmap(start, usize::MAX, 3), -1);
+    assert_eq!(mmap(start, usize::MAX - start, 3), -1);
+    // no wrap, but beyond user space
+    assert_eq!(mmap(USER_SPACE_END, PAGE_SIZE, 3), -1);
+    assert_eq!(mmap(USER_SPACE_END - PAGE_SIZE * 4, PAGE_SIZE * 8, 3), -1);
+    assert_eq!(mmap(start, USER_SPACE_END, 3), -1);
+    // still fine below it
+    assert_eq!(mmap(start, PAGE_SIZE, 3), 0);
+    assert_eq!(mremap(start, PAGE_SIZE, usize::MAX), -1);
+    assert_eq!(mremap(start, PAGE_SIZE, USER_SPACE_END), -1);
+    unsafe {
+        (start as *mut usize).write_volatile(42);
+        assert_eq!((start as *const usize).read_volatile(), 42);
+    }
+    assert_eq!(munmap(start, PAGE_SIZE), 0);
+    println!("Test mmap overflow OK!");
+    0
+}
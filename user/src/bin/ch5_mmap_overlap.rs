@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::mmap;
+
+/*
+理想结果：与已有映射重叠的 mmap 全部返回 -1，且原映射保持可读，
+失败的 mmap 不会留下任何新映射。输出 Test mmap overlap OK!
+*/
+
+#[no_mangle]
+fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len: usize = 4096 * 3;
+    let prot: usize = 3;
+    assert_eq!(0, mmap(start, len, prot));
+    for i in (start..(start + len)).step_by(4096) {
+        let addr: *mut u8 = i as *mut u8;
+        unsafe {
+            *addr = i as u8 + 1;
+        }
+    }
+    // only the last page collides
+    assert_eq!(mmap(start - 4096, 4096 * 2, prot), -1);
+    // only the first page collides
+    assert_eq!(mmap(start + 4096 * 2, 4096 * 2, prot), -1);
+    // entirely inside the existing area
+    assert_eq!(mmap(start + 4096, 4096, prot), -1);
+    for i in (start..(start + len)).step_by(4096) {
+        let addr: *mut u8 = i as *mut u8;
+        unsafe {
+            assert_eq!(*addr, i as u8 + 1);
+        }
+    }
+    // the failed requests left their free pages unmapped
+    assert_eq!(mmap(start - 4096, 4096, prot), 0);
+    assert_eq!(mmap(start + len, 4096, prot), 0);
+    println!("Test mmap overlap OK!");
+    0
+}
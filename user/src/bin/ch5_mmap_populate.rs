@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, query_pte, PteInfo, MAP_LAZY, MAP_POPULATE};
+
+/*
+理想结果：带 MAP_POPULATE 的映射建立后所有页的页表项都有效；同样大小的惰性映射只有访问过的页有效；
+MAP_POPULATE 与 MAP_LAZY 同时给出时映射失败。输出 Test mmap populate OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const PAGES: usize = 16;
+
+/// Number of pages of `[start, start + PAGES)` with a valid pte
+fn present_pages(start: usize) -> usize {
+    (0..PAGES)
+        .filter(|i| {
+            let mut info = PteInfo::default();
+            assert_eq!(query_pte(start + i * PAGE_SIZE, &mut info), 0);
+            info.flags_v
+        })
+        .count()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len = PAGES * PAGE_SIZE;
+    assert_eq!(mmap(start, len, 3 | MAP_POPULATE | MAP_LAZY), -1);
+    // populated: every page is there before any access
+    assert_eq!(0, mmap(start, len, 3 | MAP_POPULATE));
+    assert_eq!(present_pages(start), PAGES);
+    assert_eq!(0, munmap(start, len));
+    // lazy: only the pages touched so far
+    assert_eq!(0, mmap(start, len, 3 | MAP_LAZY));
+    assert_eq!(present_pages(start), 0);
+    for i in [0, 5, PAGES - 1] {
+        unsafe {
+            ((start + i * PAGE_SIZE) as *mut u8).write_volatile(1);
+        }
+    }
+    assert_eq!(present_pages(start), 3);
+    assert_eq!(0, munmap(start, len));
+    println!("Test mmap populate OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, waitpid, MAP_LAZY, MAP_SHARED};
+
+/*
+理想结果：fork 前以共享方式映射的页在父子进程间共享，子进程写入的值父进程可以读到；
+普通映射仍然互不影响。输出 Test mmap shared OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let shared: usize = 0x10000000;
+    let private: usize = 0x10001000;
+    assert_eq!(0, mmap(shared, 4096, 3 | MAP_SHARED));
+    assert_eq!(0, mmap(private, 4096, 3));
+    // lazy pages can not be shared
+    assert_eq!(-1, mmap(0x10002000, 4096, 3 | MAP_LAZY | MAP_SHARED));
+    unsafe {
+        *(shared as *mut usize) = 1;
+        *(private as *mut usize) = 1;
+    }
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            assert_eq!(*(shared as *const usize), 1);
+            *(shared as *mut usize) = 0xc0de;
+            *(private as *mut usize) = 0xc0de;
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    unsafe {
+        assert_eq!(*(shared as *const usize), 0xc0de);
+        assert_eq!(*(private as *const usize), 1);
+    }
+    println!("Test mmap shared OK!");
+    0
+}
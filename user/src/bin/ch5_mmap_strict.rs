@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mmap_strict, munmap, waitpid};
+
+/*
+理想结果：默认模式下 len = 4097 向上取整为两页，mmap 和 munmap 均成功；严格模式下两者都返回 -1，
+整页的 len 照常工作，fork 出的子进程继承该模式。输出 Test mmap strict OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len = PAGE_SIZE + 1;
+    // lenient: two pages
+    assert_eq!(mmap(start, len, 3), 0);
+    unsafe {
+        ((start + PAGE_SIZE * 2 - 1) as *mut u8).write_volatile(1);
+    }
+    assert_eq!(mmap(start + PAGE_SIZE, PAGE_SIZE, 3), -1);
+    assert_eq!(munmap(start, len), 0);
+
+    assert!(!mmap_strict(true));
+    assert_eq!(mmap(start, len, 3), -1);
+    assert_eq!(mmap(start, PAGE_SIZE * 2, 3), 0);
+    assert_eq!(munmap(start, len), -1);
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(munmap(start, len), -1);
+        assert!(mmap_strict(true));
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(munmap(start, PAGE_SIZE * 2), 0);
+
+    assert!(mmap_strict(false));
+    assert_eq!(mmap(start, len, 3), 0);
+    assert_eq!(munmap(start, len), 0);
+    println!("Test mmap strict OK!");
+    0
+}
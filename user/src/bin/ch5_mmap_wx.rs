@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{mmap, mprotect, munmap, MAP_LAZY};
+
+/*
+理想结果：同时可写和可执行的映射被拒绝，mmap 和 mprotect 均返回 -1；
+只读可执行的代码页和可读写的数据页照常工作。输出 Test mmap W^X OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+/// `jalr x0, 0(ra)`, that is `ret`
+const RET: u32 = 0x0000_8067;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(-1, mmap(start, PAGE_SIZE, 6));
+    assert_eq!(-1, mmap(start, PAGE_SIZE, 7));
+    assert_eq!(-1, mmap(start, PAGE_SIZE, 7 | MAP_LAZY));
+    // data: R|W
+    assert_eq!(0, mmap(start, PAGE_SIZE, 3));
+    unsafe {
+        (start as *mut u32).write_volatile(RET);
+        assert_eq!((start as *const u32).read_volatile(), RET);
+    }
+    assert_eq!(-1, mprotect(start, PAGE_SIZE, 6));
+    assert_eq!(-1, mprotect(start, PAGE_SIZE, 7));
+    // code: R|X, the word written above runs
+    assert_eq!(0, mprotect(start, PAGE_SIZE, 5));
+    unsafe {
+        asm!("fence.i");
+        let code: fn() = core::mem::transmute(start);
+        code();
+    }
+    assert_eq!(0, munmap(start, PAGE_SIZE));
+    assert_eq!(0, mmap(start, PAGE_SIZE, 5));
+    assert_eq!(0, munmap(start, PAGE_SIZE));
+    println!("Test mmap W^X OK!");
+    0
+}
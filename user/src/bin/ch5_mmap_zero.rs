@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, translate, MAP_LAZY};
+
+/*
+理想结果：新 mmap 的页全为 0；写入数据并 munmap 后重新 mmap，
+落在同一个物理页上（通过 translate 确认）时仍然全为 0，lazy 页同理。
+输出 Test mmap zero OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+fn assert_zero(start: usize) {
+    let page = unsafe { core::slice::from_raw_parts(start as *const u8, PAGE_SIZE) };
+    assert!(page.iter().all(|b| *b == 0));
+}
+
+fn dirty(start: usize) {
+    let page = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, PAGE_SIZE) };
+    page.fill(0xa5);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    for prot in [3, 3 | MAP_LAZY] {
+        assert_eq!(0, mmap(start, PAGE_SIZE, prot));
+        assert_zero(start);
+        dirty(start);
+        let pa = translate(start);
+        assert!(pa > 0);
+        assert_eq!(0, munmap(start, PAGE_SIZE));
+        assert_eq!(-1, translate(start));
+        // the frame just freed is the first to be allocated again
+        assert_eq!(0, mmap(start, PAGE_SIZE, prot));
+        assert_zero(start);
+        assert_eq!(translate(start), pa);
+        assert_eq!(0, munmap(start, PAGE_SIZE));
+    }
+    println!("Test mmap zero OK!");
+    0
+}
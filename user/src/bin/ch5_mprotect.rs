@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, waitpid};
+
+/*
+理想结果：把读写映射改为只读后仍可读取，写入则导致子进程被杀死；
+范围未映射或 port 非法时返回 -1。输出 Test mprotect OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let len: usize = 4096;
+    assert_eq!(0, mmap(start, len * 2, 3));
+    for i in start..(start + len * 2) {
+        let addr: *mut u8 = i as *mut u8;
+        unsafe {
+            *addr = i as u8;
+        }
+    }
+    // only the first page becomes read-only
+    assert_eq!(0, mprotect(start, len, 1));
+    for i in start..(start + len) {
+        let addr: *const u8 = i as *const u8;
+        unsafe {
+            assert_eq!(*addr, i as u8);
+        }
+    }
+    unsafe {
+        *((start + len) as *mut u8) = 0xab;
+        assert_eq!(*((start + len) as *const u8), 0xab);
+    }
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            *(start as *mut u8) = 0;
+        }
+        println!("Should cause error, Test mprotect fail!");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+    // range partly unmapped, illegal port
+    assert_eq!(-1, mprotect(start, len * 3, 1));
+    assert_eq!(-1, mprotect(start, len, 0));
+    assert_eq!(-1, mprotect(start, len, 8));
+    println!("Test mprotect OK!");
+    0
+}
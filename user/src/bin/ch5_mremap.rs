@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, mremap, munmap};
+
+/*
+理想结果：mremap 原地扩大映射后原有内容保留、新增的页全为 0；紧随其后的页已被占用时返回 -1；
+缩小映射后尾部的页被解除映射。输出 Test mremap OK!
+*/
+
+const PAGE: usize = 4096;
+const MARKER: usize = 0x5a5a_1234;
+
+#[no_mangle]
+fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, PAGE, 3));
+    unsafe {
+        *(start as *mut usize) = MARKER;
+    }
+    assert_eq!(mremap(start, PAGE, PAGE * 3), start as isize);
+    unsafe {
+        assert_eq!(*(start as *const usize), MARKER);
+        for i in 1..3 {
+            let page = core::slice::from_raw_parts((start + i * PAGE) as *const u8, PAGE);
+            assert!(page.iter().all(|&b| b == 0));
+        }
+        *((start + PAGE * 2) as *mut usize) = MARKER + 2;
+    }
+    // the page right after the grown area is taken
+    let other = start + PAGE * 5;
+    assert_eq!(0, mmap(other, PAGE, 3));
+    assert_eq!(mremap(start, PAGE * 3, PAGE * 6), -1);
+    assert_eq!(munmap(start + PAGE * 3, PAGE), -1);
+    // the old range must be mapped as a whole
+    assert_eq!(mremap(start, PAGE * 4, PAGE * 5), -1);
+    assert_eq!(mremap(start + 1, PAGE, PAGE * 2), -1);
+    // there is still room for two more pages
+    assert_eq!(mremap(start, PAGE * 3, PAGE * 5), start as isize);
+    unsafe {
+        assert_eq!(*((start + PAGE * 2) as *const usize), MARKER + 2);
+        *((start + PAGE * 4) as *mut usize) = MARKER + 4;
+    }
+    // shrink back to one page, the tail is gone
+    assert_eq!(mremap(start, PAGE * 5, PAGE), start as isize);
+    assert_eq!(munmap(start + PAGE, PAGE), -1);
+    unsafe {
+        assert_eq!(*(start as *const usize), MARKER);
+    }
+    assert_eq!(munmap(start, PAGE), 0);
+    assert_eq!(munmap(other, PAGE), 0);
+    println!("Test mremap OK!");
+    0
+}
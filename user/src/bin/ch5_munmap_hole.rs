@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap};
+
+/*
+理想结果：范围内存在未映射页的 munmap 返回 -1，且不会解除其中已映射页的映射；
+完全映射的范围（可以只是某个区域的一部分）可以正常解除。输出 Test munmap hole OK!
+*/
+
+const PAGE: usize = 4096;
+
+fn check(start: usize, pages: usize) {
+    for i in 0..pages {
+        let addr = (start + i * PAGE) as *const u8;
+        unsafe {
+            assert_eq!(*addr, (i + 1) as u8);
+        }
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let start: usize = 0x10000000;
+    // [start, start + 2) and [start + 3, start + 5), a hole at start + 2
+    assert_eq!(0, mmap(start, PAGE * 2, 3));
+    assert_eq!(0, mmap(start + PAGE * 3, PAGE * 2, 3));
+    for i in [0, 1, 3, 4] {
+        let addr = (start + i * PAGE) as *mut u8;
+        unsafe {
+            *addr = (i + 1) as u8;
+        }
+    }
+    // the hole is in the middle of the range
+    assert_eq!(munmap(start, PAGE * 5), -1);
+    check(start, 2);
+    // the range extends one page past a valid area
+    assert_eq!(munmap(start + PAGE * 3, PAGE * 3), -1);
+    assert_eq!(munmap(start - PAGE, PAGE * 3), -1);
+    check(start, 2);
+    unsafe {
+        assert_eq!(*((start + PAGE * 3) as *const u8), 4);
+        assert_eq!(*((start + PAGE * 4) as *const u8), 5);
+    }
+    // unmapping a part of an area keeps the rest
+    assert_eq!(munmap(start + PAGE * 4, PAGE), 0);
+    unsafe {
+        assert_eq!(*((start + PAGE * 3) as *const u8), 4);
+    }
+    assert_eq!(munmap(start + PAGE * 4, PAGE), -1);
+    assert_eq!(munmap(start, PAGE * 2), 0);
+    assert_eq!(munmap(start + PAGE * 3, PAGE), 0);
+    println!("Test munmap hole OK!");
+    0
+}
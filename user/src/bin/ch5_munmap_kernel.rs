@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getpid, mmap, mprotect, munmap, translate};
+
+/*
+理想结果：munmap / mprotect trampoline 与 trap context 这些内核页返回 -1，
+之后系统调用照常工作，mmap 也不能覆盖它们；长度为 0 或越过地址空间末尾的范围同样返回 -1。
+输出 Test munmap kernel OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
+const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    for page in [TRAP_CONTEXT, TRAMPOLINE] {
+        assert_eq!(-1, munmap(page, PAGE_SIZE));
+        assert_eq!(-1, mprotect(page, PAGE_SIZE, 1));
+        assert_eq!(-1, mmap(page, PAGE_SIZE, 3));
+        // not user pages
+        assert_eq!(-1, translate(page));
+    }
+    assert_eq!(-1, munmap(TRAP_CONTEXT, PAGE_SIZE * 2));
+    assert_eq!(-1, mmap(0x10000000, 0, 3));
+    assert_eq!(-1, munmap(0x10000000, 0));
+    assert_eq!(-1, mmap(0x10000000, usize::MAX, 3));
+    // the trap context survived, or this would not return
+    assert_eq!(getpid(), pid);
+    println!("Test munmap kernel OK!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, munmap, waitpid};
+
+/*
+理想结果：munmap 之后访问刚才还在使用的页、mprotect 改为只读之后写入该页都会触发缺页并被杀死，
+无论解除的范围是几页还是超过按页刷新上限的大范围。输出 Test munmap tlb OK!
+*/
+
+const PAGE: usize = 4096;
+
+/// Touch every page of the range in a child, which has to die of a page fault.
+fn expect_fault(start: usize, pages: usize) {
+    let pid = fork();
+    if pid == 0 {
+        for i in 0..pages {
+            unsafe {
+                ((start + i * PAGE) as *mut u8).write_volatile(1);
+            }
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+}
+
+/// Map, fill and read back `pages` pages, so their translations are cached.
+fn map_and_use(start: usize, pages: usize) {
+    assert_eq!(mmap(start, pages * PAGE, 3), 0);
+    for i in 0..pages {
+        let addr = (start + i * PAGE) as *mut usize;
+        unsafe {
+            addr.write_volatile(i);
+            assert_eq!(addr.read_volatile(), i);
+        }
+    }
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let start: usize = 0x10000000;
+    // a single page, flushed on its own
+    map_and_use(start, 1);
+    assert_eq!(munmap(start, PAGE), 0);
+    expect_fault(start, 1);
+    assert_eq!(munmap(start, PAGE), -1);
+    // more pages than are flushed one by one
+    map_and_use(start, 100);
+    assert_eq!(munmap(start, 100 * PAGE), 0);
+    expect_fault(start + 99 * PAGE, 1);
+    expect_fault(start, 1);
+    // a page made read-only loses its writable translation
+    map_and_use(start, 2);
+    assert_eq!(mprotect(start + PAGE, PAGE, 1), 0);
+    unsafe {
+        assert_eq!(((start + PAGE) as *const usize).read_volatile(), 1);
+    }
+    expect_fault(start + PAGE, 1);
+    assert_eq!(munmap(start, 2 * PAGE), 0);
+    println!("Test munmap tlb OK!");
+    0
+}
@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    exit, mutex_blocking_create, mutex_destroy, mutex_lock, mutex_unlock, thread_create, yield_,
+};
+
+/*
+理想结果：两个线程在同一把阻塞互斥锁下先读后写共享计数器，中途让出 CPU 也不丢失更新；
+未持有时解锁、重复加锁和使用已销毁的锁都返回 -1。输出 Test mutex OK!
+*/
+
+const ROUNDS: usize = 100;
+
+static mut COUNTER: usize = 0;
+static MUTEX_ID: AtomicUsize = AtomicUsize::new(0);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+
+fn worker(_arg: usize) -> ! {
+    let id = MUTEX_ID.load(Ordering::SeqCst);
+    for i in 0..ROUNDS {
+        assert_eq!(mutex_lock(id), 0);
+        let counter = unsafe { core::ptr::read_volatile(&COUNTER) };
+        // let the other thread run while the mutex is held
+        if i % 10 == 0 {
+            yield_();
+        }
+        unsafe { core::ptr::write_volatile(&mut COUNTER, counter + 1) };
+        assert_eq!(mutex_unlock(id), 0);
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let id = mutex_blocking_create();
+    assert!(id >= 0);
+    let id = id as usize;
+    // only the holder may unlock, and only once
+    assert_eq!(mutex_unlock(id), -1);
+    assert_eq!(mutex_lock(id), 0);
+    assert_eq!(mutex_lock(id), -1);
+    assert_eq!(mutex_destroy(id), -1);
+    assert_eq!(mutex_unlock(id), 0);
+    MUTEX_ID.store(id, Ordering::SeqCst);
+    assert!(thread_create(worker as usize, 0) > 0);
+    assert!(thread_create(worker as usize, 0) > 0);
+    while DONE.load(Ordering::SeqCst) < 2 {
+        yield_();
+    }
+    assert_eq!(unsafe { core::ptr::read_volatile(&COUNTER) }, 2 * ROUNDS);
+    // a destroyed id is gone until the next create reuses it
+    assert_eq!(mutex_destroy(id), 0);
+    assert_eq!(mutex_lock(id), -1);
+    assert_eq!(mutex_unlock(id), -1);
+    assert_eq!(mutex_blocking_create(), id as isize);
+    println!("Test mutex OK!");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    clock_gettime, exit, fork, getpid, kill, nanosleep, sigaction, sigreturn, sleep, waitpid,
+    TimeSpec, CLOCK_MONOTONIC, EINTR, SIGUSR1,
+};
+
+/*
+理想结果：不被打断的 nanosleep 睡满 150ms 后返回 0，剩余时间为 0；
+nsec 不小于 10^9 或 sec 大到无法以微秒计的请求返回 -1；睡眠 10s 的子进程被 SIGUSR1 提前唤醒，
+处理函数运行后 nanosleep 返回 -EINTR，剩余时间大于 0 且不超过 10s。输出 Test nanosleep OK!
+*/
+
+const SLEEP_NS: usize = 150_000_000;
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn handler(_signum: usize) {
+    HANDLED.store(1, Ordering::SeqCst);
+    sigreturn();
+}
+
+fn now_ns() -> usize {
+    let mut ts = TimeSpec::new();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut ts), 0);
+    ts.sec * 1_000_000_000 + ts.nsec
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let req = TimeSpec {
+        sec: 0,
+        nsec: SLEEP_NS,
+    };
+    let mut rem = TimeSpec { sec: 1, nsec: 1 };
+    let start = now_ns();
+    assert_eq!(nanosleep(&req, Some(&mut rem)), 0);
+    let slept = now_ns() - start;
+    println!("slept {}ns", slept);
+    assert!(slept >= SLEEP_NS);
+    // a timer tick is 10ms, the wakeup may be that late and some more
+    assert!(slept < SLEEP_NS + 50_000_000);
+    assert_eq!((rem.sec, rem.nsec), (0, 0));
+    assert_eq!(nanosleep(&req, None), 0);
+
+    let bad = TimeSpec {
+        sec: 0,
+        nsec: 1_000_000_000,
+    };
+    assert_eq!(nanosleep(&bad, None), -1);
+    let too_long = TimeSpec {
+        sec: usize::MAX / 1000,
+        nsec: 0,
+    };
+    assert_eq!(nanosleep(&too_long, None), -1);
+
+    assert_eq!(sigaction(SIGUSR1, handler as usize, None), 0);
+    let parent = getpid() as usize;
+    let pid = fork();
+    if pid == 0 {
+        // let the parent fall asleep first
+        sleep(50);
+        kill(parent, SIGUSR1);
+        exit(0);
+    }
+    let long = TimeSpec { sec: 10, nsec: 0 };
+    let start = now_ns();
+    assert_eq!(nanosleep(&long, Some(&mut rem)), -EINTR);
+    assert!(now_ns() - start < 5_000_000_000);
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1);
+    println!("{}s {}ns left", rem.sec, rem.nsec);
+    assert!(rem.sec > 0 && rem.sec < 10);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test nanosleep OK!");
+    0
+}
@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{brk, exec, exit, fork, mmap, munmap, waitpid};
+
+/*
+理想结果：物理页帧耗尽时 fork、exec、brk、mmap 返回 -1 而内核不会 panic，
+exec 失败后原程序继续运行；释放内存后 fork 恢复正常。输出 Test oom OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+
+/// Map frames until they run out, return the end of the mapping
+fn exhaust() -> usize {
+    let mut end = START;
+    let mut len = 1 << 24;
+    while len >= PAGE_SIZE {
+        if mmap(end, len, 3) == 0 {
+            end += len;
+        } else {
+            len /= 2;
+        }
+    }
+    end
+}
+
+fn fork_and_wait() {
+    let pid = fork();
+    assert!(pid >= 0);
+    if pid == 0 {
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let end = exhaust();
+    assert!(end > START);
+    assert_eq!(-1, fork());
+    assert_eq!(-1, exec("ch5_exec_args_echo\0", &[core::ptr::null()]));
+    let heap = brk(0);
+    assert!(heap > 0);
+    assert_eq!(-1, brk(heap as usize + PAGE_SIZE));
+    assert_eq!(-1, mmap(end, PAGE_SIZE, 3));
+    // the failed calls gave back what they took
+    assert_eq!(0, munmap(START, end - START));
+    fork_and_wait();
+    // the kernel page table may keep a frame for the kernel stack of the child
+    let again = exhaust();
+    assert!(again + 4 * PAGE_SIZE >= end);
+    assert_eq!(0, munmap(START, again - START));
+    fork_and_wait();
+    println!("Test oom OK!");
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getppid, sleep_blocking, waitpid};
+
+/*
+理想结果：父进程先于子进程退出后，子进程的父进程变为 initproc（pid 为 0），
+之后由 initproc 回收；父进程未回收的僵尸子进程随父进程一同释放。
+输出 Test orphan OK!，随后 initproc 输出回收该孤儿进程的信息。
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let parent = fork();
+    if parent == 0 {
+        // a zombie never reaped by its parent
+        if fork() == 0 {
+            exit(1);
+        }
+        let orphan = fork();
+        if orphan == 0 {
+            while getppid() != 0 {
+                sleep_blocking(1);
+            }
+            println!("Test orphan OK!");
+            exit(0);
+        }
+        sleep_blocking(10);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(parent as usize, &mut exit_code), parent);
+    assert_eq!(exit_code, 0);
+    // leave the orphan some time to notice
+    sleep_blocking(100);
+    0
+}
@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, pipe, read, waitpid, write};
+
+/*
+理想结果：父进程通过管道写入的数据被子进程按顺序完整读出，
+写入远超管道容量的数据时写者会阻塞等待读者。输出 Test pipe OK!
+*/
+
+static STR: &str = "Hello, pipe!";
+/// far more than the pipe holds at once
+const LEN: usize = 5000;
+
+fn byte(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    assert_ne!(pipe_fd[0], pipe_fd[1]);
+    // the ends only go one way
+    assert_eq!(write(pipe_fd[0], STR.as_bytes()), -1);
+    assert_eq!(read(pipe_fd[1], &mut [0u8; 1]), -1);
+    let pid = fork();
+    if pid == 0 {
+        let mut buffer = [0u8; 32];
+        let len_read = read(pipe_fd[0], &mut buffer[..STR.len()]) as usize;
+        assert_eq!(core::str::from_utf8(&buffer[..len_read]).unwrap(), STR);
+        let mut total = 0;
+        while total < LEN {
+            let n = read(pipe_fd[0], &mut buffer);
+            assert!(n > 0);
+            for (i, b) in buffer[..n as usize].iter().enumerate() {
+                assert_eq!(*b, byte(total + i));
+            }
+            total += n as usize;
+        }
+        assert_eq!(total, LEN);
+        exit(0);
+    }
+    assert_eq!(write(pipe_fd[1], STR.as_bytes()), STR.len() as isize);
+    let mut data = [0u8; LEN];
+    for (i, b) in data.iter_mut().enumerate() {
+        *b = byte(i);
+    }
+    assert_eq!(write(pipe_fd[1], &data), LEN as isize);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test pipe OK!");
+    0
+}
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, pipe, read, sleep, waitpid, write};
+
+/*
+理想结果：子进程关闭自己的写端后阻塞读取，父进程写入数据并关闭写端后，
+子进程读完管道中剩余的数据，之后的读取返回 0（EOF）。
+关闭无效的 fd 返回 -1。输出 Test pipe eof OK!
+*/
+
+/// more than the pipe holds at once
+const LEN: usize = 50;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        // the parent keeps the only other write end
+        assert_eq!(close(pipe_fd[1]), 0);
+        let mut buffer = [0u8; 16];
+        let mut total = 0;
+        loop {
+            let n = read(pipe_fd[0], &mut buffer);
+            assert!(n >= 0);
+            if n == 0 {
+                break;
+            }
+            for b in &buffer[..n as usize] {
+                assert_eq!(*b, b'x');
+            }
+            total += n as usize;
+        }
+        // EOF stays EOF
+        assert_eq!(read(pipe_fd[0], &mut buffer), 0);
+        exit(total as i32);
+    }
+    assert_eq!(close(pipe_fd[0]), 0);
+    // let the child block on the empty pipe first
+    sleep(10);
+    assert_eq!(write(pipe_fd[1], &[b'x'; LEN]), LEN as isize);
+    assert_eq!(close(pipe_fd[1]), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, LEN as i32);
+    assert_eq!(close(pipe_fd[1]), -1);
+    assert_eq!(close(1000), -1);
+    println!("Test pipe eof OK!");
+    0
+}
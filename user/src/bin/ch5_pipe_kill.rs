@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, kill, pipe, read, waitpid, write, yield_, SIGKILL};
+
+/*
+理想结果：阻塞在空管道上的读者与阻塞在满管道上的写者都能被 SIGKILL 杀死，
+waitpid 得到的退出码为 -9。输出 Test pipe kill OK!
+*/
+
+/// more than the pipe holds at once
+const LEN: usize = 50;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the parent keeps every end open, nobody writes to the first pipe or
+    // reads from the second one
+    let mut empty_fd = [0usize; 2];
+    assert_eq!(pipe(&mut empty_fd), 0);
+    let mut full_fd = [0usize; 2];
+    assert_eq!(pipe(&mut full_fd), 0);
+    let reader = fork();
+    if reader == 0 {
+        let mut buffer = [0u8; 16];
+        read(empty_fd[0], &mut buffer);
+        println!("Should be killed, Test pipe kill fail!");
+        exit(0);
+    }
+    let writer = fork();
+    if writer == 0 {
+        write(full_fd[1], &[b'x'; LEN]);
+        println!("Should be killed, Test pipe kill fail!");
+        exit(0);
+    }
+    // let both block
+    for _ in 0..10 {
+        yield_();
+    }
+    for pid in [reader, writer] {
+        assert_eq!(kill(pid as usize, SIGKILL), 0);
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, -SIGKILL);
+    }
+    println!("Test pipe kill OK!");
+    0
+}
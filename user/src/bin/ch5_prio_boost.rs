@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, sched_deterministic, set_priority, wait_any, yield_, MAP_SHARED,
+};
+
+/*
+理想结果：关闭时钟抢占后，优先级为 2 的进程 A 与优先级为 16 的进程 B 交替 yield，
+A 第一次运行时把自己的优先级提到 100，紧接着的调度中 A 就得到大部分运行机会。
+输出 Test prio boost OK!
+*/
+
+/// Times each child logs itself
+const ROUNDS: usize = 16;
+const LOG_LEN: usize = ROUNDS * 2;
+/// Entries of the log right after the boost that are looked at
+const WINDOW: usize = 14;
+
+const A: usize = 0;
+const B: usize = 1;
+
+/// Shared with the children, which log themselves when they run
+#[repr(C)]
+struct Shared {
+    count: usize,
+    log: [usize; LOG_LEN],
+}
+
+fn child(shared: &mut Shared, me: usize, prio: isize) -> ! {
+    set_priority(prio);
+    // from now on the strides alone tell who runs
+    yield_();
+    for round in 0..ROUNDS {
+        let count = unsafe { core::ptr::read_volatile(&shared.count) };
+        shared.log[count] = me;
+        unsafe { core::ptr::write_volatile(&mut shared.count, count + 1) };
+        if me == A && round == 0 {
+            assert_eq!(set_priority(100), 100);
+        }
+        yield_();
+    }
+    exit(0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, 4096, 3 | MAP_SHARED));
+    let shared = unsafe { &mut *(start as *mut Shared) };
+    shared.count = 0;
+    let was_deterministic = sched_deterministic(true);
+    if fork() == 0 {
+        child(shared, A, 2);
+    }
+    if fork() == 0 {
+        child(shared, B, 16);
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..2 {
+        assert!(wait_any(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    sched_deterministic(was_deterministic);
+    println!("order: {:?}", shared.log);
+    let boost = shared.log.iter().position(|&who| who == A).unwrap();
+    // at priority 2 against 16, A first runs after B has several times
+    assert!(boost >= 4);
+    let after = &shared.log[boost + 1..boost + 1 + WINDOW];
+    let a_runs = after.iter().filter(|&&who| who == A).count();
+    // ideally 100 to 16, without the boost A would run once or twice
+    assert!(a_runs >= 10);
+    assert_eq!(0, munmap(start, 4096));
+    println!("Test prio boost OK!");
+    0
+}
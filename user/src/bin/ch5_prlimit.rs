@@ -0,0 +1,89 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, prlimit, waitpid, Rlimit, MAP_LAZY, RLIMIT_AS, RLIMIT_NPROC,
+    RLIM_INFINITY,
+};
+
+/*
+理想结果：默认没有限制。把 RLIMIT_AS 设为 16MiB 后映射 32MiB 返回 -1，小的映射仍然成功；
+提高软限制后映射 32MiB 成功；软限制不能超过硬限制，硬限制不能提高。
+RLIMIT_NPROC 为 1 时第二次 fork 失败，子进程继承该限制，回收子进程后可以再 fork。
+输出 Test prlimit OK!
+*/
+
+const MB: usize = 1 << 20;
+const START: usize = 0x2000_0000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut old = Rlimit::default();
+    assert_eq!(prlimit(RLIMIT_AS, None, Some(&mut old)), 0);
+    assert_eq!((old.cur, old.max), (RLIM_INFINITY, RLIM_INFINITY));
+    assert_eq!(prlimit(7, None, Some(&mut old)), -1);
+
+    let low = Rlimit {
+        cur: 16 * MB,
+        max: RLIM_INFINITY,
+    };
+    assert_eq!(prlimit(RLIMIT_AS, Some(&low), None), 0);
+    assert_eq!(mmap(START, 32 * MB, 3 | MAP_LAZY), -1);
+    assert_eq!(mmap(START, 64 * 1024, 3 | MAP_LAZY), 0);
+    assert_eq!(munmap(START, 64 * 1024), 0);
+    let high = Rlimit {
+        cur: RLIM_INFINITY,
+        max: RLIM_INFINITY,
+    };
+    assert_eq!(prlimit(RLIMIT_AS, Some(&high), Some(&mut old)), 0);
+    assert_eq!(old.cur, 16 * MB);
+    assert_eq!(mmap(START, 32 * MB, 3 | MAP_LAZY), 0);
+    assert_eq!(munmap(START, 32 * MB), 0);
+
+    // the soft limit stays below the hard one, and that is never raised
+    let inverted = Rlimit {
+        cur: 2 * MB,
+        max: MB,
+    };
+    assert_eq!(prlimit(RLIMIT_AS, Some(&inverted), None), -1);
+    let capped = Rlimit {
+        cur: 64 * MB,
+        max: 64 * MB,
+    };
+    assert_eq!(prlimit(RLIMIT_AS, Some(&capped), None), 0);
+    assert_eq!(prlimit(RLIMIT_AS, Some(&high), None), -1);
+    assert_eq!(prlimit(RLIMIT_AS, None, Some(&mut old)), 0);
+    assert_eq!((old.cur, old.max), (64 * MB, 64 * MB));
+
+    let one = Rlimit {
+        cur: 1,
+        max: RLIM_INFINITY,
+    };
+    assert_eq!(prlimit(RLIMIT_NPROC, Some(&one), None), 0);
+    let pid = fork();
+    if pid == 0 {
+        let mut inherited = Rlimit::default();
+        assert_eq!(prlimit(RLIMIT_NPROC, None, Some(&mut inherited)), 0);
+        assert_eq!(inherited.cur, 1);
+        assert_eq!(prlimit(RLIMIT_AS, None, Some(&mut inherited)), 0);
+        assert_eq!(inherited.max, 64 * MB);
+        exit(0);
+    }
+    assert!(pid > 0);
+    // the child counts until it is reaped, even after it exits
+    assert_eq!(fork(), -1);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    println!("Test prlimit OK!");
+    0
+}
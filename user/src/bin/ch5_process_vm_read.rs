@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, getpid, getppid, pipe, process_vm_read, read, syscall6, waitpid, write,
+    EFAULT, SYSCALL_PROCESS_VM_READ,
+};
+
+/*
+理想结果：父进程从 fork 出的子进程中读出子进程写入的全局变量，读取范围在两边都可以跨页；
+目标地址未映射、目标不是后代进程或已经退出时返回 -1，本地缓冲区不可写时返回 -14，
+父进程自己的变量不受影响。输出 Test process vm read OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const MAGIC: usize = 0x5eed_cafe;
+/// bytes read across a page boundary
+const LEN: usize = 100;
+
+static mut GLOBAL: usize = 0;
+static mut PATTERN: [u8; 2 * PAGE_SIZE] = [0; 2 * PAGE_SIZE];
+static mut LOCAL: [u8; 2 * PAGE_SIZE] = [0; 2 * PAGE_SIZE];
+
+/// The index of `array` that is `LEN / 2` bytes before a page boundary
+fn straddling(array: &[u8]) -> usize {
+    let addr = array.as_ptr() as usize;
+    (PAGE_SIZE - LEN / 2 - addr % PAGE_SIZE) % PAGE_SIZE
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut ready = [0usize; 2];
+    let mut done = [0usize; 2];
+    assert_eq!(pipe(&mut ready), 0);
+    assert_eq!(pipe(&mut done), 0);
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            GLOBAL = MAGIC;
+            let pattern = &mut *core::ptr::addr_of_mut!(PATTERN);
+            for (i, byte) in pattern.iter_mut().enumerate() {
+                *byte = (i % 251) as u8;
+            }
+        }
+        assert_eq!(write(ready[1], &[1]), 1);
+        // stay alive until the parent has looked
+        assert_eq!(read(done[0], &mut [0u8]), 1);
+        exit(0);
+    }
+    assert_eq!(read(ready[0], &mut [0u8]), 1);
+    let global = unsafe { core::ptr::addr_of!(GLOBAL) as usize };
+    let mut value = [0u8; core::mem::size_of::<usize>()];
+    assert_eq!(
+        process_vm_read(pid as usize, global, &mut value),
+        value.len() as isize
+    );
+    assert_eq!(usize::from_ne_bytes(value), MAGIC);
+    assert_eq!(unsafe { GLOBAL }, 0);
+    // a page boundary on both sides
+    let pattern = unsafe { &*core::ptr::addr_of!(PATTERN) };
+    let local = unsafe { &mut *core::ptr::addr_of_mut!(LOCAL) };
+    let start = straddling(pattern);
+    let local_start = straddling(local);
+    let local = &mut local[local_start..local_start + LEN];
+    assert_eq!(
+        process_vm_read(pid as usize, pattern.as_ptr() as usize + start, local),
+        LEN as isize
+    );
+    for (i, byte) in local.iter().enumerate() {
+        assert_eq!(*byte, ((start + i) % 251) as u8);
+    }
+    // not mapped in the child, or not writable here
+    assert_eq!(process_vm_read(pid as usize, 0x5000_0000, &mut value), -1);
+    assert_eq!(
+        syscall6(SYSCALL_PROCESS_VM_READ, [pid as usize, global, 0, 8, 0, 0]),
+        -EFAULT
+    );
+    // not descendants
+    assert_eq!(process_vm_read(getpid() as usize, global, &mut value), -1);
+    assert_eq!(process_vm_read(getppid() as usize, global, &mut value), -1);
+    assert_eq!(process_vm_read(9999, global, &mut value), -1);
+    assert_eq!(write(done[1], &[1]), 1);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(process_vm_read(pid as usize, global, &mut value), -1);
+    for fd in ready.iter().chain(done.iter()) {
+        close(*fd);
+    }
+    println!("Test process vm read OK!");
+    0
+}
@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, mprotect, query_pte, translate, PteInfo, MAP_LAZY};
+
+/*
+理想结果：mmap 读写映射后 pte 带有 V R W U，mprotect 为只读后 W 被清除；
+惰性页在访问前没有有效的 pte，未映射的地址返回 -1。输出 Test query pte OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(0, mmap(START, PAGE_SIZE, 3));
+    let mut info = PteInfo::default();
+    assert_eq!(0, query_pte(START, &mut info));
+    assert!(info.present && info.flags_v && info.flags_u);
+    assert!(info.flags_r && info.flags_w && !info.flags_x);
+    assert_eq!(info.ppn, translate(START) as usize / PAGE_SIZE);
+    assert_eq!(0, mprotect(START, PAGE_SIZE, 1));
+    assert_eq!(0, query_pte(START + 8, &mut info));
+    assert!(info.flags_v && info.flags_r && !info.flags_w);
+    // a lazy page has no valid pte until it is touched
+    let lazy = START + 2 * PAGE_SIZE;
+    assert_eq!(0, mmap(lazy, PAGE_SIZE, 3 | MAP_LAZY));
+    assert_eq!(0, query_pte(lazy, &mut info));
+    assert!(info.present && !info.flags_v);
+    unsafe {
+        *(lazy as *mut u8) = 1;
+    }
+    assert_eq!(0, query_pte(lazy, &mut info));
+    assert!(info.flags_v && info.flags_w);
+    // not mapped
+    assert_eq!(-1, query_pte(START + PAGE_SIZE, &mut info));
+    println!("Test query pte OK!");
+    0
+}
@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    get_time, getpid, reset_task_info, task_info, yield_, TaskInfo, TaskStatus, SYSCALL_GETPID,
+    SYSCALL_GETTIMEOFDAY, SYSCALL_RESET_TASK_INFO, SYSCALL_TASK_INFO, SYSCALL_YIELD,
+};
+
+/*
+理想结果：重置后 task info 只统计之后的系统调用，运行时间也从重置时刻重新计算。
+输出 Test reset task info OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let info = TaskInfo::new();
+    for _ in 0..5 {
+        get_time();
+        yield_();
+    }
+    assert_eq!(0, task_info(&info));
+    assert!(5 <= info.syscall_times[SYSCALL_GETTIMEOFDAY]);
+    assert_eq!(0, reset_task_info());
+    let t1 = get_time() as usize;
+    getpid();
+    getpid();
+    // 本次 task info 调用也计入，重置调用本身则不计入
+    assert_eq!(0, task_info(&info));
+    let t2 = get_time() as usize;
+    assert_eq!(2, info.syscall_times[SYSCALL_GETPID]);
+    assert_eq!(1, info.syscall_times[SYSCALL_TASK_INFO]);
+    assert_eq!(1, info.syscall_times[SYSCALL_GETTIMEOFDAY]);
+    assert_eq!(0, info.syscall_times[SYSCALL_YIELD]);
+    assert_eq!(0, info.syscall_times[SYSCALL_RESET_TASK_INFO]);
+    assert!(info.time <= t2 - t1 + 1);
+    assert!(info.status == TaskStatus::Running);
+    println!("Test reset task info OK!");
+    0
+}
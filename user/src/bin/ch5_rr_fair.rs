@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, sched_policy, set_priority, waitpid, SCHED_RR};
+
+/*
+理想结果：轮转调度下，优先级分别为 2、16、100 的三个进程在同一时间段内得到的运行次数大致相同。
+输出 Test rr fair OK!
+*/
+
+const RUN_TIME: isize = 1000;
+const PRIORITIES: [isize; 3] = [2, 16, 100];
+
+fn count_until(prio: isize, end_time: isize) -> i32 {
+    set_priority(prio);
+    let mut acc: i32 = 0;
+    loop {
+        acc += 1;
+        if acc % 400 == 0 && get_time() > end_time {
+            return acc / 400;
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let old_policy = sched_policy(SCHED_RR);
+    let end_time = get_time() + RUN_TIME;
+    let pids = PRIORITIES.map(|prio| {
+        let pid = fork();
+        if pid == 0 {
+            exit(count_until(prio, end_time));
+        }
+        pid
+    });
+    let mut counts = [0i32; PRIORITIES.len()];
+    for (pid, count) in pids.iter().zip(counts.iter_mut()) {
+        assert_eq!(waitpid(*pid as usize, count), *pid);
+    }
+    sched_policy(old_policy);
+    println!("prio {:?}: {:?}", PRIORITIES, counts);
+    let min = *counts.iter().min().unwrap();
+    let max = *counts.iter().max().unwrap();
+    assert!(min > 0);
+    // ideally equal, leave room for the timer granularity and the parent
+    assert!(max < min * 2);
+    println!("Test rr fair OK!");
+    0
+}
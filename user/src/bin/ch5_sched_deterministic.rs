@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, sched_deterministic, set_priority, wait_any, yield_, MAP_SHARED,
+};
+
+/*
+理想结果：关闭时钟抢占后，三个不同优先级的子进程只在 yield 处切换，记录下的运行顺序与按 stride
+推算出的顺序完全一致，重复运行得到相同的顺序。输出 Test sched deterministic OK!
+*/
+
+/// BIG_STRIDE in the kernel
+const BIG_STRIDE: u64 = 0x1111_1111;
+const PRIORITIES: [usize; 3] = [3, 5, 7];
+/// Times each child logs itself
+const ROUNDS: usize = 6;
+const LOG_LEN: usize = ROUNDS * PRIORITIES.len();
+
+/// Shared with the children, which log their index when they run
+#[repr(C)]
+struct Shared {
+    count: usize,
+    log: [usize; LOG_LEN],
+}
+
+/// The order the stride scheduler runs the children in. Each starts at the
+/// stride of the parent and has yielded once after setting its priority, so
+/// its stride is one pass past that.
+fn expected_log() -> [usize; LOG_LEN] {
+    let passes = PRIORITIES.map(|prio| BIG_STRIDE / prio as u64);
+    let mut strides = passes;
+    let mut left = [ROUNDS; PRIORITIES.len()];
+    let mut log = [0; LOG_LEN];
+    for entry in log.iter_mut() {
+        let next = (0..PRIORITIES.len())
+            .filter(|&i| left[i] > 0)
+            .min_by_key(|&i| strides[i])
+            .unwrap();
+        // a tie would be broken by the heap, not by the strides
+        assert!(
+            (0..PRIORITIES.len()).all(|i| i == next || left[i] == 0 || strides[i] != strides[next])
+        );
+        *entry = next;
+        strides[next] += passes[next];
+        left[next] -= 1;
+    }
+    log
+}
+
+fn run_children(shared: &mut Shared) -> [usize; LOG_LEN] {
+    shared.count = 0;
+    for (i, &prio) in PRIORITIES.iter().enumerate() {
+        if fork() == 0 {
+            set_priority(prio as isize);
+            // from now on the strides alone tell who runs
+            yield_();
+            for _ in 0..ROUNDS {
+                let count = unsafe { core::ptr::read_volatile(&shared.count) };
+                shared.log[count] = i;
+                unsafe { core::ptr::write_volatile(&mut shared.count, count + 1) };
+                yield_();
+            }
+            exit(0);
+        }
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..PRIORITIES.len() {
+        assert!(wait_any(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    assert_eq!(shared.count, LOG_LEN);
+    shared.log
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, 4096, 3 | MAP_SHARED));
+    let shared = unsafe { &mut *(start as *mut Shared) };
+    let was_deterministic = sched_deterministic(true);
+    let first = run_children(shared);
+    let second = run_children(shared);
+    sched_deterministic(was_deterministic);
+    println!("order: {:?}", first);
+    assert_eq!(first, expected_log());
+    assert_eq!(first, second);
+    assert_eq!(0, munmap(start, 4096));
+    println!("Test sched deterministic OK!");
+    0
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    fork, getpid, kill, set_priority, task_sched_info, waitpid, yield_, SchedInfo, SIGKILL,
+};
+
+/*
+理想结果：设置优先级后 pass 等于 BIG_STRIDE / priority，运行一段时间后 stride 增加；
+可以查询其他进程，查询不存在的进程返回 -1。输出 Test sched info OK!
+*/
+
+const BIG_STRIDE: u64 = 0x1111_1111;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_priority(32), 32);
+    let mut before = SchedInfo::default();
+    assert_eq!(task_sched_info(-1, &mut before), 0);
+    assert_eq!(before.priority, 32);
+    assert_eq!(before.pass, BIG_STRIDE / 32);
+    for _ in 0..10 {
+        yield_();
+    }
+    let mut after = SchedInfo::default();
+    assert_eq!(task_sched_info(getpid(), &mut after), 0);
+    // each yield charges a pass, aging may only have moved it further on
+    assert!(after.stride.wrapping_sub(before.stride) as i64 > 0);
+    assert!(after.switch_count > before.switch_count);
+    assert!(after.last_run_tick > before.last_run_tick);
+    let pid = fork();
+    if pid == 0 {
+        set_priority(5);
+        loop {
+            yield_();
+        }
+    }
+    // let the child set its priority
+    yield_();
+    let mut child = SchedInfo::default();
+    assert_eq!(task_sched_info(pid, &mut child), 0);
+    assert_eq!(child.priority, 5);
+    assert_eq!(child.pass, BIG_STRIDE / 5);
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(task_sched_info(pid, &mut child), -1);
+    assert_eq!(task_sched_info(0x7fff_ffff, &mut child), -1);
+    println!("Test sched info OK!");
+    0
+}
@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, sched_deterministic, sched_policy, set_priority, wait_any, yield_,
+    MAP_SHARED, SCHED_RR, SCHED_STRIDE,
+};
+
+/*
+理想结果：关闭时钟抢占后，轮转调度下三个不同优先级的子进程严格按就绪顺序轮流运行；
+stride 调度下优先级最高的子进程最先运行完，优先级最低的最后运行完。非法的策略返回 -1。
+输出 Test sched policy OK!
+*/
+
+const PRIORITIES: [usize; 3] = [3, 5, 7];
+/// Times each child logs itself
+const ROUNDS: usize = 6;
+const LOG_LEN: usize = ROUNDS * PRIORITIES.len();
+
+/// Shared with the children, which log their index when they run
+#[repr(C)]
+struct Shared {
+    count: usize,
+    log: [usize; LOG_LEN],
+}
+
+fn run_children(shared: &mut Shared) -> [usize; LOG_LEN] {
+    shared.count = 0;
+    for (i, &prio) in PRIORITIES.iter().enumerate() {
+        if fork() == 0 {
+            set_priority(prio as isize);
+            yield_();
+            for _ in 0..ROUNDS {
+                let count = unsafe { core::ptr::read_volatile(&shared.count) };
+                shared.log[count] = i;
+                unsafe { core::ptr::write_volatile(&mut shared.count, count + 1) };
+                yield_();
+            }
+            exit(0);
+        }
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..PRIORITIES.len() {
+        assert!(wait_any(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    assert_eq!(shared.count, LOG_LEN);
+    shared.log
+}
+
+/// Where the child `i` logs itself for the last time
+fn last_run(log: &[usize; LOG_LEN], i: usize) -> usize {
+    log.iter().rposition(|&entry| entry == i).unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, 4096, 3 | MAP_SHARED));
+    let shared = unsafe { &mut *(start as *mut Shared) };
+    assert_eq!(sched_policy(2), usize::MAX);
+    let was_deterministic = sched_deterministic(true);
+    let old_policy = sched_policy(SCHED_RR);
+
+    let round_robin = run_children(shared);
+    println!("round-robin: {:?}", round_robin);
+    for (n, &entry) in round_robin.iter().enumerate() {
+        assert_eq!(entry, n % PRIORITIES.len());
+    }
+
+    assert_eq!(sched_policy(SCHED_STRIDE), SCHED_RR);
+    let stride = run_children(shared);
+    println!("stride: {:?}", stride);
+    assert!(last_run(&stride, 2) < last_run(&stride, 1));
+    assert!(last_run(&stride, 1) < last_run(&stride, 0));
+
+    sched_policy(old_policy);
+    sched_deterministic(was_deterministic);
+    assert_eq!(0, munmap(start, 4096));
+    println!("Test sched policy OK!");
+    0
+}
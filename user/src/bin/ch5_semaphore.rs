@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    exit, fork, kill, semaphore_create, semaphore_down, semaphore_up, thread_create, waitpid,
+    yield_, SIGKILL,
+};
+
+/*
+理想结果：生产者与消费者线程通过两个信号量共享一个有界缓冲区，消费者按顺序收到全部数据；
+阻塞在信号量上的进程可以被 SIGKILL 杀死，退出码为 -9。输出 Test semaphore OK!
+*/
+
+const SLOTS: usize = 4;
+const ITEMS: usize = 64;
+
+static mut BUFFER: [usize; SLOTS] = [0; SLOTS];
+static EMPTY: AtomicUsize = AtomicUsize::new(0);
+static FULL: AtomicUsize = AtomicUsize::new(0);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+static IN_ORDER: AtomicUsize = AtomicUsize::new(0);
+
+fn producer(_arg: usize) -> ! {
+    for item in 0..ITEMS {
+        assert_eq!(semaphore_down(EMPTY.load(Ordering::SeqCst)), 0);
+        unsafe { core::ptr::write_volatile(&mut BUFFER[item % SLOTS], item) };
+        semaphore_up(FULL.load(Ordering::SeqCst));
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+fn consumer(_arg: usize) -> ! {
+    for item in 0..ITEMS {
+        assert_eq!(semaphore_down(FULL.load(Ordering::SeqCst)), 0);
+        if unsafe { core::ptr::read_volatile(&BUFFER[item % SLOTS]) } == item {
+            IN_ORDER.fetch_add(1, Ordering::SeqCst);
+        }
+        semaphore_up(EMPTY.load(Ordering::SeqCst));
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let empty = semaphore_create(SLOTS);
+    let full = semaphore_create(0);
+    assert!(empty >= 0 && full >= 0 && empty != full);
+    EMPTY.store(empty as usize, Ordering::SeqCst);
+    FULL.store(full as usize, Ordering::SeqCst);
+    assert!(thread_create(consumer as usize, 0) > 0);
+    assert!(thread_create(producer as usize, 0) > 0);
+    while DONE.load(Ordering::SeqCst) < 2 {
+        yield_();
+    }
+    assert_eq!(IN_ORDER.load(Ordering::SeqCst), ITEMS);
+    assert_eq!(semaphore_down(usize::MAX), -1);
+
+    // a task waiting for a semaphore forever can still be killed
+    let pid = fork();
+    if pid == 0 {
+        let sem = semaphore_create(0);
+        semaphore_down(sem as usize);
+        unreachable!();
+    }
+    for _ in 0..10 {
+        yield_();
+    }
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -9);
+    println!("Test semaphore OK!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{getpid, kill, sigaction, sigreturn, SIGKILL, SIGUSR1};
+
+/*
+理想结果：SIGUSR1 的用户处理函数收到信号编号并运行一次，之后程序从被打断处继续执行；
+SIGKILL 不能注册处理函数，旧的处理函数通过 old 返回。输出 Test sigaction OK!
+*/
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn handler(signum: usize) {
+    HANDLED.store(signum, Ordering::SeqCst);
+    sigreturn();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(sigaction(SIGKILL, handler as usize, None), -1);
+    assert_eq!(sigaction(0, handler as usize, None), -1);
+    let mut old = usize::MAX;
+    assert_eq!(sigaction(SIGUSR1, handler as usize, Some(&mut old)), 0);
+    assert_eq!(old, 0);
+    let mut old = 0;
+    assert_eq!(sigaction(SIGUSR1, handler as usize, Some(&mut old)), 0);
+    assert_eq!(old, handler as usize);
+    // the handler runs on the way back from kill
+    assert_eq!(kill(getpid() as usize, SIGUSR1), 0);
+    assert_eq!(HANDLED.load(Ordering::SeqCst), SIGUSR1 as usize);
+    println!("Test sigaction OK!");
+    0
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    getpid, kill, sigaction, sigprocmask, sigreturn, SIGKILL, SIGUSR1, SIG_BLOCK, SIG_SETMASK,
+    SIG_UNBLOCK,
+};
+
+/*
+理想结果：屏蔽 SIGUSR1 后发送的信号保持挂起，处理函数不运行；解除屏蔽后处理函数立即运行一次。
+SIGKILL 不能被屏蔽。输出 Test sigprocmask OK!
+*/
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn handler(_signum: usize) {
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+    sigreturn();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let usr1 = 1u64 << SIGUSR1;
+    assert_eq!(sigaction(SIGUSR1, handler as usize, None), 0);
+    let mut old = u64::MAX;
+    assert_eq!(sigprocmask(SIG_BLOCK, Some(usr1), Some(&mut old)), 0);
+    assert_eq!(old, 0);
+    assert_eq!(kill(getpid() as usize, SIGUSR1), 0);
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 0);
+    // the pending signal is handled on the way back from unblocking it
+    assert_eq!(sigprocmask(SIG_UNBLOCK, Some(usr1), Some(&mut old)), 0);
+    assert_eq!(old, usr1);
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1);
+    // SIGKILL never gets into the mask
+    let kill_bit = 1u64 << SIGKILL;
+    assert_eq!(sigprocmask(SIG_SETMASK, Some(kill_bit | usr1), None), 0);
+    assert_eq!(sigprocmask(SIG_BLOCK, None, Some(&mut old)), 0);
+    assert_eq!(old, usr1);
+    assert_eq!(sigprocmask(3, Some(0), None), -1);
+    assert_eq!(sigprocmask(SIG_SETMASK, Some(0), None), 0);
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1);
+    println!("Test sigprocmask OK!");
+    0
+}
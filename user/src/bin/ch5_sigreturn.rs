@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{exit, fork, getpid, kill, sigaction, sigreturn, waitpid, SIGUSR1};
+
+/*
+理想结果：子进程向正在循环计算的父进程发送 SIGUSR1，处理函数修改标志后返回，
+循环从被打断处继续，计算结果与重新计算的一致；没有处理函数在运行时调用 sigreturn
+的进程被杀死，退出码为 -9。输出 Test sigreturn OK!
+*/
+
+static FLAG: AtomicUsize = AtomicUsize::new(0);
+
+fn handler(_signum: usize) {
+    FLAG.store(1, Ordering::SeqCst);
+    sigreturn();
+}
+
+fn step(acc: usize, i: usize) -> usize {
+    acc.wrapping_mul(31).wrapping_add(i)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(sigaction(SIGUSR1, handler as usize, None), 0);
+    let parent = getpid() as usize;
+    let pid = fork();
+    if pid == 0 {
+        kill(parent, SIGUSR1);
+        exit(0);
+    }
+    // the signal lands somewhere in this loop, at a timer interrupt
+    let mut acc: usize = 0;
+    let mut i: usize = 0;
+    while FLAG.load(Ordering::SeqCst) == 0 {
+        i += 1;
+        acc = step(acc, i);
+    }
+    let mut expected: usize = 0;
+    for j in 1..=i {
+        expected = step(expected, j);
+    }
+    assert_eq!(acc, expected);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // there is nothing to return to without a handler running
+    let pid = fork();
+    if pid == 0 {
+        sigreturn();
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -9);
+    println!("Test sigreturn OK!");
+    0
+}
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{fork, get_time, kill, mmap, sleep_blocking, waitpid, yield_, MAP_SHARED, SIGKILL};
+
+/*
+理想结果：sleep_blocking(50) 至少睡眠 50ms，期间不占用 CPU，另一个进程得以持续运行；
+sleep_blocking(0) 等同于 yield。输出 Test sleep blocking OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let counter: usize = 0x10000000;
+    assert_eq!(0, mmap(counter, 4096, 3 | MAP_SHARED));
+    let counter = counter as *mut usize;
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            unsafe {
+                counter.write_volatile(counter.read_volatile() + 1);
+            }
+            yield_();
+        }
+    }
+    sleep_blocking(0);
+    let start = get_time();
+    let before = unsafe { counter.read_volatile() };
+    sleep_blocking(50);
+    let elapsed = get_time() - start;
+    let after = unsafe { counter.read_volatile() };
+    println!(
+        "slept {}ms, the other task made {} rounds",
+        elapsed,
+        after - before
+    );
+    assert!(elapsed >= 50);
+    assert!(after > before);
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    println!("Test sleep blocking OK!");
+    0
+}
@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, spawn, waitpid};
+
+/*
+理想结果：反复 spawn 同一个应用，每次都得到正确的退出码；不存在的应用 spawn 失败。
+输出 Test spawn many OK!
+*/
+
+const TIMES: usize = 200;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time();
+    for _ in 0..TIMES {
+        let cpid = spawn("ch5_exit0\0");
+        assert!(cpid > 0, "child pid invalid");
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(cpid as usize, &mut exit_code), cpid);
+        assert_eq!(exit_code, 66778);
+    }
+    println!("{} spawns took {} ms", TIMES, get_time() - start);
+    // names sorting around existing ones are not found
+    assert_eq!(spawn("ch5_exit\0"), -1);
+    assert_eq!(spawn("ch5_exit00\0"), -1);
+    assert_eq!(spawn("\0"), -1);
+    println!("Test spawn many OK!");
+    0
+}
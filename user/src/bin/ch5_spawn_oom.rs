@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, spawn, waitpid};
+
+/*
+理想结果：物理页帧几乎耗尽时 spawn 返回 -1，已分配的页帧与 pid 全部归还：
+之后同样数量的页帧可以重新映射，下一个 fork 得到的 pid 与之前回收的相同。
+输出 Test spawn oom OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+/// fewer frames than any program needs
+const SPARE_PAGES: usize = 8;
+
+/// Map frames until they run out, return the end of the mapping
+fn exhaust() -> usize {
+    let mut end = START;
+    let mut len = 1 << 24;
+    while len >= PAGE_SIZE {
+        if mmap(end, len, 3) == 0 {
+            end += len;
+        } else {
+            len /= 2;
+        }
+    }
+    end
+}
+
+fn fork_and_wait() -> isize {
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    pid
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the pid of this child is recycled and handed out next
+    let recycled = fork_and_wait();
+    let end = exhaust();
+    assert!(end - START > SPARE_PAGES * PAGE_SIZE);
+    assert_eq!(0, munmap(START, SPARE_PAGES * PAGE_SIZE));
+    assert_eq!(-1, spawn("ch5_exec_args_echo\0"));
+    // every frame the failed spawn took is back
+    assert_eq!(0, mmap(START, SPARE_PAGES * PAGE_SIZE, 3));
+    assert_eq!(0, munmap(START, end - START));
+    // and so is the pid
+    assert_eq!(fork_and_wait(), recycled);
+    let pid = spawn("ch5_exec_args_echo\0");
+    assert!(pid > 0);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test spawn oom OK!");
+    0
+}
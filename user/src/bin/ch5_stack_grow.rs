@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{exit, fork, waitpid};
+
+/*
+理想结果：递归深度远超初始用户栈时栈自动向下增长，递归正常完成；
+访问栈下方过远的地址仍然导致子进程被杀死。输出 Test stack grow OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+/// About 1KiB of stack per level
+fn recurse(depth: usize) -> usize {
+    let mut buf = [0u8; 1024];
+    unsafe {
+        write_volatile(&mut buf[depth % 1024], depth as u8);
+    }
+    let below = if depth == 0 { 0 } else { recurse(depth - 1) };
+    below + unsafe { read_volatile(&buf[depth % 1024]) } as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 128 KiB, far more than the initial 8 KiB stack
+    let depth = 128;
+    let expected: usize = (0..=depth).map(|i| i as u8 as usize).sum();
+    assert_eq!(recurse(depth), expected);
+    let pid = fork();
+    if pid == 0 {
+        let local = 0u8;
+        let far = &local as *const u8 as usize - 200 * PAGE_SIZE;
+        unsafe {
+            write_volatile(far as *mut u8, 1);
+        }
+        println!("Should cause error, Test stack grow fail!");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+    println!("Test stack grow OK!");
+    0
+}
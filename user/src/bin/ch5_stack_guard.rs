@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{exit, fork, mmap, waitpid};
+
+/*
+理想结果：无限递归使栈增长到上限后访问保护页，子进程被杀死而不会改写保护页下方的映射；
+保护页本身无法被 mmap。输出 Test stack guard OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+/// USER_STACK_TOP - USER_STACK_MAX_SIZE - PAGE_SIZE in the kernel
+const GUARD_PAGE: usize = 0x40_0000_0000 - PAGE_SIZE - 4096 * 256 - PAGE_SIZE;
+
+fn overflow(depth: usize) -> usize {
+    let mut buf = [0u8; 1024];
+    unsafe {
+        write_volatile(&mut buf[depth % 1024], depth as u8);
+    }
+    // never true, it only keeps the recursion from being unconditional
+    if depth == usize::MAX {
+        return 0;
+    }
+    overflow(depth + 1) + unsafe { read_volatile(&buf[depth % 1024]) } as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(-1, mmap(GUARD_PAGE, PAGE_SIZE, 3));
+    let below = GUARD_PAGE - PAGE_SIZE;
+    assert_eq!(0, mmap(below, PAGE_SIZE, 3));
+    for i in below..GUARD_PAGE {
+        unsafe {
+            *(i as *mut u8) = 0xaa;
+        }
+    }
+    let pid = fork();
+    if pid == 0 {
+        overflow(0);
+        println!("Should cause error, Test stack guard fail!");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+    // so does a plain write to the guard page
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            write_volatile(GUARD_PAGE as *mut u8, 0);
+        }
+        println!("Should cause error, Test stack guard fail!");
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -2);
+    for i in below..GUARD_PAGE {
+        unsafe {
+            assert_eq!(*(i as *const u8), 0xaa);
+        }
+    }
+    println!("Test stack guard OK!");
+    0
+}
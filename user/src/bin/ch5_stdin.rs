@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, inject_input, kill, read, sleep_blocking, waitpid, yield_, SIGKILL,
+};
+
+/*
+理想结果：从 stdin 读取时一次返回当前所有可读的输入（不超过缓冲区长度），
+没有输入时读者阻塞直到输入到来，阻塞中的读者可被 SIGKILL 杀死。输出 hello 与 Test stdin OK!
+*/
+
+const STDIN: usize = 0;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // read a whole line at once and echo it
+    assert_eq!(inject_input(b"hello\n"), 6);
+    let mut buf = [0u8; 32];
+    let n = read(STDIN, &mut buf);
+    assert_eq!(n, 6);
+    let line = core::str::from_utf8(&buf[..n as usize]).unwrap();
+    print!("{}", line);
+    assert_eq!(line, "hello\n");
+    // no more than the buffer holds, the rest is left for the next read
+    assert_eq!(inject_input(b"abc"), 3);
+    assert_eq!(read(STDIN, &mut buf[..2]), 2);
+    assert_eq!(&buf[..2], b"ab");
+    assert_eq!(read(STDIN, &mut buf), 1);
+    assert_eq!(buf[0], b'c');
+    // block until the input arrives
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(100);
+        inject_input(b"late\n");
+        exit(0);
+    }
+    let start = get_time();
+    assert_eq!(read(STDIN, &mut buf), 5);
+    assert!(get_time() - start >= 100);
+    assert_eq!(&buf[..5], b"late\n");
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    // a reader still waiting for input can be killed
+    let pid = fork();
+    if pid == 0 {
+        read(STDIN, &mut buf);
+        println!("Should be killed, Test stdin fail!");
+        exit(0);
+    }
+    for _ in 0..10 {
+        yield_();
+    }
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -SIGKILL);
+    println!("Test stdin OK!");
+    0
+}
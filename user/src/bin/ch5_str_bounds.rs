@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, mmap, munmap, set_task_name, spawn};
+
+/*
+理想结果：传给 spawn、exec 和 set_task_name 的字符串在映射页的末尾仍未结束，或者长度超过 PATH_MAX 时，
+系统调用返回 -1，内核不会崩溃。输出 Test str bounds OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+/// PATH_MAX in the kernel
+const PATH_MAX: usize = 255;
+
+/// The string of `len` bytes at `addr`, which need not end with a NUL
+fn str_at(addr: usize, len: usize) -> &'static str {
+    unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(addr as *const u8, len)) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    // nothing is mapped right after the page
+    assert_eq!(0, mmap(start, PAGE_SIZE, 3));
+    let page = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, PAGE_SIZE) };
+    page.fill(b'a');
+    let unterminated = str_at(start + PAGE_SIZE - 8, 8);
+    assert_eq!(spawn(unterminated), -1);
+    assert_eq!(exec(unterminated, &[core::ptr::null::<u8>()]), -1);
+    assert_eq!(set_task_name(unterminated), -1);
+    // terminated, but too long
+    page[PATH_MAX + 1] = 0;
+    let too_long = str_at(start, PATH_MAX + 2);
+    assert_eq!(spawn(too_long), -1);
+    assert_eq!(set_task_name(too_long), -1);
+    // just short enough, no such app though
+    page[PATH_MAX] = 0;
+    let longest = str_at(start, PATH_MAX + 1);
+    assert_eq!(spawn(longest), -1);
+    assert_eq!(set_task_name(longest), 0);
+    assert_eq!(set_task_name("ch5_str_bounds\0"), 0);
+    assert_eq!(munmap(start, PAGE_SIZE), 0);
+    println!("Test str bounds OK!");
+    0
+}
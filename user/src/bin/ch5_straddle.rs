@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use core::ptr::read_unaligned;
+use user_lib::{mmap, sys_get_time, task_info, TaskInfo, TaskStatus, TimeVal, SYSCALL_TASK_INFO};
+
+/*
+理想结果：跨越页边界的 TimeVal 与 TaskInfo 被完整写入，且不会改写相邻的字节。
+输出 Test straddle OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, PAGE_SIZE * 2, 3));
+    for i in start..start + PAGE_SIZE * 2 {
+        unsafe {
+            *(i as *mut u8) = 0xaa;
+        }
+    }
+    // 4 bytes in the first page, 12 in the second one
+    let ts = start + PAGE_SIZE - 4;
+    assert_eq!(0, sys_get_time(unsafe { &*(ts as *const TimeVal) }, 0));
+    let now = TimeVal::new();
+    assert_eq!(0, sys_get_time(&now, 0));
+    let ts: TimeVal = unsafe { read_unaligned(ts as *const TimeVal) };
+    let us = ts.sec * 1_000_000 + ts.usec;
+    let now_us = now.sec * 1_000_000 + now.usec;
+    assert!(ts.usec < 1_000_000);
+    assert!(us <= now_us && now_us - us < 1_000_000);
+    unsafe {
+        assert_eq!(*((start + PAGE_SIZE - 5) as *const u8), 0xaa);
+        assert_eq!(*((start + PAGE_SIZE + 12) as *const u8), 0xaa);
+    }
+    // the TaskInfo is much larger, most of it lands in the second page
+    let ti = start + PAGE_SIZE - 4;
+    assert_eq!(0, task_info(unsafe { &*(ti as *const TaskInfo) }));
+    let ti: TaskInfo = unsafe { read_unaligned(ti as *const TaskInfo) };
+    assert!(ti.status == TaskStatus::Running);
+    assert_eq!(ti.syscall_times[SYSCALL_TASK_INFO], 1);
+    unsafe {
+        assert_eq!(*((start + PAGE_SIZE - 5) as *const u8), 0xaa);
+        assert_eq!(
+            *((start + PAGE_SIZE - 4 + size_of::<TaskInfo>()) as *const u8),
+            0xaa
+        );
+    }
+    println!("Test straddle OK!");
+    0
+}
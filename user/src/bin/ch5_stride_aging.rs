@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{exit, fork, mmap, set_priority, waitpid, yield_, MAP_SHARED};
+
+/*
+理想结果：优先级为 2 的进程夹在多个优先级为 100 的进程中间，也能在有限的调度轮数内得到运行：
+两次运行之间高优先级进程最多运行 MAX_GAP 次（没有老化时约为 400 次）。
+输出 Test stride aging OK!
+*/
+
+const HIGH: usize = 8;
+/// Runs of each high priority child
+const HIGH_RUNS: usize = 200;
+/// Aging lets the low priority child run after 144 rounds at most
+const MAX_GAP: usize = 200;
+
+/// Shared with the children
+#[repr(C)]
+struct Shared {
+    /// Runs of the high priority children so far
+    high_runs: usize,
+    /// High priority children that are done
+    high_done: usize,
+    /// Runs of the low priority child while the others were running
+    low_runs: usize,
+    /// Most runs of the high priority children between two of the low one
+    max_gap: usize,
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, 4096, 3 | MAP_SHARED));
+    let shared = start as *mut Shared;
+    let mut pids = [0isize; HIGH + 1];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        *pid = fork();
+        if *pid != 0 {
+            continue;
+        }
+        unsafe {
+            if i == HIGH {
+                set_priority(2);
+                let mut last = None;
+                while read_volatile(&(*shared).high_done) < HIGH {
+                    let now = read_volatile(&(*shared).high_runs);
+                    if let Some(last) = last {
+                        (*shared).max_gap = (*shared).max_gap.max(now - last);
+                    }
+                    last = Some(now);
+                    (*shared).low_runs += 1;
+                    yield_();
+                }
+            } else {
+                set_priority(100);
+                for _ in 0..HIGH_RUNS {
+                    write_volatile(&mut (*shared).high_runs, (*shared).high_runs + 1);
+                    yield_();
+                }
+                write_volatile(&mut (*shared).high_done, (*shared).high_done + 1);
+            }
+        }
+        exit(0);
+    }
+    for pid in pids.iter() {
+        let mut exit_code: i32 = -1;
+        assert_eq!(waitpid(*pid as usize, &mut exit_code), *pid);
+        assert_eq!(exit_code, 0);
+    }
+    let shared = unsafe { &*shared };
+    println!(
+        "low ran {} times, at most {} high runs apart",
+        shared.low_runs, shared.max_gap
+    );
+    assert!(shared.low_runs >= 2);
+    assert!(shared.max_gap <= MAX_GAP);
+    println!("Test stride aging OK!");
+    0
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, set_priority, waitpid};
+
+/*
+理想结果：优先级为 100 的进程在同一时间段内得到的运行次数远多于优先级为 2 的进程。
+输出 Test stride ratio OK!
+*/
+
+const RUN_TIME: isize = 2000;
+
+fn count_until(prio: isize, end_time: isize) -> i32 {
+    set_priority(prio);
+    let mut acc: i32 = 0;
+    loop {
+        acc += 1;
+        if acc % 400 == 0 && get_time() > end_time {
+            return acc / 400;
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let end_time = get_time() + RUN_TIME;
+    let low = fork();
+    if low == 0 {
+        exit(count_until(2, end_time));
+    }
+    let high = fork();
+    if high == 0 {
+        exit(count_until(100, end_time));
+    }
+    let mut low_count: i32 = 0;
+    let mut high_count: i32 = 0;
+    assert_eq!(waitpid(low as usize, &mut low_count), low);
+    assert_eq!(waitpid(high as usize, &mut high_count), high);
+    println!("prio 2: {}, prio 100: {}", low_count, high_count);
+    // ideally 50 times, leave room for the timer granularity
+    assert!(high_count > low_count * 10);
+    println!("Test stride ratio OK!");
+    0
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{meminfo, mmap, munmap, MemInfo, MAP_LAZY};
+
+/*
+理想结果：惰性映射并写入比空闲物理页更多的页时，部分页被换出到交换区，再次读取时换入，
+所有页的内容都完好无损；munmap 后交换区被全部释放。输出 Test swap OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+/// Pages mapped beyond the free frames
+const OVERCOMMIT: usize = 256;
+
+fn mem_info() -> MemInfo {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info
+}
+
+fn page(start: usize, i: usize) -> &'static mut [u64] {
+    unsafe { core::slice::from_raw_parts_mut((start + i * PAGE_SIZE) as *mut u64, PAGE_SIZE / 8) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    let before = mem_info();
+    // the page tables of the range take frames as well
+    assert!(before.swap_pages >= OVERCOMMIT * 2);
+    let pages = before.free_frames + OVERCOMMIT;
+    assert_eq!(mmap(start, pages * PAGE_SIZE, 3 | MAP_LAZY), 0);
+    for i in 0..pages {
+        let page = page(start, i);
+        page[0] = i as u64;
+        page[PAGE_SIZE / 8 - 1] = !(i as u64);
+    }
+    let during = mem_info();
+    println!(
+        "{} pages mapped, {} frames free, {} pages swapped out",
+        pages, during.free_frames, during.swap_used
+    );
+    assert!(during.swap_used >= OVERCOMMIT);
+    // every page reads back, those swapped out as well
+    for i in 0..pages {
+        let page = page(start, i);
+        assert_eq!(page[0], i as u64);
+        assert!(page[1..PAGE_SIZE / 8 - 1].iter().all(|w| *w == 0));
+        assert_eq!(page[PAGE_SIZE / 8 - 1], !(i as u64));
+    }
+    assert_eq!(munmap(start, pages * PAGE_SIZE), 0);
+    let after = mem_info();
+    assert_eq!(after.swap_used, 0);
+    // the page tables stay
+    assert!(after.free_frames + pages / 512 + 4 >= before.free_frames);
+    println!("Test swap OK!");
+    0
+}
@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{exit, fork, meminfo, mmap, munmap, waitpid, yield_, MemInfo, MAP_LAZY, MAP_SHARED};
+
+/*
+理想结果：子进程的惰性页占满空闲物理页后，父进程仍能立即映射 256 页，子进程的页被换出为其腾出物理页；
+子进程之后读回的所有页都完好无损。输出 Test swap reclaim OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+/// Pages the parent maps once the child holds every free frame
+const EAGER_PAGES: usize = 256;
+
+fn mem_info() -> MemInfo {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info
+}
+
+fn page(start: usize, i: usize) -> &'static mut [u64] {
+    unsafe { core::slice::from_raw_parts_mut((start + i * PAGE_SIZE) as *mut u64, PAGE_SIZE / 8) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let sync: usize = 0x20000000;
+    assert_eq!(0, mmap(sync, PAGE_SIZE, 3 | MAP_SHARED));
+    let stage = unsafe { &*(sync as *const AtomicUsize) };
+    let before = mem_info();
+    assert!(before.swap_pages >= EAGER_PAGES * 2);
+    let pid = fork();
+    if pid == 0 {
+        let start: usize = 0x10000000;
+        let pages = mem_info().free_frames;
+        assert_eq!(mmap(start, pages * PAGE_SIZE, 3 | MAP_LAZY), 0);
+        for i in 0..pages {
+            page(start, i)[0] = i as u64;
+        }
+        stage.store(1, Ordering::SeqCst);
+        while stage.load(Ordering::SeqCst) == 1 {
+            yield_();
+        }
+        // some pages were swapped out for the parent, they read back
+        for i in 0..pages {
+            assert_eq!(page(start, i)[0], i as u64);
+        }
+        exit(0);
+    }
+    while stage.load(Ordering::SeqCst) == 0 {
+        yield_();
+    }
+    let start: usize = 0x30000000;
+    // every frame is mapped right away, the lazy pages of the child make room
+    assert_eq!(mmap(start, EAGER_PAGES * PAGE_SIZE, 3), 0);
+    for i in 0..EAGER_PAGES {
+        page(start, i)[0] = !(i as u64);
+    }
+    let during = mem_info();
+    println!(
+        "{} frames free, {} pages swapped out",
+        during.free_frames, during.swap_used
+    );
+    assert!(during.swap_used >= EAGER_PAGES);
+    for i in 0..EAGER_PAGES {
+        assert_eq!(page(start, i)[0], !(i as u64));
+    }
+    assert_eq!(munmap(start, EAGER_PAGES * PAGE_SIZE), 0);
+    stage.store(2, Ordering::SeqCst);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(mem_info().swap_used, 0);
+    println!("Test swap reclaim OK!");
+    0
+}
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::write_volatile;
+use user_lib::{exit, fork, get_time, kill, task_stats, waitpid, yield_, TaskStats, SIGKILL};
+
+/*
+理想结果：有其他就绪进程时，每次 yield 都使进程重新被换入一次；
+一直计算的进程只在时钟中断被换出后才被重新换入，换入次数远少于频繁 yield 的进程。
+输出 Test switch count OK!
+*/
+
+const YIELDS: usize = 100;
+const RUN_MS: isize = 50;
+
+fn switch_count() -> usize {
+    let mut stats = TaskStats::default();
+    assert_eq!(0, task_stats(&mut stats));
+    stats.switch_count
+}
+
+fn spin() -> ! {
+    let mut i: usize = 0;
+    loop {
+        unsafe {
+            write_volatile(&mut i, i.wrapping_add(1));
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // something else is always ready while the yielder runs
+    let partner = fork();
+    if partner == 0 {
+        spin();
+    }
+    let yielder = fork();
+    if yielder == 0 {
+        let before = switch_count();
+        for _ in 0..YIELDS {
+            yield_();
+        }
+        exit((switch_count() - before) as i32);
+    }
+    let mut yields: i32 = 0;
+    assert_eq!(waitpid(yielder as usize, &mut yields), yielder);
+    assert_eq!(kill(partner as usize, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(partner as usize, &mut exit_code), partner);
+    let computer = fork();
+    if computer == 0 {
+        let before = switch_count();
+        let start = get_time();
+        let mut i: usize = 0;
+        while get_time() - start < RUN_MS {
+            unsafe {
+                write_volatile(&mut i, i.wrapping_add(1));
+            }
+        }
+        exit((switch_count() - before) as i32);
+    }
+    let mut computes: i32 = 0;
+    assert_eq!(waitpid(computer as usize, &mut computes), computer);
+    println!(
+        "switched in: yield-heavy {}, compute-heavy {}",
+        yields, computes
+    );
+    assert!(yields as usize >= YIELDS);
+    assert!(computes < yields);
+    println!("Test switch count OK!");
+    0
+}
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{reset_task_info, syscall, task_info, TaskInfo, SYSCALL_TASK_INFO};
+
+/*
+理想结果：系统调用号超出 syscall_times 范围时不被计数，也不会越界；
+范围内的未知调用照常计数。输出 Test syscall times bound OK!
+*/
+
+const MAX_SYSCALL_NUM: usize = 500;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(0, reset_task_info());
+    assert_eq!(-1, syscall(MAX_SYSCALL_NUM, [0, 0, 0]));
+    assert_eq!(-1, syscall(MAX_SYSCALL_NUM * 1000, [0, 0, 0]));
+    assert_eq!(-1, syscall(usize::MAX, [0, 0, 0]));
+    assert_eq!(-1, syscall(MAX_SYSCALL_NUM - 1, [0, 0, 0]));
+    let info = TaskInfo::new();
+    assert_eq!(0, task_info(&info));
+    assert_eq!(1, info.syscall_times[MAX_SYSCALL_NUM - 1]);
+    assert_eq!(1, info.syscall_times[SYSCALL_TASK_INFO]);
+    let total: u32 = info.syscall_times.iter().sum();
+    assert_eq!(total, 2);
+    println!("Test syscall times bound OK!");
+    0
+}
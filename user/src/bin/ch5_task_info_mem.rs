@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, task_stats, TaskStats};
+
+/*
+理想结果：映射两段内存后 area_count 增加 2，rss_pages 增加映射的页数；
+解除映射后恢复原值。输出 Test task info mem OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = TaskStats::default();
+    assert_eq!(0, task_stats(&mut before));
+    assert!(before.area_count > 0);
+    assert!(before.rss_pages > 0);
+    // not adjacent, so they stay two areas
+    let start1: usize = 0x10000000;
+    let start2: usize = 0x20000000;
+    assert_eq!(0, mmap(start1, PAGE_SIZE * 2, 3));
+    assert_eq!(0, mmap(start2, PAGE_SIZE * 3, 3));
+    let mut after = TaskStats::default();
+    assert_eq!(0, task_stats(&mut after));
+    assert_eq!(after.area_count, before.area_count + 2);
+    assert_eq!(after.rss_pages, before.rss_pages + 5);
+    assert_eq!(0, munmap(start1, PAGE_SIZE * 2));
+    assert_eq!(0, munmap(start2, PAGE_SIZE * 3));
+    assert_eq!(0, task_stats(&mut after));
+    assert_eq!(after.area_count, before.area_count);
+    assert_eq!(after.rss_pages, before.rss_pages);
+    println!("Test task info mem OK!");
+    0
+}
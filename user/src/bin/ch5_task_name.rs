@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, list_procs, set_task_name, waitpid, ProcEntry};
+
+/*
+理想结果：进程名默认为应用名，设置后在进程列表中可见，过长的名字被截断为 16 字节，
+fork 出的子进程继承父进程的名字。输出 Test task name OK!
+*/
+
+const MAX_PROCS: usize = 32;
+
+fn name_of(pid: usize) -> ProcEntry {
+    let mut procs = [ProcEntry::new(); MAX_PROCS];
+    let total = list_procs(&mut procs);
+    assert!(total >= 1 && total as usize <= MAX_PROCS);
+    *procs[..total as usize]
+        .iter()
+        .find(|p| p.pid == pid)
+        .unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let me = getpid() as usize;
+    assert_eq!(name_of(me).name(), "ch5_task_name");
+    assert_eq!(set_task_name("worker\0"), 0);
+    assert_eq!(name_of(me).name(), "worker");
+    // cut to 16 bytes
+    assert_eq!(set_task_name("a_rather_long_task_name\0"), 0);
+    assert_eq!(name_of(me).name(), "a_rather_long_ta");
+    assert_eq!(set_task_name("parent\0"), 0);
+    let pid = fork();
+    if pid == 0 {
+        let child = getpid() as usize;
+        assert_eq!(name_of(child).name(), "parent");
+        set_task_name("child\0");
+        assert_eq!(name_of(child).name(), "child");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // the child renamed only itself
+    assert_eq!(name_of(me).name(), "parent");
+    println!("Test task name OK!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{exit, thread_create, yield_};
+
+/*
+理想结果：两个线程共享地址空间，各自给同一个计数器加 ROUNDS 次，
+主线程看到计数器为 2 * ROUNDS。输出 Test thread OK!
+*/
+
+const ROUNDS: usize = 1000;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+
+fn worker(step: usize) -> ! {
+    for _ in 0..ROUNDS {
+        COUNTER.fetch_add(step, Ordering::SeqCst);
+    }
+    DONE.fetch_add(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let tid1 = thread_create(worker as usize, 1);
+    let tid2 = thread_create(worker as usize, 1);
+    assert!(tid1 > 0 && tid2 > 0 && tid1 != tid2);
+    while DONE.load(Ordering::SeqCst) < 2 {
+        yield_();
+    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2 * ROUNDS);
+    println!("Test thread OK!");
+    0
+}
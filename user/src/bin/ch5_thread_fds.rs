@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use user_lib::{close, exit, fork, pipe, read, thread_create, waitpid, write, yield_};
+
+/*
+理想结果：线程创建的管道对主线程可见，主线程关闭写端后读到 EOF；
+线程 fork 出的子进程由主线程的 waitpid 回收。输出 Test thread fds OK!
+*/
+
+static READ_FD: AtomicUsize = AtomicUsize::new(0);
+static WRITE_FD: AtomicUsize = AtomicUsize::new(0);
+static CHILD: AtomicIsize = AtomicIsize::new(0);
+static DONE: AtomicUsize = AtomicUsize::new(0);
+
+fn worker() -> ! {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    READ_FD.store(pipe_fd[0], Ordering::SeqCst);
+    WRITE_FD.store(pipe_fd[1], Ordering::SeqCst);
+    let pid = fork();
+    if pid == 0 {
+        exit(42);
+    }
+    CHILD.store(pid, Ordering::SeqCst);
+    DONE.store(1, Ordering::SeqCst);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert!(thread_create(worker as usize, 0) > 0);
+    while DONE.load(Ordering::SeqCst) == 0 {
+        yield_();
+    }
+    // the child of the thread is a child of the process
+    let pid = CHILD.load(Ordering::SeqCst);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 42);
+    // the fds of the thread are those of the process
+    let (read_fd, write_fd) = (
+        READ_FD.load(Ordering::SeqCst),
+        WRITE_FD.load(Ordering::SeqCst),
+    );
+    assert_eq!(write(write_fd, b"hi"), 2);
+    let mut buf = [0u8; 4];
+    assert_eq!(read(read_fd, &mut buf), 2);
+    assert_eq!(&buf[..2], b"hi");
+    assert_eq!(close(write_fd), 0);
+    assert_eq!(read(read_fd, &mut buf), 0);
+    println!("Test thread fds OK!");
+    0
+}
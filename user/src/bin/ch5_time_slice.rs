@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::write_volatile;
+use user_lib::{exit, fork, set_priority, task_stats, waitpid, TaskStats};
+
+/*
+理想结果：高优先级进程的时间片更长，完成同样的计算被换出的次数少于低优先级进程。
+输出 Test time slice OK!
+*/
+
+const WORK: usize = 1 << 24;
+
+fn switch_count() -> usize {
+    let mut stats = TaskStats::default();
+    assert_eq!(0, task_stats(&mut stats));
+    stats.switch_count
+}
+
+fn work(prio: isize) -> ! {
+    set_priority(prio);
+    let before = switch_count();
+    let mut i: usize = 0;
+    while i < WORK {
+        unsafe {
+            write_volatile(&mut i, i + 1);
+        }
+    }
+    exit((switch_count() - before) as i32);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let high = fork();
+    if high == 0 {
+        work(128);
+    }
+    let low = fork();
+    if low == 0 {
+        work(16);
+    }
+    let mut high_switches: i32 = 0;
+    let mut low_switches: i32 = 0;
+    assert_eq!(waitpid(high as usize, &mut high_switches), high);
+    assert_eq!(waitpid(low as usize, &mut low_switches), low);
+    println!(
+        "switched in: priority 128 {}, priority 16 {}",
+        high_switches, low_switches
+    );
+    assert!(high_switches < low_switches);
+    println!("Test time slice OK!");
+    0
+}
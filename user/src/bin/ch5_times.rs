@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::write_volatile;
+use user_lib::{exit, fork, get_time, times, waitpid, yield_, Tms};
+
+/*
+理想结果：一直计算的子进程的用户态时间多于一直让出 CPU 的子进程，
+回收后父进程的 cutime 与 cstime 包含两个子进程的时间。输出 Test times OK!
+*/
+
+const RUN_MS: isize = 200;
+
+fn spin() -> ! {
+    let start = get_time();
+    let mut x: usize = 1;
+    while get_time() - start < RUN_MS {
+        for _ in 0..10000 {
+            // volatile to keep the loop from being optimized away
+            unsafe {
+                write_volatile(&mut x, x.wrapping_mul(6364136223846793005).wrapping_add(1));
+            }
+        }
+    }
+    let mut tms = Tms::new();
+    assert_eq!(0, times(&mut tms));
+    exit((tms.utime / 1000) as i32);
+}
+
+fn yield_loop() -> ! {
+    let start = get_time();
+    while get_time() - start < RUN_MS {
+        yield_();
+    }
+    let mut tms = Tms::new();
+    assert_eq!(0, times(&mut tms));
+    exit((tms.utime / 1000) as i32);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut tms = Tms::new();
+    assert_eq!(0, times(&mut tms));
+    assert_eq!(tms.cutime, 0);
+    assert_eq!(tms.cstime, 0);
+    let spinner = fork();
+    if spinner == 0 {
+        spin();
+    }
+    let yielder = fork();
+    if yielder == 0 {
+        yield_loop();
+    }
+    let mut spin_ms: i32 = 0;
+    let mut yield_ms: i32 = 0;
+    assert_eq!(waitpid(spinner as usize, &mut spin_ms), spinner);
+    assert_eq!(waitpid(yielder as usize, &mut yield_ms), yielder);
+    println!("user time: spin {}ms, yield {}ms", spin_ms, yield_ms);
+    assert!(spin_ms > yield_ms);
+    assert_eq!(0, times(&mut tms));
+    assert!(tms.cutime / 1000 >= (spin_ms + yield_ms) as usize);
+    assert!(tms.cstime > 0);
+    println!("Test times OK!");
+    0
+}
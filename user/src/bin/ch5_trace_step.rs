@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, getppid, kill, process_vm_read, sched_deterministic, trace_regs,
+    trace_step, waitpid, yield_, TrapContext, SIGKILL, SYSCALL_EXIT, SYSCALL_GETPID, SYSCALL_YIELD,
+};
+
+/*
+理想结果：跟踪者每一步都让被跟踪的子进程停在下一个系统调用前，依次观察到 getpid、yield、exit，
+停下时能读到子进程的 trap 上下文，sepc 处是 ecall 指令；子进程退出后单步返回 -1。
+停下的子进程可以被杀死，不能跟踪非后代进程。输出 Test trace step OK!
+*/
+
+const ECALL: u32 = 0x73;
+
+/// Read the trap context of the stopped `pid` and check what it stopped at
+fn check_stop(pid: usize, syscall_id: usize) -> TrapContext {
+    let mut cx = TrapContext::default();
+    assert_eq!(
+        trace_regs(pid, &mut cx),
+        core::mem::size_of::<TrapContext>() as isize
+    );
+    assert_eq!(cx.x[17], syscall_id);
+    let mut insn = [0u8; 4];
+    assert_eq!(process_vm_read(pid, cx.sepc, &mut insn), 4);
+    assert_eq!(u32::from_le_bytes(insn), ECALL);
+    cx
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the child only runs once the parent blocks in trace_step
+    let was_deterministic = sched_deterministic(true);
+    let pid = fork();
+    if pid == 0 {
+        getpid();
+        yield_();
+        exit(3);
+    }
+    let pid = pid as usize;
+    // not stopped yet
+    assert_eq!(trace_regs(pid, &mut TrapContext::default()), -1);
+    assert_eq!(trace_step(pid), SYSCALL_GETPID as isize);
+    check_stop(pid, SYSCALL_GETPID);
+    assert_eq!(trace_step(pid), SYSCALL_YIELD as isize);
+    check_stop(pid, SYSCALL_YIELD);
+    assert_eq!(trace_step(pid), SYSCALL_EXIT as isize);
+    assert_eq!(check_stop(pid, SYSCALL_EXIT).x[10], 3);
+    // it exits on this step
+    assert_eq!(trace_step(pid), -1);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(exit_code, 3);
+
+    // killed while stopped, it never makes the syscall
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            yield_();
+        }
+    }
+    let pid = pid as usize;
+    assert_eq!(trace_step(pid), SYSCALL_YIELD as isize);
+    assert_eq!(kill(pid, SIGKILL), 0);
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(exit_code, -9);
+
+    assert_eq!(trace_step(getpid() as usize), -1);
+    assert_eq!(trace_step(getppid() as usize), -1);
+    sched_deterministic(was_deterministic);
+    println!("Test trace step OK!");
+    0
+}
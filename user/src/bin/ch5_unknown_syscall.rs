@@ -0,0 +1,24 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getpid, syscall};
+
+/*
+理想结果：未知的系统调用号返回 -1，内核不会 panic，进程继续运行。
+输出 Test unknown syscall OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // unused but inside syscall_times
+    assert_eq!(-1, syscall(499, [0, 0, 0]));
+    // beyond syscall_times
+    assert_eq!(-1, syscall(100000, [0, 0, 0]));
+    assert_eq!(-1, syscall(usize::MAX, [0, 0, 0]));
+    assert!(getpid() >= 0);
+    println!("Test unknown syscall OK!");
+    0
+}
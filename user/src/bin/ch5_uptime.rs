@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, uptime, yield_};
+
+/*
+理想结果：时钟中断计数单调不减，让出处理器运行 100ms 后至少增加数次，
+大致与流逝的时间相符（每秒 100 次）。输出 Test uptime OK!
+*/
+
+const TICKS_PER_SEC: u64 = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start_ticks = uptime();
+    let start = get_time();
+    let mut last = start_ticks;
+    while get_time() - start < 100 {
+        yield_();
+        let now = uptime();
+        assert!(now >= last);
+        last = now;
+    }
+    let ticks = uptime() - start_ticks;
+    let elapsed_ms = (get_time() - start) as u64;
+    println!("{} ticks in {}ms", ticks, elapsed_ms);
+    assert!(ticks >= 3);
+    // never more than one a tick period
+    assert!(ticks <= elapsed_ms * TICKS_PER_SEC / 1000 + 1);
+    println!("Test uptime OK!");
+    0
+}
@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{brk, mmap, vm_dump, MAP_SHARED};
+
+/*
+理想结果：打印出各个内存区域，堆、mmap 和共享映射分别带有 heap、mmap、shared 的名字，
+权限与映射时一致。输出 Test vm dump OK!
+*/
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let heap = brk(0) as usize;
+    assert_eq!(brk(heap + PAGE_SIZE), (heap + PAGE_SIZE) as isize);
+    assert_eq!(0, mmap(START, PAGE_SIZE, 3));
+    assert_eq!(0, mmap(START + 2 * PAGE_SIZE, PAGE_SIZE, 1 | MAP_SHARED));
+    let mut buf = [0u8; 1024];
+    let len = vm_dump(&mut buf);
+    assert!(len > 0 && (len as usize) <= buf.len());
+    let dump = core::str::from_utf8(&buf[..len as usize]).unwrap();
+    let expected = [
+        format!("{:#x}-{:#x} rw-u heap\n", heap, heap + PAGE_SIZE),
+        format!("{:#x}-{:#x} rw-u mmap\n", START, START + PAGE_SIZE),
+        format!(
+            "{:#x}-{:#x} r--u shared\n",
+            START + 2 * PAGE_SIZE,
+            START + 3 * PAGE_SIZE
+        ),
+    ];
+    for line in expected.iter() {
+        assert!(dump.contains(line.as_str()));
+    }
+    assert!(dump.contains(" user stack\n"));
+    // the program itself comes first
+    assert!(dump.lines().next().unwrap().ends_with(" elf"));
+    // a short buffer only gets the head of the dump
+    let mut short = [0u8; 8];
+    assert_eq!(vm_dump(&mut short), len);
+    assert_eq!(&short, &buf[..8]);
+    println!("Test vm dump OK!");
+    0
+}
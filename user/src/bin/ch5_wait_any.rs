@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sleep_blocking, task_info, task_stats, wait_any, TaskInfo, TaskStats};
+
+/*
+理想结果：父进程阻塞等待先后退出的两个子进程，按退出顺序回收它们，期间只调用两次 waitpid 且不调用 yield；
+没有子进程时立即返回 -1。输出 Test wait any OK!
+*/
+
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_WAITPID: usize = 260;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code: i32 = 0;
+    assert_eq!(wait_any(&mut exit_code), -1);
+    let first = fork();
+    if first == 0 {
+        sleep_blocking(50);
+        exit(1);
+    }
+    let second = fork();
+    if second == 0 {
+        sleep_blocking(200);
+        exit(2);
+    }
+    let before = TaskInfo::new();
+    let mut before_stats = TaskStats::default();
+    assert_eq!(0, task_info(&before));
+    assert_eq!(0, task_stats(&mut before_stats));
+    assert_eq!(wait_any(&mut exit_code), first);
+    assert_eq!(exit_code, 1);
+    assert_eq!(wait_any(&mut exit_code), second);
+    assert_eq!(exit_code, 2);
+    let after = TaskInfo::new();
+    let mut after_stats = TaskStats::default();
+    assert_eq!(0, task_info(&after));
+    assert_eq!(0, task_stats(&mut after_stats));
+    // blocked in the kernel all along
+    assert_eq!(
+        after.syscall_times[SYSCALL_WAITPID],
+        before.syscall_times[SYSCALL_WAITPID] + 2
+    );
+    assert_eq!(
+        after.syscall_times[SYSCALL_YIELD],
+        before.syscall_times[SYSCALL_YIELD]
+    );
+    assert!(after_stats.switch_count - before_stats.switch_count <= 8);
+    assert_eq!(wait_any(&mut exit_code), -1);
+    println!("Test wait any OK!");
+    0
+}
@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, kill, waitpid, waitpid_status, wexitstatus, wifexited, wifsignaled, wtermsig,
+    yield_, SIGKILL,
+};
+
+/*
+理想结果：exit(7) 的子进程的等待状态为正常退出、退出码 7；被 SIGKILL 杀死的子进程的
+等待状态为被信号 9 终止。waitpid 仍然给出 7 和 -9。输出 Test wait status OK!
+*/
+
+fn spin() -> ! {
+    loop {
+        yield_();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    let mut status: i32 = 0;
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert_eq!(status, 7 << 8);
+    assert!(wifexited(status) && !wifsignaled(status));
+    assert_eq!(wexitstatus(status), 7);
+
+    let pid = fork();
+    if pid == 0 {
+        spin();
+    }
+    yield_();
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert_eq!(status, SIGKILL);
+    assert!(wifsignaled(status) && !wifexited(status));
+    assert_eq!(wtermsig(status), SIGKILL);
+
+    // exit codes are only 8 bits wide in WEXITSTATUS
+    let pid = fork();
+    if pid == 0 {
+        exit(-1);
+    }
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert!(wifexited(status));
+    assert_eq!(wexitstatus(status), 255);
+
+    // waitpid keeps giving the exit code, or the negated signal number
+    for (code, killed) in [(7, false), (-9, true)] {
+        let pid = fork();
+        if pid == 0 {
+            if killed {
+                spin();
+            }
+            exit(code);
+        }
+        if killed {
+            yield_();
+            kill(pid as usize, SIGKILL);
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, code);
+    }
+    println!("Test wait status OK!");
+    0
+}
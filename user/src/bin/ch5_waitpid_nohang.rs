@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, waitpid, waitpid_nohang, yield_};
+
+/*
+理想结果：子进程仍在运行时带 WNOHANG 的 waitpid 立即返回 0，子进程退出后返回其 pid；
+不存在的子进程返回 -1。输出 Test waitpid nohang OK!
+*/
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        let start = get_time();
+        while get_time() < start + 500 {
+            yield_();
+        }
+        exit(3);
+    }
+    let mut exit_code: i32 = 0;
+    let start = get_time();
+    assert_eq!(waitpid_nohang(pid as usize, &mut exit_code), 0);
+    assert!(get_time() - start < 100);
+    loop {
+        match waitpid_nohang(pid as usize, &mut exit_code) {
+            0 => {
+                yield_();
+            }
+            n => {
+                assert_eq!(n, pid);
+                break;
+            }
+        }
+    }
+    assert_eq!(exit_code, 3);
+    assert_eq!(waitpid_nohang(pid as usize, &mut exit_code), -1);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), -1);
+    println!("Test waitpid nohang OK!");
+    0
+}
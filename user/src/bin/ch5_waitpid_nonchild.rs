@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getpid, getppid, sleep, sys_waitpid_options, waitpid, WBLOCK,
+};
+
+/*
+理想结果：以阻塞方式等待自己、父进程、兄弟进程或不存在的 pid 时立即返回 -1，不会永久阻塞；
+兄弟进程之后仍能被父进程正常回收。输出 Test waitpid nonchild OK!
+*/
+
+/// How long the sibling lives
+const SIBLING_MS: usize = 200;
+
+fn wait_blocking(pid: isize) -> isize {
+    let mut status: i32 = 0;
+    sys_waitpid_options(pid, &mut status as *mut _, WBLOCK)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = get_time();
+    assert_eq!(wait_blocking(getpid()), -1);
+    assert_eq!(wait_blocking(getppid()), -1);
+    assert_eq!(wait_blocking(9999), -1);
+    // no child at all
+    assert_eq!(wait_blocking(-1), -1);
+
+    let sibling = fork();
+    if sibling == 0 {
+        sleep(SIBLING_MS);
+        exit(3);
+    }
+    let waiter = fork();
+    if waiter == 0 {
+        // the sibling is alive, but it is not a child of this one
+        assert_eq!(wait_blocking(sibling), -1);
+        assert_eq!(wait_blocking(getpid()), -1);
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(waiter as usize, &mut exit_code), waiter);
+    assert_eq!(exit_code, 0);
+    // the waiter came back long before the sibling exits
+    assert!(get_time() - start < SIBLING_MS as isize / 2);
+    assert_eq!(waitpid(sibling as usize, &mut exit_code), sibling);
+    assert_eq!(exit_code, 3);
+    println!("Test waitpid nonchild OK!");
+    0
+}
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, kill, sys_waitpid, waitpid_status, wexitstatus, yield_, SIGKILL};
+
+/*
+理想结果：不带 WSTATUS 的 sys_waitpid 原样给出退出码 66778 与 -233，被 SIGKILL 杀死的子进程为 -9；
+带 WSTATUS 时 66778 只剩低 8 位。输出 Test waitpid raw OK!
+*/
+
+/// Wait the way the lab test programs do, polling without any option
+fn wait_raw(pid: isize) -> i32 {
+    let mut exit_code: i32 = 0;
+    loop {
+        match sys_waitpid(pid, &mut exit_code) {
+            -2 => {
+                yield_();
+            }
+            n => {
+                assert_eq!(n, pid);
+                return exit_code;
+            }
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for code in [66778, -233, 0] {
+        let pid = fork();
+        if pid == 0 {
+            exit(code);
+        }
+        assert_eq!(wait_raw(pid), code);
+    }
+
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            yield_();
+        }
+    }
+    yield_();
+    assert_eq!(kill(pid as usize, SIGKILL), 0);
+    assert_eq!(wait_raw(pid), -SIGKILL);
+
+    let pid = fork();
+    if pid == 0 {
+        exit(66778);
+    }
+    let mut status: i32 = 0;
+    assert_eq!(waitpid_status(pid as usize, &mut status), pid);
+    assert_eq!(status, (66778 & 0xff) << 8);
+    assert_eq!(wexitstatus(status), 66778 & 0xff);
+    println!("Test waitpid raw OK!");
+    0
+}
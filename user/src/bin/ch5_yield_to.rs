@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, mmap, waitpid, yield_, yield_to, MAP_SHARED};
+
+/*
+理想结果：让出给指定的子进程后，它是下一个运行的进程；目标不在就绪队列中时返回 -1。
+输出 Test yield to OK!
+*/
+
+const CHILDREN: usize = 4;
+
+/// Everyone may log once `go` is this
+const ALL: usize = usize::MAX;
+
+/// Shared with the children: the child `go`, then all of them, log their
+/// pids in the order they run at `log[..count]`
+#[repr(C)]
+struct Shared {
+    go: usize,
+    count: usize,
+    log: [usize; CHILDREN],
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start: usize = 0x10000000;
+    assert_eq!(0, mmap(start, 4096, 3 | MAP_SHARED));
+    let shared = unsafe { &mut *(start as *mut Shared) };
+    let mut pids = [0usize; CHILDREN];
+    for pid in pids.iter_mut() {
+        let ret = fork();
+        if ret == 0 {
+            // stay ready until it is its turn
+            let me = getpid() as usize;
+            loop {
+                let go = unsafe { core::ptr::read_volatile(&shared.go) };
+                if go == me || go == ALL {
+                    break;
+                }
+                yield_();
+            }
+            shared.log[shared.count] = me;
+            shared.count += 1;
+            unsafe { core::ptr::write_volatile(&mut shared.go, ALL) };
+            exit(0);
+        }
+        *pid = ret as usize;
+    }
+    // let every child start spinning
+    for _ in 0..CHILDREN {
+        yield_();
+    }
+    assert_eq!(yield_to(getpid() as usize), -1);
+    assert_eq!(yield_to(0x7fff_ffff), -1);
+    // the last child runs before any of the others
+    let target = pids[CHILDREN - 1];
+    unsafe { core::ptr::write_volatile(&mut shared.go, target) };
+    assert_eq!(yield_to(target), 0);
+    for pid in pids.iter() {
+        let mut exit_code: i32 = -1;
+        assert_eq!(waitpid(*pid, &mut exit_code), *pid as isize);
+        assert_eq!(exit_code, 0);
+    }
+    assert_eq!(shared.count, CHILDREN);
+    assert_eq!(shared.log[0], target);
+    // an exited child is not ready any more
+    assert_eq!(yield_to(target), -1);
+    println!("Test yield to OK!");
+    0
+}
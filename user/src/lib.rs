@@ -16,6 +16,7 @@ extern crate bitflags;
 use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
 pub use console::{flush, STDIN, STDOUT};
+use core::sync::atomic::AtomicU32;
 pub use syscall::*;
 
 const USER_HEAP_SIZE: usize = 16384;
@@ -44,31 +45,52 @@ fn clear_bss() {
     }
 }
 
+/// The null-terminated array of `KEY=VALUE` strings the program was started
+/// with, 0 for none
+static mut ENVP: usize = 0;
+
+/// The string at `str_start`, up to its NUL
+fn c_str(str_start: usize) -> &'static str {
+    let len = (0usize..)
+        .find(|i| unsafe { ((str_start + *i) as *const u8).read_volatile() == 0 })
+        .unwrap();
+    core::str::from_utf8(unsafe { core::slice::from_raw_parts(str_start as *const u8, len) })
+        .unwrap()
+}
+
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub extern "C" fn _start(argc: usize, argv: usize) -> ! {
+pub extern "C" fn _start(argc: usize, argv: usize, envp: usize) -> ! {
     clear_bss();
     unsafe {
         HEAP.lock()
             .init(HEAP_SPACE.as_ptr() as usize, USER_HEAP_SIZE);
+        ENVP = envp;
     }
     let mut v: Vec<&'static str> = Vec::new();
     for i in 0..argc {
         let str_start =
             unsafe { ((argv + i * core::mem::size_of::<usize>()) as *const usize).read_volatile() };
-        let len = (0usize..)
-            .find(|i| unsafe { ((str_start + *i) as *const u8).read_volatile() == 0 })
-            .unwrap();
-        v.push(
-            core::str::from_utf8(unsafe {
-                core::slice::from_raw_parts(str_start as *const u8, len)
-            })
-            .unwrap(),
-        );
+        v.push(c_str(str_start));
     }
     exit(main(argc, v.as_slice()));
 }
 
+/// The value of the environment variable `key`, None if it is not set
+pub fn getenv(key: &str) -> Option<&'static str> {
+    let envp = unsafe { ENVP };
+    if envp == 0 {
+        return None;
+    }
+    (0..)
+        .map(|i| unsafe {
+            ((envp + i * core::mem::size_of::<usize>()) as *const usize).read_volatile()
+        })
+        .take_while(|str_start| *str_start != 0)
+        .map(c_str)
+        .find_map(|env| env.strip_prefix(key)?.strip_prefix('='))
+}
+
 #[linkage = "weak"]
 #[no_mangle]
 fn main(_argc: usize, _argv: &[&str]) -> i32 {
@@ -98,12 +120,42 @@ impl TimeVal {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+impl TimeSpec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// CPU times in microseconds, the c* fields are those of the reaped children
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Tms {
+    pub utime: usize,
+    pub stime: usize,
+    pub cutime: usize,
+    pub cstime: usize,
+}
+
+impl Tms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TaskStatus {
     UnInit,
     Ready,
     Running,
     Exited,
+    Blocking,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -114,6 +166,59 @@ pub struct SyscallInfo {
 
 const MAX_SYSCALL_NUM: usize = 500;
 
+/// One process reported by `list_procs`, keep the layout in sync with the kernel
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ProcEntry {
+    pub pid: usize,
+    /// 0 if the parent has exited
+    pub ppid: usize,
+    pub status: TaskStatus,
+    pub priority: usize,
+    /// Name of the task, padded with NULs
+    pub name: [u8; TASK_NAME_LEN],
+}
+
+/// Task names are cut to this many bytes
+pub const TASK_NAME_LEN: usize = 16;
+
+impl ProcEntry {
+    pub fn new() -> Self {
+        ProcEntry {
+            pid: 0,
+            ppid: 0,
+            status: TaskStatus::UnInit,
+            priority: 0,
+            name: [0; TASK_NAME_LEN],
+        }
+    }
+
+    /// The name without the padding
+    pub fn name(&self) -> &str {
+        let len = self
+            .name
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(TASK_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+}
+
+/// Stride scheduling state of a task, keep the layout in sync with the kernel
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SchedInfo {
+    pub priority: usize,
+    pub stride: u64,
+    /// What `stride` grows by each time the task runs, `BIG_STRIDE / priority`
+    pub pass: u64,
+    pub switch_count: usize,
+    /// Scheduling round the task last ran in
+    pub last_run_tick: usize,
+}
+
+/// Keep the layout in sync with the kernel
+#[repr(C)]
 #[derive(Debug)]
 pub struct TaskInfo {
     pub status: TaskStatus,
@@ -131,6 +236,23 @@ impl TaskInfo {
     }
 }
 
+/// Statistics of the task and its address space from `task_stats`, keep the
+/// layout in sync with the kernel
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TaskStats {
+    /// Pages backed by a frame of their own
+    pub rss_pages: usize,
+    /// Mapped areas
+    pub area_count: usize,
+    /// Pages backed by a frame other address spaces map as well
+    pub shared_pages: usize,
+    /// Frames holding the page table
+    pub page_table_frames: usize,
+    /// Times the task has been switched in
+    pub switch_count: usize,
+}
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct Stat {
@@ -224,6 +346,46 @@ pub fn yield_() -> isize {
     sys_yield()
 }
 
+/// Give the processor to the ready task `pid`, which runs next. Return -1 if
+/// it is not ready to run.
+pub fn yield_to(pid: usize) -> isize {
+    sys_yield_to(pid)
+}
+
+/// Let the task `pid`, this one for 0, run only on the harts in `mask`.
+/// There is only hart 0, a task without it in its mask does not run.
+pub fn sched_setaffinity(pid: usize, mask: usize) -> isize {
+    sys_sched_setaffinity(pid, mask)
+}
+
+/// The cpu affinity mask of the task `pid`, this one for 0
+pub fn sched_getaffinity(pid: usize) -> isize {
+    sys_sched_getaffinity(pid)
+}
+
+/// Turn timer preemption off if `enable`, so tasks only switch when they
+/// yield, block or exit, or on again. Return whether it was off already.
+pub fn sched_deterministic(enable: bool) -> bool {
+    sys_sched_deterministic(enable) == 1
+}
+
+/// The stride scheduler, the default
+pub const SCHED_STRIDE: usize = 0;
+/// First in first out, ignoring the priorities
+pub const SCHED_RR: usize = 1;
+
+/// Schedule with `policy`, return the one used before
+pub fn sched_policy(policy: usize) -> usize {
+    sys_sched_policy(policy) as usize
+}
+
+/// Timer ticks since boot, 100 a second
+pub fn uptime() -> u64 {
+    let mut ticks = 0;
+    sys_uptime(&mut ticks);
+    ticks
+}
+
 pub fn get_time() -> isize {
     let time = TimeVal::new();
     match sys_get_time(&time, 0) {
@@ -232,22 +394,281 @@ pub fn get_time() -> isize {
     }
 }
 
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
+
+pub fn clock_gettime(clk_id: usize, time: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clk_id, time)
+}
+
+pub fn times(tms: &mut Tms) -> isize {
+    sys_times(tms)
+}
+
+/// `resource` of prlimit: seconds of cpu time, SIGXCPU comes beyond the soft
+/// limit once a second and SIGKILL beyond the hard one
+pub const RLIMIT_CPU: usize = 0;
+/// `resource` of prlimit: the children not reaped yet, fork and spawn fail
+/// beyond it
+pub const RLIMIT_NPROC: usize = 6;
+/// `resource` of prlimit: the bytes the address space covers, mmap, mremap
+/// and brk fail beyond it
+pub const RLIMIT_AS: usize = 9;
+/// No limit
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// Soft and hard limit of a resource, keep the layout in sync with the kernel
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Rlimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+/// Get the limits of `resource` into `old` and then set them to `new`. The
+/// soft limit can be raised up to the hard one, which can only be lowered.
+pub fn prlimit(resource: usize, new: Option<&Rlimit>, old: Option<&mut Rlimit>) -> isize {
+    sys_prlimit(
+        resource,
+        new.map_or(core::ptr::null(), |new| new as *const Rlimit),
+        old.map_or(core::ptr::null_mut(), |old| old as *mut Rlimit),
+    )
+}
+
+/// Fill `buf` with the bytes at `remote_addr` of the descendant `pid`
+pub fn process_vm_read(pid: usize, remote_addr: usize, buf: &mut [u8]) -> isize {
+    sys_process_vm_read(pid, remote_addr, buf)
+}
+
+/// Where `process_vm_read` finds the trap context of a stopped main thread
+pub const TRAP_CONTEXT: usize = usize::MAX - 2 * 4096 + 1;
+
+/// Registers saved on a trap, keep the layout in sync with the kernel
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TrapContext {
+    pub x: [usize; 32],
+    pub sstatus: usize,
+    pub sepc: usize,
+    pub kernel_satp: usize,
+    pub kernel_sp: usize,
+    pub trap_handler: usize,
+}
+
+/// Let the descendant `pid` run until it is about to make a syscall and
+/// return the id of that syscall, it makes it on the next step
+pub fn trace_step(pid: usize) -> isize {
+    sys_trace_step(pid)
+}
+
+/// Read the trap context of `pid` while `trace_step` has it stopped
+pub fn trace_regs(pid: usize, cx: &mut TrapContext) -> isize {
+    let bytes = unsafe {
+        core::slice::from_raw_parts_mut(
+            cx as *mut TrapContext as *mut u8,
+            core::mem::size_of::<TrapContext>(),
+        )
+    };
+    sys_process_vm_read(pid, TRAP_CONTEXT, bytes)
+}
+
+/// `who` of getrusage
+pub const RUSAGE_SELF: isize = 0;
+/// The reaped children and their own children
+pub const RUSAGE_CHILDREN: isize = -1;
+
+/// Resource usage, times in microseconds, keep the layout in sync with the
+/// kernel
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Rusage {
+    pub utime: usize,
+    pub stime: usize,
+    /// The most pages resident at once, for the children the highest of theirs
+    pub maxrss: usize,
+    /// Page faults resolved without I/O: lazy allocation, copy-on-write and
+    /// stack growth
+    pub minflt: usize,
+    /// Always 0, a page read back from swap is a minor fault as the swap
+    /// area is memory
+    pub majflt: usize,
+}
+
+pub fn getrusage(who: isize, usage: &mut Rusage) -> isize {
+    sys_getrusage(who, usage)
+}
+
+pub const SIGHUP: i32 = 1;
+pub const SIGINT: i32 = 2;
+pub const SIGQUIT: i32 = 3;
+pub const SIGILL: i32 = 4;
+pub const SIGTRAP: i32 = 5;
+pub const SIGABRT: i32 = 6;
+pub const SIGBUS: i32 = 7;
+pub const SIGFPE: i32 = 8;
+pub const SIGKILL: i32 = 9;
+pub const SIGUSR1: i32 = 10;
+pub const SIGSEGV: i32 = 11;
+pub const SIGUSR2: i32 = 12;
+pub const SIGPIPE: i32 = 13;
+pub const SIGALRM: i32 = 14;
+pub const SIGTERM: i32 = 15;
+pub const SIGSTKFLT: i32 = 16;
+pub const SIGCHLD: i32 = 17;
+pub const SIGCONT: i32 = 18;
+pub const SIGSTOP: i32 = 19;
+pub const SIGTSTP: i32 = 20;
+pub const SIGTTIN: i32 = 21;
+pub const SIGTTOU: i32 = 22;
+pub const SIGURG: i32 = 23;
+pub const SIGXCPU: i32 = 24;
+pub const SIGXFSZ: i32 = 25;
+pub const SIGVTALRM: i32 = 26;
+pub const SIGPROF: i32 = 27;
+pub const SIGWINCH: i32 = 28;
+pub const SIGIO: i32 = 29;
+pub const SIGPWR: i32 = 30;
+pub const SIGSYS: i32 = 31;
+
+pub fn kill(pid: usize, signum: i32) -> isize {
+    sys_kill(pid, signum)
+}
+
+/// Register `handler`, which has to end with `sigreturn`, for `signum`. A
+/// handler of 0 restores the default action. The previous handler goes to
+/// `old` if it is given.
+pub fn sigaction(signum: i32, handler: usize, old: Option<&mut usize>) -> isize {
+    let old = old.map_or(core::ptr::null_mut(), |old| old as *mut usize);
+    sys_sigaction(signum, handler, old)
+}
+
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// Change the signal mask as `how` says with the signals in bits of `set`,
+/// or only read it if `set` is None. The previous mask goes to `old` if it
+/// is given.
+pub fn sigprocmask(how: usize, set: Option<u64>, old: Option<&mut u64>) -> isize {
+    let set = set
+        .as_ref()
+        .map_or(core::ptr::null(), |set| set as *const u64);
+    let old = old.map_or(core::ptr::null_mut(), |old| old as *mut u64);
+    sys_sigprocmask(how, set, old)
+}
+
+pub fn sigreturn() -> isize {
+    sys_sigreturn()
+}
+
+/// The last fault the kernel could not resolve, keep the layout in sync
+/// with the kernel
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct FaultInfo {
+    /// The address accessed
+    pub addr: usize,
+    /// `scause` of the fault, 13 for a load and 15 for a store page fault
+    pub cause: usize,
+    pub is_write: bool,
+}
+
+/// Fill `info` with the last fault that was not resolved, the one a SIGSEGV
+/// handler runs for. Return -1 if there has been none.
+pub fn last_fault(info: &mut FaultInfo) -> isize {
+    sys_last_fault(info)
+}
+
+pub fn alarm(seconds: usize) -> isize {
+    sys_alarm(seconds)
+}
+
 pub fn getpid() -> isize {
     sys_getpid()
 }
 
+pub fn getppid() -> isize {
+    sys_getppid()
+}
+
 pub fn fork() -> isize {
     sys_fork()
 }
 
+/// Errors some syscalls return negated, as in Linux
+pub const EINTR: isize = 4;
+pub const EAGAIN: isize = 11;
+pub const ENOMEM: isize = 12;
+pub const EFAULT: isize = 14;
+pub const EMFILE: isize = 24;
+
+/// Fork, writing the pid of the child to `child_pid`, 0 in the child.
+/// Return 0, or `-EAGAIN` if there are too many tasks and `-ENOMEM` if the
+/// kernel runs out of frames.
+pub fn fork2(child_pid: &mut isize) -> isize {
+    sys_fork2(child_pid)
+}
+
 pub fn exec(path: &str, args: &[*const u8]) -> isize {
-    sys_exec(path, args)
+    sys_exec(path, args, &[core::ptr::null::<u8>()])
+}
+
+/// Like `exec`, with `envs` as the environment of the new program, a
+/// null-terminated array of `KEY=VALUE` strings
+pub fn execve(path: &str, args: &[*const u8], envs: &[*const u8]) -> isize {
+    sys_exec(path, args, envs)
 }
 
 pub fn set_priority(prio: isize) -> isize {
     sys_set_priority(prio)
 }
 
+pub fn get_priority() -> isize {
+    sys_getpriority()
+}
+
+/// Fill `info` with the scheduling state of process `pid`, or of the current
+/// task if `pid` is -1. Return -1 if there is no such process.
+pub fn task_sched_info(pid: isize, info: &mut SchedInfo) -> isize {
+    sys_task_sched_info(pid, info)
+}
+
+/// True if the child exited by itself, for a status from `waitpid_status`
+pub fn wifexited(status: i32) -> bool {
+    status & 0x7f == 0
+}
+
+/// The low 8 bits of the exit code of a child that exited by itself
+pub fn wexitstatus(status: i32) -> i32 {
+    (status >> 8) & 0xff
+}
+
+/// True if a signal killed the child
+pub fn wifsignaled(status: i32) -> bool {
+    status & 0x7f != 0
+}
+
+/// The signal that killed the child
+pub fn wtermsig(status: i32) -> i32 {
+    status & 0x7f
+}
+
+/// Like `waitpid`, but get the wait status for `wifexited` and friends
+pub fn waitpid_status(pid: usize, status: &mut i32) -> isize {
+    loop {
+        match sys_waitpid_options(pid as isize, status as *mut _, WSTATUS) {
+            -2 => {
+                sys_yield();
+            }
+            n => {
+                return n;
+            }
+        }
+    }
+}
+
+/// Wait for any child, `exit_code` gets its exit code or the negated number
+/// of the signal that killed it
 pub fn wait(exit_code: &mut i32) -> isize {
     loop {
         match sys_waitpid(-1, exit_code as *mut _) {
@@ -261,6 +682,8 @@ pub fn wait(exit_code: &mut i32) -> isize {
     }
 }
 
+/// Wait for the child `pid`, `exit_code` gets its exit code or the negated
+/// number of the signal that killed it
 pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     loop {
         match sys_waitpid(pid as isize, exit_code as *mut _) {
@@ -274,16 +697,53 @@ pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
     }
 }
 
+/// `options` bit of waitpid: return 0 at once if the child is still running
+pub const WNOHANG: usize = 1;
+/// `options` bit of waitpid: block until the child exits
+pub const WBLOCK: usize = 1 << 1;
+/// `options` bit of waitpid: get the wait status, see `waitpid_status`
+pub const WSTATUS: usize = 1 << 2;
+
+/// Block until any child exits, without spinning like `wait`. `exit_code`
+/// gets its exit code or the negated number of the signal that killed it.
+/// Return the pid of the child, or -1 if there is none.
+pub fn wait_any(exit_code: &mut i32) -> isize {
+    sys_waitpid_options(-1, exit_code as *mut _, WBLOCK)
+}
+
+pub fn waitpid_nohang(pid: usize, exit_code: &mut i32) -> isize {
+    sys_waitpid_options(pid as isize, exit_code as *mut _, WNOHANG)
+}
+
 pub fn sleep_blocking(sleep_ms: usize) {
     sys_sleep(sleep_ms);
 }
 
+/// Sleep for `req`, return 0, or `-EINTR` if a signal woke the task up
+/// early. The time left is written to `rem` either way, 0 after a full sleep.
+/// Return -1 for a bad or too long `req`.
+pub fn nanosleep(req: &TimeSpec, rem: Option<&mut TimeSpec>) -> isize {
+    sys_nanosleep(
+        req,
+        rem.map_or(core::ptr::null_mut(), |rem| rem as *mut TimeSpec),
+    )
+}
+
 pub fn sleep(period_ms: usize) {
     let start = get_time();
     while get_time() < start + period_ms as isize {
         sys_yield();
     }
 }
+/// `prot` bit of mmap: frames are only allocated on the first access
+pub const MAP_LAZY: usize = 1 << 8;
+/// `prot` bit of mmap: the frames stay shared with children after fork
+pub const MAP_SHARED: usize = 1 << 9;
+/// `prot` bit of mmap: map 2MiB megapages if the range is 2MiB-aligned at both ends
+pub const MAP_HUGE: usize = 1 << 10;
+/// `prot` bit of mmap: map every frame right away, the default unless `MAP_LAZY`
+pub const MAP_POPULATE: usize = 1 << 11;
+
 pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
     sys_mmap(start, len, prot)
 }
@@ -292,8 +752,111 @@ pub fn munmap(start: usize, len: usize) -> isize {
     sys_munmap(start, len)
 }
 
+/// Grow or shrink a mapping in place, keeping its contents. Return its
+/// unchanged start, or -1 if the pages right after it are taken.
+pub fn mremap(old_start: usize, old_len: usize, new_len: usize) -> isize {
+    sys_mremap(old_start, old_len, new_len)
+}
+
+/// Make mmap, munmap and the other calls taking a range reject a len that is
+/// not a whole number of pages if `enable`, or round it up again. Return
+/// whether they were strict already.
+pub fn mmap_strict(enable: bool) -> bool {
+    sys_mmap_strict(enable) == 1
+}
+
+/// `advice` of madvise: free the frames of the range, it stays mapped and
+/// reads as zero on the next access
+pub const MADV_DONTNEED: usize = 4;
+
+/// Return -1 if the range is not all mapped or the advice is unknown
+pub fn madvise(start: usize, len: usize, advice: usize) -> isize {
+    sys_madvise(start, len, advice)
+}
+
+/// Frames of the whole system
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct MemInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub used_frames: usize,
+    /// Pages of the swap area, memory that is not counted as frames
+    pub swap_pages: usize,
+    /// Pages swapped out
+    pub swap_used: usize,
+}
+
+pub fn meminfo(info: &mut MemInfo) -> isize {
+    sys_meminfo(info)
+}
+
+/// Time of the processor since boot, in microseconds
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct CpuStats {
+    pub total_us: usize,
+    /// Time with no task to run
+    pub idle_us: usize,
+}
+
+pub fn cpu_stats(stats: &mut CpuStats) -> isize {
+    sys_cpu_stats(stats)
+}
+
+/// Debug hook: the physical address `va` is mapped to, -1 if it is not mapped
+pub fn translate(va: usize) -> isize {
+    sys_translate(va)
+}
+
+/// Debug hook: queue `buf` as input to stdin, ahead of the console
+pub fn inject_input(buf: &[u8]) -> isize {
+    sys_inject_input(buf)
+}
+
+/// What the page table says about a page
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct PteInfo {
+    pub ppn: usize,
+    pub flags_v: bool,
+    pub flags_r: bool,
+    pub flags_w: bool,
+    pub flags_x: bool,
+    pub flags_u: bool,
+    /// the page is in a mapped area, though a lazy one may have no valid pte yet
+    pub present: bool,
+}
+
+/// Debug hook: the pte of `vaddr`, -1 if the page is not mapped at all
+pub fn query_pte(vaddr: usize, out: &mut PteInfo) -> isize {
+    sys_query_pte(vaddr, out)
+}
+
+/// Debug hook: print the memory areas, one `start-end perm name` line each,
+/// and copy as much of it as fits into `buf`. Return the length of the dump.
+pub fn vm_dump(buf: &mut [u8]) -> isize {
+    sys_vm_dump(buf)
+}
+
+pub fn mprotect(start: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(start, len, prot)
+}
+
+pub fn brk(new_brk: usize) -> isize {
+    sys_brk(new_brk)
+}
+
+/// `flags` bit of spawn_with: the child gets the priority of the caller
+/// rather than the default one, which `spawn` gives it
+pub const SPAWN_INHERIT_PRIO: usize = 1;
+
 pub fn spawn(path: &str) -> isize {
-    sys_spawn(path)
+    sys_spawn(path, 0)
+}
+
+pub fn spawn_with(path: &str, flags: usize) -> isize {
+    sys_spawn(path, flags)
 }
 
 pub fn dup(fd: usize) -> isize {
@@ -302,11 +865,58 @@ pub fn dup(fd: usize) -> isize {
 pub fn pipe(pipe_fd: &mut [usize]) -> isize {
     sys_pipe(pipe_fd)
 }
+/// The most fds the task can have open, `dup` and `pipe` fail with
+/// `-EMFILE` past it
+pub fn getdtablesize() -> isize {
+    sys_getdtablesize()
+}
 
 pub fn task_info(info: &TaskInfo) -> isize {
     sys_task_info(info)
 }
 
+pub fn task_stats(stats: &mut TaskStats) -> isize {
+    sys_task_stats(stats)
+}
+
+pub fn reset_task_info() -> isize {
+    sys_reset_task_info()
+}
+
+/// Fill `procs` with the processes that have not exited, return how many there
+/// are, which may be more than `procs.len()`.
+pub fn list_procs(procs: &mut [ProcEntry]) -> isize {
+    sys_list_procs(procs)
+}
+
+/// Fill `buf` with the names of the apps that can be spawned, one per line.
+/// Return the length of the whole list, which may be more than `buf.len()`.
+pub fn list_apps(buf: &mut [u8]) -> isize {
+    sys_list_apps(buf)
+}
+
+/// Rename the current task, `name` has to end with "\0". A name longer than
+/// `TASK_NAME_LEN` bytes is cut.
+pub fn set_task_name(name: &str) -> isize {
+    sys_set_task_name(name)
+}
+
+/// `op` of futex: block while the word is `val`
+pub const FUTEX_WAIT: usize = 0;
+/// `op` of futex: wake up to `val` waiters
+pub const FUTEX_WAKE: usize = 1;
+
+/// Block until `futex_wake` on `word` if it is still `val`, return
+/// `-EAGAIN` at once if it is not
+pub fn futex_wait(word: &AtomicU32, val: u32) -> isize {
+    sys_futex(word as *const AtomicU32 as *const u32, FUTEX_WAIT, val)
+}
+
+/// Wake up to `count` tasks blocked on `word`, return how many woke
+pub fn futex_wake(word: &AtomicU32, count: u32) -> isize {
+    sys_futex(word as *const AtomicU32 as *const u32, FUTEX_WAKE, count)
+}
+
 pub fn thread_create(entry: usize, arg: usize) -> isize {
     sys_thread_create(entry, arg)
 }
@@ -333,8 +943,11 @@ pub fn mutex_blocking_create() -> isize {
 pub fn mutex_lock(mutex_id: usize) -> isize {
     sys_mutex_lock(mutex_id)
 }
-pub fn mutex_unlock(mutex_id: usize) {
-    sys_mutex_unlock(mutex_id);
+pub fn mutex_unlock(mutex_id: usize) -> isize {
+    sys_mutex_unlock(mutex_id)
+}
+pub fn mutex_destroy(mutex_id: usize) -> isize {
+    sys_mutex_destroy(mutex_id)
 }
 pub fn semaphore_create(res_count: usize) -> isize {
     sys_semaphore_create(res_count)
@@ -354,6 +967,6 @@ pub fn condvar_create() -> isize {
 pub fn condvar_signal(condvar_id: usize) {
     sys_condvar_signal(condvar_id);
 }
-pub fn condvar_wait(condvar_id: usize, mutex_id: usize) {
-    sys_condvar_wait(condvar_id, mutex_id);
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    sys_condvar_wait(condvar_id, mutex_id)
 }
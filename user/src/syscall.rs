@@ -1,6 +1,6 @@
-use crate::TaskInfo;
+use crate::{CpuStats, FaultInfo, MemInfo, ProcEntry, PteInfo, SchedInfo, TaskInfo, TaskStats};
 
-use super::{Stat, TimeVal};
+use super::{Rlimit, Rusage, Stat, TimeSpec, TimeVal, Tms};
 
 pub const SYSCALL_OPENAT: usize = 56;
 pub const SYSCALL_CLOSE: usize = 57;
@@ -10,27 +10,69 @@ pub const SYSCALL_UNLINKAT: usize = 35;
 pub const SYSCALL_LINKAT: usize = 37;
 pub const SYSCALL_FSTAT: usize = 80;
 pub const SYSCALL_EXIT: usize = 93;
+pub const SYSCALL_FUTEX: usize = 98;
 pub const SYSCALL_SLEEP: usize = 101;
+pub const SYSCALL_CLOCK_GETTIME: usize = 113;
+pub const SYSCALL_SCHED_SETAFFINITY: usize = 122;
+pub const SYSCALL_SCHED_GETAFFINITY: usize = 123;
 pub const SYSCALL_YIELD: usize = 124;
+pub const SYSCALL_KILL: usize = 129;
+pub const SYSCALL_SIGACTION: usize = 134;
+pub const SYSCALL_SIGPROCMASK: usize = 135;
+pub const SYSCALL_SIGRETURN: usize = 139;
+pub const SYSCALL_TIMES: usize = 153;
+pub const SYSCALL_GETRUSAGE: usize = 165;
 pub const SYSCALL_GETTIMEOFDAY: usize = 169;
 pub const SYSCALL_GETPID: usize = 172;
+pub const SYSCALL_GETPPID: usize = 173;
 pub const SYSCALL_GETTID: usize = 178;
 pub const SYSCALL_FORK: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
 pub const SYSCALL_WAITPID: usize = 260;
 pub const SYSCALL_SET_PRIORITY: usize = 140;
+pub const SYSCALL_GETPRIORITY: usize = 141;
 pub const SYSCALL_MUNMAP: usize = 215;
+pub const SYSCALL_MREMAP: usize = 216;
 pub const SYSCALL_MMAP: usize = 222;
+pub const SYSCALL_BRK: usize = 214;
+pub const SYSCALL_MPROTECT: usize = 226;
+pub const SYSCALL_MADVISE: usize = 233;
 pub const SYSCALL_SPAWN: usize = 400;
 pub const SYSCALL_MAIL_READ: usize = 401;
 pub const SYSCALL_MAIL_WRITE: usize = 402;
 pub const SYSCALL_DUP: usize = 24;
 pub const SYSCALL_PIPE: usize = 59;
 pub const SYSCALL_TASK_INFO: usize = 410;
+pub const SYSCALL_RESET_TASK_INFO: usize = 411;
+pub const SYSCALL_LIST_PROCS: usize = 412;
+pub const SYSCALL_ALARM: usize = 413;
+pub const SYSCALL_TRANSLATE: usize = 414;
+pub const SYSCALL_MEMINFO: usize = 415;
+pub const SYSCALL_VM_DUMP: usize = 416;
+pub const SYSCALL_QUERY_PTE: usize = 417;
+pub const SYSCALL_INJECT_INPUT: usize = 418;
+pub const SYSCALL_SET_TASK_NAME: usize = 419;
+pub const SYSCALL_LIST_APPS: usize = 420;
+pub const SYSCALL_CPU_STATS: usize = 421;
+pub const SYSCALL_YIELD_TO: usize = 422;
+pub const SYSCALL_TASK_SCHED_INFO: usize = 423;
+pub const SYSCALL_FORK2: usize = 424;
+pub const SYSCALL_SCHED_DETERMINISTIC: usize = 425;
+pub const SYSCALL_LAST_FAULT: usize = 426;
+pub const SYSCALL_MMAP_STRICT: usize = 427;
+pub const SYSCALL_UPTIME: usize = 428;
+pub const SYSCALL_GETDTABLESIZE: usize = 429;
+pub const SYSCALL_SCHED_POLICY: usize = 430;
+pub const SYSCALL_NANOSLEEP: usize = 431;
+pub const SYSCALL_PRLIMIT: usize = 432;
+pub const SYSCALL_PROCESS_VM_READ: usize = 433;
+pub const SYSCALL_TRACE_STEP: usize = 434;
+pub const SYSCALL_TASK_STATS: usize = 435;
 pub const SYSCALL_THREAD_CREATE: usize = 460;
 pub const SYSCALL_WAITTID: usize = 462;
 pub const SYSCALL_MUTEX_CREATE: usize = 463;
 pub const SYSCALL_MUTEX_LOCK: usize = 464;
+pub const SYSCALL_MUTEX_DESTROY: usize = 465;
 pub const SYSCALL_MUTEX_UNLOCK: usize = 466;
 pub const SYSCALL_SEMAPHORE_CREATE: usize = 467;
 pub const SYSCALL_SEMAPHORE_UP: usize = 468;
@@ -141,6 +183,10 @@ pub fn sys_mail_write(pid: usize, buffer: &[u8]) -> isize {
     )
 }
 
+pub fn sys_futex(uaddr: *const u32, op: usize, val: u32) -> isize {
+    syscall(SYSCALL_FUTEX, [uaddr as usize, op, val as usize])
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     syscall(SYSCALL_EXIT, [exit_code as usize, 0, 0]);
     panic!("sys_exit never returns!");
@@ -150,26 +196,131 @@ pub fn sys_sleep(sleep_ms: usize) -> isize {
     syscall(SYSCALL_SLEEP, [sleep_ms, 0, 0])
 }
 
+pub fn sys_sched_setaffinity(pid: usize, mask: usize) -> isize {
+    syscall(SYSCALL_SCHED_SETAFFINITY, [pid, mask, 0])
+}
+
+pub fn sys_sched_getaffinity(pid: usize) -> isize {
+    syscall(SYSCALL_SCHED_GETAFFINITY, [pid, 0, 0])
+}
+
 pub fn sys_yield() -> isize {
     syscall(SYSCALL_YIELD, [0, 0, 0])
 }
 
+pub fn sys_yield_to(pid: usize) -> isize {
+    syscall(SYSCALL_YIELD_TO, [pid, 0, 0])
+}
+
 pub fn sys_get_time(time: &TimeVal, tz: usize) -> isize {
     syscall(SYSCALL_GETTIMEOFDAY, [time as *const _ as usize, tz, 0])
 }
 
+pub fn sys_nanosleep(req: &TimeSpec, rem: *mut TimeSpec) -> isize {
+    syscall(
+        SYSCALL_NANOSLEEP,
+        [req as *const TimeSpec as usize, rem as usize, 0],
+    )
+}
+
+pub fn sys_prlimit(resource: usize, new: *const Rlimit, old: *mut Rlimit) -> isize {
+    syscall(SYSCALL_PRLIMIT, [resource, new as usize, old as usize])
+}
+
+pub fn sys_process_vm_read(pid: usize, remote_addr: usize, buf: &mut [u8]) -> isize {
+    syscall6(
+        SYSCALL_PROCESS_VM_READ,
+        [pid, remote_addr, buf.as_mut_ptr() as usize, buf.len(), 0, 0],
+    )
+}
+
+pub fn sys_trace_step(pid: usize) -> isize {
+    syscall(SYSCALL_TRACE_STEP, [pid, 0, 0])
+}
+
+pub fn sys_clock_gettime(clk_id: usize, time: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME, [clk_id, time as *mut _ as usize, 0])
+}
+
+pub fn sys_getrusage(who: isize, usage: &mut Rusage) -> isize {
+    syscall(
+        SYSCALL_GETRUSAGE,
+        [who as usize, usage as *mut _ as usize, 0],
+    )
+}
+
+pub fn sys_times(tms: &mut Tms) -> isize {
+    syscall(SYSCALL_TIMES, [tms as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_kill(pid: usize, signum: i32) -> isize {
+    syscall(SYSCALL_KILL, [pid, signum as usize, 0])
+}
+
+pub fn sys_sigaction(signum: i32, handler: usize, old: *mut usize) -> isize {
+    syscall(SYSCALL_SIGACTION, [signum as usize, handler, old as usize])
+}
+
+pub fn sys_sigprocmask(how: usize, set: *const u64, old: *mut u64) -> isize {
+    syscall(SYSCALL_SIGPROCMASK, [how, set as usize, old as usize])
+}
+
+pub fn sys_sigreturn() -> isize {
+    syscall(SYSCALL_SIGRETURN, [0; 3])
+}
+
+pub fn sys_alarm(seconds: usize) -> isize {
+    syscall(SYSCALL_ALARM, [seconds, 0, 0])
+}
+
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+pub fn sys_getppid() -> isize {
+    syscall(SYSCALL_GETPPID, [0, 0, 0])
+}
+
 pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
 }
 
-pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
+pub fn sys_sched_deterministic(enable: bool) -> isize {
+    syscall(SYSCALL_SCHED_DETERMINISTIC, [enable as usize, 0, 0])
+}
+
+pub fn sys_sched_policy(policy: usize) -> isize {
+    syscall(SYSCALL_SCHED_POLICY, [policy, 0, 0])
+}
+
+pub fn sys_uptime(ticks: &mut u64) -> isize {
+    syscall(SYSCALL_UPTIME, [ticks as *mut u64 as usize, 0, 0])
+}
+
+pub fn sys_getdtablesize() -> isize {
+    syscall(SYSCALL_GETDTABLESIZE, [0, 0, 0])
+}
+
+pub fn sys_mmap_strict(enable: bool) -> isize {
+    syscall(SYSCALL_MMAP_STRICT, [enable as usize, 0, 0])
+}
+
+pub fn sys_last_fault(info: &mut FaultInfo) -> isize {
+    syscall(SYSCALL_LAST_FAULT, [info as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_fork2(child_pid: &mut isize) -> isize {
+    syscall(SYSCALL_FORK2, [child_pid as *mut isize as usize, 0, 0])
+}
+
+pub fn sys_exec(path: &str, args: &[*const u8], envs: &[*const u8]) -> isize {
     syscall(
         SYSCALL_EXEC,
-        [path.as_ptr() as usize, args.as_ptr() as usize, 0],
+        [
+            path.as_ptr() as usize,
+            args.as_ptr() as usize,
+            envs.as_ptr() as usize,
+        ],
     )
 }
 
@@ -177,10 +328,25 @@ pub fn sys_waitpid(pid: isize, xstatus: *mut i32) -> isize {
     syscall(SYSCALL_WAITPID, [pid as usize, xstatus as usize, 0])
 }
 
+pub fn sys_waitpid_options(pid: isize, xstatus: *mut i32, options: usize) -> isize {
+    syscall(SYSCALL_WAITPID, [pid as usize, xstatus as usize, options])
+}
+
 pub fn sys_set_priority(prio: isize) -> isize {
     syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
 }
 
+pub fn sys_getpriority() -> isize {
+    syscall(SYSCALL_GETPRIORITY, [0, 0, 0])
+}
+
+pub fn sys_task_sched_info(pid: isize, info: &mut SchedInfo) -> isize {
+    syscall(
+        SYSCALL_TASK_SCHED_INFO,
+        [pid as usize, info as *mut _ as usize, 0],
+    )
+}
+
 pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
     syscall(SYSCALL_MMAP, [start, len, prot])
 }
@@ -189,8 +355,56 @@ pub fn sys_munmap(start: usize, len: usize) -> isize {
     syscall(SYSCALL_MUNMAP, [start, len, 0])
 }
 
-pub fn sys_spawn(path: &str) -> isize {
-    syscall(SYSCALL_SPAWN, [path.as_ptr() as usize, 0, 0])
+pub fn sys_mremap(old_start: usize, old_len: usize, new_len: usize) -> isize {
+    syscall(SYSCALL_MREMAP, [old_start, old_len, new_len])
+}
+
+pub fn sys_madvise(start: usize, len: usize, advice: usize) -> isize {
+    syscall(SYSCALL_MADVISE, [start, len, advice])
+}
+
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [start, len, prot])
+}
+
+pub fn sys_cpu_stats(stats: &mut CpuStats) -> isize {
+    syscall(SYSCALL_CPU_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_meminfo(info: &mut MemInfo) -> isize {
+    syscall(SYSCALL_MEMINFO, [info as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_vm_dump(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_VM_DUMP, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_query_pte(vaddr: usize, out: &mut PteInfo) -> isize {
+    syscall(SYSCALL_QUERY_PTE, [vaddr, out as *mut _ as usize, 0])
+}
+
+pub fn sys_inject_input(buf: &[u8]) -> isize {
+    syscall(SYSCALL_INJECT_INPUT, [buf.as_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_set_task_name(name: &str) -> isize {
+    syscall(SYSCALL_SET_TASK_NAME, [name.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_list_apps(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_LIST_APPS, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_translate(va: usize) -> isize {
+    syscall(SYSCALL_TRANSLATE, [va, 0, 0])
+}
+
+pub fn sys_brk(new_brk: usize) -> isize {
+    syscall(SYSCALL_BRK, [new_brk, 0, 0])
+}
+
+pub fn sys_spawn(path: &str, flags: usize) -> isize {
+    syscall(SYSCALL_SPAWN, [path.as_ptr() as usize, flags, 0])
 }
 
 pub fn sys_dup(fd: usize) -> isize {
@@ -205,6 +419,21 @@ pub fn sys_task_info(info: &TaskInfo) -> isize {
     syscall(SYSCALL_TASK_INFO, [info as *const _ as usize, 0, 0])
 }
 
+pub fn sys_task_stats(stats: &mut TaskStats) -> isize {
+    syscall(SYSCALL_TASK_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_reset_task_info() -> isize {
+    syscall(SYSCALL_RESET_TASK_INFO, [0, 0, 0])
+}
+
+pub fn sys_list_procs(procs: &mut [ProcEntry]) -> isize {
+    syscall(
+        SYSCALL_LIST_PROCS,
+        [procs.as_mut_ptr() as usize, procs.len(), 0],
+    )
+}
+
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
 }
@@ -229,6 +458,10 @@ pub fn sys_mutex_unlock(id: usize) -> isize {
     syscall(SYSCALL_MUTEX_UNLOCK, [id, 0, 0])
 }
 
+pub fn sys_mutex_destroy(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_DESTROY, [id, 0, 0])
+}
+
 pub fn sys_semaphore_create(res_count: usize) -> isize {
     syscall(SYSCALL_SEMAPHORE_CREATE, [res_count, 0, 0])
 }